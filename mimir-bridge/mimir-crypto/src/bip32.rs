@@ -0,0 +1,388 @@
+//! `bip-32` hierarchical deterministic keys.
+//!
+//! extended keys pair a `secp256k1` secret w/ a chain code, allowing
+//! a tree of keys to be derived from a single seed (see the `bip39`
+//! module for seeds derived from mnemonic phrases).  keys are located
+//! by derivation paths of the form `m/44'/60'/0'/0/0`, where `'`
+//! denotes hardened derivation:
+//!
+//! ```
+//! # extern crate mimir_crypto;
+//! # fn main() {
+//! use mimir_crypto::bip32::{ExtendedKey,DerivationPath};
+//!
+//! let master = ExtendedKey::master(&[0xab;32]).unwrap();
+//!
+//! // `bip-44` path of the first ethereum account
+//! let path: DerivationPath = "m/44'/60'/0'/0/0".parse().unwrap();
+//! assert_eq!(path,DerivationPath::ethereum(0));
+//!
+//! let signer = master.derive(&path).unwrap().signer();
+//! # let _ = signer;
+//! # }
+//! ```
+//!
+use hmac::{Hmac,Mac,NewMac};
+use sha2::{Sha256,Sha512,Digest};
+use ripemd160::Ripemd160;
+use secp256k1::{Signer,Secret};
+use std::str::FromStr;
+use std::fmt;
+
+
+/// offset of hardened child indexes.
+pub const HARDENED: u32 = 1 << 31;
+
+/// version prefix of serialized extended secret keys.
+const XPRV: [u8;4] = [0x04,0x88,0xad,0xe4];
+
+/// version prefix of serialized extended public keys.
+const XPUB: [u8;4] = [0x04,0x88,0xb2,0x1e];
+
+/// hmac key used to derive master keys.
+const MASTER_KEY: &[u8] = b"Bitcoin seed";
+
+/// base58 alphabet.
+const BASE58: &[u8] = b"123456789ABCDEFGHJKLMNPQRSTUVWXYZabcdefghijkmnopqrstuvwxyz";
+
+
+simple_error!(
+    Error,
+    InvalidSeed => "seed must be between 16 and 64 bytes",
+    InvalidKey => "derived key is invalid",
+    InvalidPath => "malformed derivation path",
+    MaxDepth => "maximum derivation depth exceeded",
+);
+
+
+/// path of child indexes from a master key.
+///
+#[derive(Default,Debug,Clone,PartialEq,Eq,Hash)]
+pub struct DerivationPath {
+    indexes: Vec<u32>,
+}
+
+
+impl DerivationPath {
+
+    /// `bip-44` path of the nth ethereum account (`m/44'/60'/0'/0/n`).
+    pub fn ethereum(index: u32) -> Self {
+        let indexes = vec![44 + HARDENED,60 + HARDENED,HARDENED,0,index];
+        DerivationPath { indexes }
+    }
+
+    /// get the child indexes of this path.
+    pub fn indexes(&self) -> &[u32] { &self.indexes }
+}
+
+
+impl FromStr for DerivationPath {
+
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self,Self::Err> {
+        let mut elements = s.trim().split('/');
+        if elements.next() != Some("m") {
+            return Err(Error::InvalidPath);
+        }
+        let mut indexes = Vec::new();
+        for element in elements {
+            let (digits,offset) = match element.trim_right_matches(|c| c == '\'' || c == 'h') {
+                digits if digits.len() + 1 == element.len() => (digits,HARDENED),
+                digits if digits.len() == element.len() => (digits,0),
+                _ => return Err(Error::InvalidPath),
+            };
+            let index: u32 = digits.parse().map_err(|_| Error::InvalidPath)?;
+            if index >= HARDENED {
+                return Err(Error::InvalidPath);
+            }
+            indexes.push(index + offset);
+        }
+        Ok(DerivationPath { indexes })
+    }
+}
+
+
+impl fmt::Display for DerivationPath {
+
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("m")?;
+        for &index in self.indexes.iter() {
+            if index >= HARDENED {
+                write!(f,"/{}'",index - HARDENED)?;
+            } else {
+                write!(f,"/{}",index)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+
+/// extended secret key.
+///
+#[derive(Debug,Clone,PartialEq,Eq)]
+pub struct ExtendedKey {
+    signer: Signer,
+    chain_code: [u8;32],
+    depth: u8,
+    parent: [u8;4],
+    index: u32,
+}
+
+
+impl ExtendedKey {
+
+    /// derive the master key of a seed.
+    pub fn master(seed: &[u8]) -> Result<Self,Error> {
+        if seed.len() < 16 || seed.len() > 64 {
+            return Err(Error::InvalidSeed);
+        }
+        let (secret,chain_code) = hmac_split(MASTER_KEY,&[seed]);
+        let signer = Signer::new(secret).map_err(|_| Error::InvalidKey)?;
+        Ok(ExtendedKey { signer, chain_code, depth: 0, parent: [0u8;4], index: 0 })
+    }
+
+    /// derive the descendant key at the specified path.
+    pub fn derive(&self, path: &DerivationPath) -> Result<Self,Error> {
+        let mut key = self.clone();
+        for &index in path.indexes() {
+            key = key.child(index)?;
+        }
+        Ok(key)
+    }
+
+    /// derive the child key at the specified index (hardened
+    /// if the index is greater or equal to `HARDENED`).
+    pub fn child(&self, index: u32) -> Result<Self,Error> {
+        let depth = self.depth.checked_add(1).ok_or(Error::MaxDepth)?;
+        let (tweak,chain_code) = if index >= HARDENED {
+            let secret = self.signer.secret();
+            hmac_split(&self.chain_code,&[&[0u8],secret.as_ref(),&ser32(index)])
+        } else {
+            hmac_split(&self.chain_code,&[&self.public(),&ser32(index)])
+        };
+        let signer = self.signer.add_tweak(&tweak).map_err(|_| Error::InvalidKey)?;
+        let parent = self.fingerprint();
+        Ok(ExtendedKey { signer, chain_code, depth, parent, index })
+    }
+
+    /// get the signer of this key.
+    pub fn signer(&self) -> Signer { self.signer.clone() }
+
+    /// get the secret of this key.
+    pub fn secret(&self) -> Secret { self.signer.secret() }
+
+    /// get the chain code of this key.
+    pub fn chain_code(&self) -> &[u8;32] { &self.chain_code }
+
+    /// get the depth of this key (the master key has depth 0).
+    pub fn depth(&self) -> u8 { self.depth }
+
+    /// get the compressed (33 byte) public key.
+    pub fn public(&self) -> [u8;33] {
+        let public = self.signer.public();
+        let mut buff = [0u8;33];
+        buff[0] = 0x02 | (public[63] & 1);
+        buff[1..].copy_from_slice(&public[..32]);
+        buff
+    }
+
+    /// get the fingerprint of this key (used to identify parent keys).
+    pub fn fingerprint(&self) -> [u8;4] {
+        let hash = Ripemd160::digest(&Sha256::digest(&self.public()));
+        let mut buff = [0u8;4];
+        buff.copy_from_slice(&hash[..4]);
+        buff
+    }
+
+    /// serialize as a base58 extended secret key (`xprv...`).
+    pub fn xprv(&self) -> String {
+        let secret = self.secret();
+        self.serialize(XPRV,&[&[0u8],secret.as_ref()])
+    }
+
+    /// serialize as a base58 extended public key (`xpub...`).
+    pub fn xpub(&self) -> String {
+        self.serialize(XPUB,&[&self.public()])
+    }
+
+    fn serialize(&self, version: [u8;4], key: &[&[u8]]) -> String {
+        let mut buff = Vec::with_capacity(82);
+        buff.extend_from_slice(&version);
+        buff.push(self.depth);
+        buff.extend_from_slice(&self.parent);
+        buff.extend_from_slice(&ser32(self.index));
+        buff.extend_from_slice(&self.chain_code);
+        for part in key { buff.extend_from_slice(part); }
+        let checksum = Sha256::digest(&Sha256::digest(&buff));
+        buff.extend_from_slice(&checksum[..4]);
+        base58(&buff)
+    }
+}
+
+
+/// compute `hmac-sha512` over the concatenated parts of a message,
+/// splitting the output into left & right halves.
+fn hmac_split(key: &[u8], message: &[&[u8]]) -> (Secret,[u8;32]) {
+    let mut mac = Hmac::<Sha512>::new_varkey(key)
+        .expect("hmac accepts keys of any length");
+    for part in message { mac.update(part); }
+    let output = mac.finalize().into_bytes();
+    let (mut left,mut right) = ([0u8;32],[0u8;32]);
+    left.copy_from_slice(&output[..32]);
+    right.copy_from_slice(&output[32..]);
+    (Secret(left),right)
+}
+
+
+/// serialize an index as big-endian bytes.
+fn ser32(index: u32) -> [u8;4] {
+    [(index >> 24) as u8,(index >> 16) as u8,(index >> 8) as u8,index as u8]
+}
+
+
+/// encode bytes as base58.
+fn base58(bytes: &[u8]) -> String {
+    // base58 digits, least significant first.
+    let mut digits: Vec<u8> = Vec::new();
+    for &byte in bytes {
+        let mut carry = byte as u32;
+        for digit in digits.iter_mut() {
+            carry += (*digit as u32) << 8;
+            *digit = (carry % 58) as u8;
+            carry /= 58;
+        }
+        while carry > 0 {
+            digits.push((carry % 58) as u8);
+            carry /= 58;
+        }
+    }
+    let zeros = bytes.iter().take_while(|&&byte| byte == 0).count();
+    let mut encoded = "1".repeat(zeros);
+    encoded.extend(digits.iter().rev().map(|&digit| BASE58[digit as usize] as char));
+    encoded
+}
+
+
+#[cfg(test)]
+mod tests {
+    use bip32::{ExtendedKey,DerivationPath,Error,HARDENED};
+    use secp256k1::Signer;
+    use mimir_util::hex;
+
+    // vectors from the `bip-32` specification, as `(path,xprv,xpub)`.
+    const VECTOR_1: &[(&str,&str,&str)] = &[
+        (
+            "m",
+            "xprv9s21ZrQH143K3QTDL4LXw2F7HEK3wJUD2nW2nRk4stbPy6cq3jPPqjiChkVvvNKmPGJxWUtg6LnF5kejMRNNU3TGtRBeJgk33yuGBxrMPHi",
+            "xpub661MyMwAqRbcFtXgS5sYJABqqG9YLmC4Q1Rdap9gSE8NqtwybGhePY2gZ29ESFjqJoCu1Rupje8YtGqsefD265TMg7usUDFdp6W1EGMcet8",
+        ),
+        (
+            "m/0'",
+            "xprv9uHRZZhk6KAJC1avXpDAp4MDc3sQKNxDiPvvkX8Br5ngLNv1TxvUxt4cV1rGL5hj6KCesnDYUhd7oWgT11eZG7XnxHrnYeSvkzY7d2bhkJ7",
+            "xpub68Gmy5EdvgibQVfPdqkBBCHxA5htiqg55crXYuXoQRKfDBFA1WEjWgP6LHhwBZeNK1VTsfTFUHCdrfp1bgwQ9xv5ski8PX9rL2dZXvgGDnw",
+        ),
+        (
+            "m/0'/1",
+            "xprv9wTYmMFdV23N2TdNG573QoEsfRrWKQgWeibmLntzniatZvR9BmLnvSxqu53Kw1UmYPxLgboyZQaXwTCg8MSY3H2EU4pWcQDnRnrVA1xe8fs",
+            "xpub6ASuArnXKPbfEwhqN6e3mwBcDTgzisQN1wXN9BJcM47sSikHjJf3UFHKkNAWbWMiGj7Wf5uMash7SyYq527Hqck2AxYysAA7xmALppuCkwQ",
+        ),
+        (
+            "m/0'/1/2'",
+            "xprv9z4pot5VBttmtdRTWfWQmoH1taj2axGVzFqSb8C9xaxKymcFzXBDptWmT7FwuEzG3ryjH4ktypQSAewRiNMjANTtpgP4mLTj34bhnZX7UiM",
+            "xpub6D4BDPcP2GT577Vvch3R8wDkScZWzQzMMUm3PWbmWvVJrZwQY4VUNgqFJPMM3No2dFDFGTsxxpG5uJh7n7epu4trkrX7x7DogT5Uv6fcLW5",
+        ),
+        (
+            "m/0'/1/2'/2",
+            "xprvA2JDeKCSNNZky6uBCviVfJSKyQ1mDYahRjijr5idH2WwLsEd4Hsb2Tyh8RfQMuPh7f7RtyzTtdrbdqqsunu5Mm3wDvUAKRHSC34sJ7in334",
+            "xpub6FHa3pjLCk84BayeJxFW2SP4XRrFd1JYnxeLeU8EqN3vDfZmbqBqaGJAyiLjTAwm6ZLRQUMv1ZACTj37sR62cfN7fe5JnJ7dh8zL4fiyLHV",
+        ),
+        (
+            "m/0'/1/2'/2/1000000000",
+            "xprvA41z7zogVVwxVSgdKUHDy1SKmdb533PjDz7J6N6mV6uS3ze1ai8FHa8kmHScGpWmj4WggLyQjgPie1rFSruoUihUZREPSL39UNdE3BBDu76",
+            "xpub6H1LXWLaKsWFhvm6RVpEL9P4KfRZSW7abD2ttkWP3SSQvnyA8FSVqNTEcYFgJS2UaFcxupHiYkro49S8yGasTvXEYBVPamhGW6cFJodrTHy",
+        ),
+    ];
+
+    const VECTOR_2: &[(&str,&str,&str)] = &[
+        (
+            "m",
+            "xprv9s21ZrQH143K31xYSDQpPDxsXRTUcvj2iNHm5NUtrGiGG5e2DtALGdso3pGz6ssrdK4PFmM8NSpSBHNqPqm55Qn3LqFtT2emdEXVYsCzC2U",
+            "xpub661MyMwAqRbcFW31YEwpkMuc5THy2PSt5bDMsktWQcFF8syAmRUapSCGu8ED9W6oDMSgv6Zz8idoc4a6mr8BDzTJY47LJhkJ8UB7WEGuduB",
+        ),
+        (
+            "m/0",
+            "xprv9vHkqa6EV4sPZHYqZznhT2NPtPCjKuDKGY38FBWLvgaDx45zo9WQRUT3dKYnjwih2yJD9mkrocEZXo1ex8G81dwSM1fwqWpWkeS3v86pgKt",
+            "xpub69H7F5d8KSRgmmdJg2KhpAK8SR3DjMwAdkxj3ZuxV27CprR9LgpeyGmXUbC6wb7ERfvrnKZjXoUmmDznezpbZb7ap6r1D3tgFxHmwMkQTPH",
+        ),
+        (
+            "m/0/2147483647'",
+            "xprv9wSp6B7kry3Vj9m1zSnLvN3xH8RdsPP1Mh7fAaR7aRLcQMKTR2vidYEeEg2mUCTAwCd6vnxVrcjfy2kRgVsFawNzmjuHc2YmYRmagcEPdU9",
+            "xpub6ASAVgeehLbnwdqV6UKMHVzgqAG8Gr6riv3Fxxpj8ksbH9ebxaEyBLZ85ySDhKiLDBrQSARLq1uNRts8RuJiHjaDMBU4Zn9h8LZNnBC5y4a",
+        ),
+        (
+            "m/0/2147483647'/1",
+            "xprv9zFnWC6h2cLgpmSA46vutJzBcfJ8yaJGg8cX1e5StJh45BBciYTRXSd25UEPVuesF9yog62tGAQtHjXajPPdbRCHuWS6T8XA2ECKADdw4Ef",
+            "xpub6DF8uhdarytz3FWdA8TvFSvvAh8dP3283MY7p2V4SeE2wyWmG5mg5EwVvmdMVCQcoNJxGoWaU9DCWh89LojfZ537wTfunKau47EL2dhHKon",
+        ),
+        (
+            "m/0/2147483647'/1/2147483646'",
+            "xprvA1RpRA33e1JQ7ifknakTFpgNXPmW2YvmhqLQYMmrj4xJXXWYpDPS3xz7iAxn8L39njGVyuoseXzU6rcxFLJ8HFsTjSyQbLYnMpCqE2VbFWc",
+            "xpub6ERApfZwUNrhLCkDtcHTcxd75RbzS1ed54G1LkBUHQVHQKqhMkhgbmJbZRkrgZw4koxb5JaHWkY4ALHY2grBGRjaDMzQLcgJvLJuZZvRcEL",
+        ),
+    ];
+
+    fn check_vector(seed_hex: &str, vector: &[(&str,&str,&str)]) {
+        let mut seed = vec![0u8;seed_hex.len() / 2];
+        hex::from(seed_hex.as_bytes(),&mut seed).unwrap();
+        let master = ExtendedKey::master(&seed).unwrap();
+        for &(path,xprv,xpub) in vector {
+            let path: DerivationPath = path.parse().unwrap();
+            let key = master.derive(&path).unwrap();
+            assert_eq!(key.depth() as usize,path.indexes().len());
+            assert_eq!((key.xprv().as_ref(),key.xpub().as_ref()),(xprv,xpub),"path {}",path);
+        }
+    }
+
+    #[test]
+    fn spec_vector_1() {
+        check_vector("000102030405060708090a0b0c0d0e0f",VECTOR_1);
+    }
+
+    #[test]
+    fn spec_vector_2() {
+        check_vector(
+            "fffcf9f6f3f0edeae7e4e1dedbd8d5d2cfccc9c6c3c0bdbab7b4b1aeaba8a5a29f9c999693908d8a8784817e7b7875726f6c696663605d5a5754514e4b484542",
+            VECTOR_2
+        );
+    }
+
+    #[test]
+    fn paths() {
+        let path: DerivationPath = "m/44'/60'/0'/0/7".parse().unwrap();
+        assert_eq!(path,DerivationPath::ethereum(7));
+        assert_eq!(path.to_string(),"m/44'/60'/0'/0/7");
+        let alt: DerivationPath = "m/44h/60h/0h/0/7".parse().unwrap();
+        assert_eq!(alt,path);
+        let master: DerivationPath = "m".parse().unwrap();
+        assert!(master.indexes().is_empty());
+        for bad in &["","44'/60'","m/","m/x","m/1''","m/2147483648"] {
+            assert_eq!(bad.parse::<DerivationPath>(),Err(Error::InvalidPath),"{:?}",bad);
+        }
+    }
+
+    #[test]
+    fn child_signers() {
+        let master = ExtendedKey::master(&[7u8;32]).unwrap();
+        let first = master.derive(&DerivationPath::ethereum(0)).unwrap();
+        let second = master.derive(&DerivationPath::ethereum(1)).unwrap();
+        assert!(first.signer().address() != second.signer().address());
+        assert_eq!(Signer::new(first.secret()).unwrap(),first.signer());
+        // paths are relative to the key from which they are derived.
+        let account = master.child(44 + HARDENED).and_then(|key| key.child(60 + HARDENED)).unwrap();
+        let rest: DerivationPath = "m/0'/0/0".parse().unwrap();
+        assert_eq!(account.derive(&rest).unwrap(),first);
+        assert_eq!(ExtendedKey::master(&[0u8;8]),Err(Error::InvalidSeed));
+    }
+}
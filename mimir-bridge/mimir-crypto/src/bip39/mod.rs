@@ -0,0 +1,283 @@
+//! `bip-39` mnemonic phrases.
+//!
+//! mnemonics encode between 128 and 256 bits of entropy (plus a short
+//! checksum) as a phrase of 12 to 24 words from the english wordlist.
+//! a phrase (and optional passphrase) is stretched into a 64 byte seed,
+//! from which keys may be derived via `bip-32` (see the `bip32` module):
+//!
+//! ```
+//! # extern crate mimir_crypto;
+//! # fn main() {
+//! use mimir_crypto::bip39::Mnemonic;
+//! use mimir_crypto::bip32::DerivationPath;
+//!
+//! // generate a new 24 word mnemonic
+//! let mnemonic = Mnemonic::generate(24).unwrap();
+//!
+//! // phrases are validated when parsed
+//! let parsed: Mnemonic = mnemonic.phrase().parse().unwrap();
+//! assert_eq!(parsed,mnemonic);
+//!
+//! // derive the first account at `m/44'/60'/0'/0/0`
+//! let path = DerivationPath::ethereum(0);
+//! let signer = mnemonic.signer("",&path).unwrap();
+//! # let _ = signer;
+//! # }
+//! ```
+//!
+use unicode_normalization::UnicodeNormalization;
+use hmac::Hmac;
+use sha2::{Sha256,Sha512,Digest};
+use bip32::{self,DerivationPath,ExtendedKey};
+use secp256k1::Signer;
+use std::str::FromStr;
+use std::fmt;
+use pbkdf2;
+use rand;
+
+
+lazy_static! {
+    /// english wordlist (sorted).
+    static ref WORDS: Vec<&'static str> = include_str!("english.txt").lines().collect();
+}
+
+
+/// valid mnemonic lengths (in words).
+const WORD_COUNTS: [usize;5] = [12,15,18,21,24];
+
+
+/// valid entropy lengths (in bytes).
+const ENTROPY_LENS: [usize;5] = [16,20,24,28,32];
+
+
+/// number of pbkdf2 rounds used to derive seeds.
+const SEED_ROUNDS: u32 = 2048;
+
+
+simple_error!(
+    Error,
+    InvalidLength => "invalid mnemonic or entropy length",
+    UnknownWord => "mnemonic contains unknown word",
+    InvalidChecksum => "invalid mnemonic checksum",
+);
+
+
+/// a validated `bip-39` mnemonic phrase.
+///
+#[derive(Debug,Clone,PartialEq,Eq)]
+pub struct Mnemonic {
+    entropy: Vec<u8>,
+    phrase: String,
+}
+
+
+impl Mnemonic {
+
+    /// generate a new random mnemonic w/ the specified number
+    /// of words (one of 12, 15, 18, 21 or 24).
+    pub fn generate(words: usize) -> Result<Self,Error> {
+        if !WORD_COUNTS.contains(&words) {
+            return Err(Error::InvalidLength);
+        }
+        let entropy: [u8;32] = rand::random();
+        Self::from_entropy(&entropy[..words / 3 * 4])
+    }
+
+    /// build the mnemonic which encodes the supplied entropy
+    /// (16 to 32 bytes, in multiples of 4).
+    pub fn from_entropy(entropy: &[u8]) -> Result<Self,Error> {
+        if !ENTROPY_LENS.contains(&entropy.len()) {
+            return Err(Error::InvalidLength);
+        }
+        let checksum = Sha256::digest(entropy);
+        let mut bits = Bits::default();
+        bits.extend(entropy,entropy.len() * 8);
+        let checksum_len = entropy.len() / 4;
+        bits.extend(&[checksum[0] >> (8 - checksum_len)],checksum_len);
+        let words: Vec<&str> = bits.chunks(11).into_iter().map(|index| WORDS[index as usize]).collect();
+        let phrase = words.join(" ");
+        Ok(Mnemonic { entropy: entropy.to_vec(), phrase })
+    }
+
+    /// parse & validate a mnemonic phrase.
+    pub fn from_phrase(phrase: &str) -> Result<Self,Error> {
+        let normalized: String = phrase.nfkd().collect();
+        let words: Vec<&str> = normalized.split_whitespace().collect();
+        if !WORD_COUNTS.contains(&words.len()) {
+            return Err(Error::InvalidLength);
+        }
+        let mut bits = Bits::default();
+        for word in words.iter() {
+            let index = WORDS.binary_search(word).map_err(|_| Error::UnknownWord)?;
+            bits.extend(&[(index >> 8) as u8,index as u8],11);
+        }
+        let entropy: Vec<u8> = bits.chunks(8).into_iter().take(words.len() * 4 / 3).map(|byte| byte as u8).collect();
+        let mnemonic = Self::from_entropy(&entropy)?;
+        if mnemonic.phrase == words.join(" ") {
+            Ok(mnemonic)
+        } else {
+            Err(Error::InvalidChecksum)
+        }
+    }
+
+    /// get the (normalized) mnemonic phrase.
+    pub fn phrase(&self) -> &str { &self.phrase }
+
+    /// get the entropy encoded by this mnemonic.
+    pub fn entropy(&self) -> &[u8] { &self.entropy }
+
+    /// derive the 64 byte seed for this mnemonic & passphrase
+    /// (use an empty passphrase if none was specified).
+    pub fn seed(&self, passphrase: &str) -> [u8;64] {
+        let salt: String = "mnemonic".chars().chain(passphrase.nfkd()).collect();
+        let mut seed = [0u8;64];
+        pbkdf2::pbkdf2::<Hmac<Sha512>>(self.phrase.as_ref(),salt.as_ref(),SEED_ROUNDS,&mut seed);
+        seed
+    }
+
+    /// derive the signer at the specified path.
+    pub fn signer(&self, passphrase: &str, path: &DerivationPath) -> Result<Signer,bip32::Error> {
+        let master = ExtendedKey::master(&self.seed(passphrase))?;
+        let child = master.derive(path)?;
+        Ok(child.signer())
+    }
+}
+
+
+impl FromStr for Mnemonic {
+
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self,Self::Err> { Self::from_phrase(s) }
+}
+
+
+impl fmt::Display for Mnemonic {
+
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result { f.write_str(&self.phrase) }
+}
+
+
+/// big-endian bit buffer.
+#[derive(Default)]
+struct Bits {
+    inner: Vec<bool>,
+}
+
+
+impl Bits {
+
+    /// append the trailing `count` bits of `bytes`.
+    fn extend(&mut self, bytes: &[u8], count: usize) {
+        let skip = bytes.len() * 8 - count;
+        let bits = bytes.iter().flat_map(|byte| (0..8).rev().map(move |bit| (byte >> bit) & 1 == 1));
+        self.inner.extend(bits.skip(skip));
+    }
+
+    /// split the buffer into integers of `size` bits each.
+    fn chunks(&self, size: usize) -> Vec<u32> {
+        self.inner.chunks(size).map(|chunk| chunk.iter().fold(0,|acc,&bit| (acc << 1) | bit as u32)).collect()
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use bip39::{Mnemonic,Error,WORDS};
+    use bip32::ExtendedKey;
+    use mimir_util::hex;
+
+    // vectors from the reference implementation (https://github.com/trezor/python-mnemonic),
+    // all of which use the passphrase "TREZOR".
+    const VECTORS: &[(&str,&str,&str,&str)] = &[
+        (
+            "00000000000000000000000000000000",
+            "abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon about",
+            "c55257c360c07c72029aebc1b53c05ed0362ada38ead3e3e9efa3708e53495531f09a6987599d18264c1e1c92f2cf141630c7a3c4ab7c81b2f001698e7463b04",
+            "xprv9s21ZrQH143K3h3fDYiay8mocZ3afhfULfb5GX8kCBdno77K4HiA15Tg23wpbeF1pLfs1c5SPmYHrEpTuuRhxMwvKDwqdKiGJS9XFKzUsAF",
+        ),
+        (
+            "7f7f7f7f7f7f7f7f7f7f7f7f7f7f7f7f",
+            "legal winner thank year wave sausage worth useful legal winner thank yellow",
+            "2e8905819b8723fe2c1d161860e5ee1830318dbf49a83bd451cfb8440c28bd6fa457fe1296106559a3c80937a1c1069be3a3a5bd381ee6260e8d9739fce1f607",
+            "xprv9s21ZrQH143K2gA81bYFHqU68xz1cX2APaSq5tt6MFSLeXnCKV1RVUJt9FWNTbrrryem4ZckN8k4Ls1H6nwdvDTvnV7zEXs2HgPezuVccsq",
+        ),
+        (
+            "ffffffffffffffffffffffffffffffff",
+            "zoo zoo zoo zoo zoo zoo zoo zoo zoo zoo zoo wrong",
+            "ac27495480225222079d7be181583751e86f571027b0497b5b5d11218e0a8a13332572917f0f8e5a589620c6f15b11c61dee327651a14c34e18231052e48c069",
+            "xprv9s21ZrQH143K2V4oox4M8Zmhi2Fjx5XK4Lf7GKRvPSgydU3mjZuKGCTg7UPiBUD7ydVPvSLtg9hjp7MQTYsW67rZHAXeccqYqrsx8LcXnyd",
+        ),
+        (
+            "000000000000000000000000000000000000000000000000",
+            "abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon agent",
+            "035895f2f481b1b0f01fcf8c289c794660b289981a78f8106447707fdd9666ca06da5a9a565181599b79f53b844d8a71dd9f439c52a3d7b3e8a79c906ac845fa",
+            "xprv9s21ZrQH143K3mEDrypcZ2usWqFgzKB6jBBx9B6GfC7fu26X6hPRzVjzkqkPvDqp6g5eypdk6cyhGnBngbjeHTe4LsuLG1cCmKJka5SMkmU",
+        ),
+        (
+            "8080808080808080808080808080808080808080808080808080808080808080",
+            "letter advice cage absurd amount doctor acoustic avoid letter advice cage absurd amount doctor acoustic avoid letter advice cage absurd amount doctor acoustic bless",
+            "c0c519bd0e91a2ed54357d9d1ebef6f5af218a153624cf4f2da911a0ed8f7a09e2ef61af0aca007096df430022f7a2b6fb91661a9589097069720d015e4e982f",
+            "xprv9s21ZrQH143K3CSnQNYC3MqAAqHwxeTLhDbhF43A4ss4ciWNmCY9zQGvAKUSqVUf2vPHBTSE1rB2pg4avopqSiLVzXEU8KziNnVPauTqLRo",
+        ),
+        (
+            "9e885d952ad362caeb4efe34a8e91bd2",
+            "ozone drill grab fiber curtain grace pudding thank cruise elder eight picnic",
+            "274ddc525802f7c828d8ef7ddbcdc5304e87ac3535913611fbbfa986d0c9e5476c91689f9c8a54fd55bd38606aa6a8595ad213d4c9c9f9aca3fb217069a41028",
+            "xprv9s21ZrQH143K2oZ9stBYpoaZ2ktHj7jLz7iMqpgg1En8kKFTXJHsjxry1JbKH19YrDTicVwKPehFKTbmaxgVEc5TpHdS1aYhB2s9aFJBeJH",
+        ),
+    ];
+
+    fn unhex(hex_str: &str) -> Vec<u8> {
+        let mut buff = vec![0u8;hex_str.len() / 2];
+        hex::from(hex_str.as_bytes(),&mut buff).unwrap();
+        buff
+    }
+
+    #[test]
+    fn wordlist() {
+        assert_eq!(WORDS.len(),2048);
+        assert!(WORDS.windows(2).all(|pair| pair[0] < pair[1]));
+    }
+
+    #[test]
+    fn reference_vectors() {
+        for &(entropy,phrase,seed,xprv) in VECTORS {
+            let mnemonic = Mnemonic::from_entropy(&unhex(entropy)).unwrap();
+            assert_eq!(mnemonic.phrase(),phrase);
+            let parsed: Mnemonic = phrase.parse().unwrap();
+            assert_eq!(parsed.entropy(),&unhex(entropy)[..]);
+            let derived = parsed.seed("TREZOR");
+            assert_eq!(&derived[..],&unhex(seed)[..]);
+            assert_eq!(ExtendedKey::master(&derived).unwrap().xprv(),xprv);
+        }
+    }
+
+    #[test]
+    fn generation() {
+        for &words in &[12,15,18,21,24] {
+            let mnemonic = Mnemonic::generate(words).unwrap();
+            assert_eq!(mnemonic.phrase().split(' ').count(),words);
+            assert_eq!(mnemonic.entropy().len(),words / 3 * 4);
+            assert_eq!(Mnemonic::from_phrase(mnemonic.phrase()).unwrap(),mnemonic);
+        }
+        for &words in &[0,11,13,27] {
+            assert_eq!(Mnemonic::generate(words),Err(Error::InvalidLength));
+        }
+    }
+
+    #[test]
+    fn validation() {
+        let (_,phrase,_,_) = VECTORS[0];
+        // extra whitespace is tolerated.
+        let spaced = format!("  {}\n",phrase.replace(" ","   "));
+        assert_eq!(Mnemonic::from_phrase(&spaced).unwrap().phrase(),phrase);
+        let bad_checksum = phrase.replace("about","above");
+        assert_eq!(Mnemonic::from_phrase(&bad_checksum),Err(Error::InvalidChecksum));
+        let unknown = phrase.replace("about","aboot");
+        assert_eq!(Mnemonic::from_phrase(&unknown),Err(Error::UnknownWord));
+        let truncated = phrase.replacen("abandon ","",1);
+        assert_eq!(Mnemonic::from_phrase(&truncated),Err(Error::InvalidLength));
+        assert_eq!(Mnemonic::from_entropy(&[0u8;15]),Err(Error::InvalidLength));
+    }
+}
@@ -0,0 +1,63 @@
+//! `eip-191` signed data hashing.
+//!
+//! wallets implementing `personal_sign` sign the keccak-256 hash of
+//! `"\x19Ethereum Signed Message:\n" ‖ len(message) ‖ message`, where
+//! `len` is the decimal length of the message in bytes.  such signatures
+//! are recovered via `ecrecover` against the `personal_hash` of the
+//! original message:
+//!
+//!```
+//! extern crate mimir_crypto;
+//! extern crate rand;
+//!# fn main() {
+//! use mimir_crypto::secp256k1::{ecrecover,Signer};
+//! use mimir_crypto::eip191::personal_hash;
+//!
+//! let signer: Signer = rand::random();
+//!
+//! // equivalent to `personal_sign("hello world")`
+//! let signature = signer.sign(&personal_hash(b"hello world"));
+//!
+//! let address = ecrecover(&personal_hash(b"hello world"),&signature).unwrap();
+//!
+//! assert_eq!(address,signer.address());
+//!# }
+//!```
+//!
+use keccak256::Keccak256;
+
+
+/// prefix of `personal_sign` messages (version byte `0x45`).
+pub const PERSONAL_PREFIX: &[u8] = b"\x19Ethereum Signed Message:\n";
+
+
+/// hash a message as per `personal_sign`.
+pub fn personal_hash(message: &[u8]) -> [u8;32] {
+    let mut hasher = Keccak256::default();
+    hasher.absorb(PERSONAL_PREFIX);
+    hasher.absorb(message.len().to_string().as_bytes());
+    hasher.absorb(message);
+    hasher.finish()
+}
+
+
+#[cfg(test)]
+mod tests {
+    use secp256k1::{ecrecover,Signer};
+    use eip191::personal_hash;
+    use rand;
+
+    #[test]
+    fn personal() {
+        let expected = [
+            0xd9, 0xeb, 0xa1, 0x6e, 0xd0, 0xec, 0xae, 0x43, 0x2b, 0x71, 0xfe, 0x00, 0x8c, 0x98, 0xcc, 0x87,
+            0x2b, 0xb4, 0xcc, 0x21, 0x4d, 0x32, 0x20, 0xa3, 0x6f, 0x36, 0x53, 0x26, 0xcf, 0x80, 0x7d, 0x68,
+        ];
+        assert_eq!(personal_hash(b"hello world"),expected);
+        let signer: Signer = rand::random();
+        let hash: [u8;32] = rand::random();
+        let signature = signer.sign(&personal_hash(&hash));
+        assert_eq!(ecrecover(&personal_hash(&hash),&signature).unwrap(),signer.address());
+        assert!(ecrecover(&hash,&signature).unwrap() != signer.address());
+    }
+}
@@ -0,0 +1,211 @@
+//! `eip-712` typed structured data hashing.
+//!
+//! wallets implementing `eth_signTypedData` sign the keccak-256 hash of
+//! `0x19 ‖ 0x01 ‖ domainSeparator ‖ hashStruct(message)`.  a `StructHasher`
+//! produces `hashStruct` by encoding the members of a struct in the order
+//! in which they appear in its encoded type, and a `TypedDomain` produces
+//! the domain separator:
+//!
+//!```
+//! extern crate mimir_crypto;
+//!# fn main() {
+//! use mimir_crypto::eip712::{StructHasher,TypedDomain};
+//!
+//! let domain = TypedDomain::new("example","1",1,Default::default());
+//!
+//! // hash a `Greeting(string text,uint256 count)` struct
+//! let greeting = StructHasher::new("Greeting(string text,uint256 count)")
+//!     .string("hello world")
+//!     .uint(3)
+//!     .finish();
+//!
+//! // get the hash signed by `eth_signTypedData`
+//! let hash = domain.hash(&greeting);
+//!
+//! assert!(hash != greeting);
+//!# }
+//!```
+//!
+use keccak256::Keccak256;
+use secp256k1::Address;
+
+
+/// encoded type of `EIP712Domain` (as used by `TypedDomain`).
+pub const DOMAIN_TYPE: &str = "EIP712Domain(string name,string version,uint256 chainId,address verifyingContract)";
+
+
+/// `eip-712` signing domain.
+#[derive(Debug,Clone,PartialEq,Eq)]
+pub struct TypedDomain {
+    /// user readable name of the signing domain
+    pub name: String,
+
+    /// current major version of the signing domain
+    pub version: String,
+
+    /// id of the chain (as a big-endian word)
+    pub chain_id: [u8;32],
+
+    /// address of the contract which verifies signatures
+    pub verifying_contract: Address,
+}
+
+
+impl TypedDomain {
+
+    /// build a domain with a numeric chain id.
+    pub fn new(name: &str, version: &str, chain_id: u64, verifying_contract: Address) -> Self {
+        let (name,version) = (name.to_owned(),version.to_owned());
+        let chain_id = uint_word(chain_id);
+        TypedDomain { name, version, chain_id, verifying_contract }
+    }
+
+    /// get the domain separator.
+    pub fn separator(&self) -> [u8;32] {
+        StructHasher::new(DOMAIN_TYPE)
+            .string(&self.name)
+            .string(&self.version)
+            .word(&self.chain_id)
+            .address(&self.verifying_contract)
+            .finish()
+    }
+
+    /// get the signing hash of a struct (as produced by `StructHasher`).
+    pub fn hash(&self, struct_hash: &[u8;32]) -> [u8;32] {
+        let mut hasher = Keccak256::default();
+        hasher.absorb(&[0x19,0x01]);
+        hasher.absorb(&self.separator());
+        hasher.absorb(struct_hash);
+        hasher.finish()
+    }
+}
+
+
+/// incremental `hashStruct` encoder.
+///
+/// members must be supplied in order of declaration.  members which
+/// are themselves structs are encoded as the `word` of their own
+/// `hashStruct`, and their types must be appended to the encoded type
+/// of the parent (see `eip-712`).
+///
+#[derive(Clone)]
+pub struct StructHasher {
+    inner: Keccak256,
+}
+
+
+impl StructHasher {
+
+    /// begin encoding a struct of the supplied encoded type.
+    pub fn new(encoded_type: &str) -> Self {
+        let mut inner = Keccak256::default();
+        inner.absorb(&Keccak256::hash(encoded_type.as_bytes()));
+        StructHasher { inner }
+    }
+
+    /// encode an atomic 32 byte value (e.g. `uint256` or `bytes32`).
+    pub fn word(mut self, word: &[u8;32]) -> Self {
+        self.inner.absorb(word);
+        self
+    }
+
+    /// encode an unsigned integer.
+    pub fn uint(self, value: u64) -> Self {
+        self.word(&uint_word(value))
+    }
+
+    /// encode a boolean.
+    pub fn boolean(self, value: bool) -> Self {
+        self.uint(value as u64)
+    }
+
+    /// encode an address.
+    pub fn address(self, address: &Address) -> Self {
+        let mut word = [0u8;32];
+        word[12..].copy_from_slice(address.as_ref());
+        self.word(&word)
+    }
+
+    /// encode a dynamic `string`.
+    pub fn string(self, value: &str) -> Self {
+        self.bytes(value.as_bytes())
+    }
+
+    /// encode a dynamic `bytes`.
+    pub fn bytes(self, value: &[u8]) -> Self {
+        self.word(&Keccak256::hash(value))
+    }
+
+    /// consume encoder, returning `hashStruct`.
+    pub fn finish(self) -> [u8;32] {
+        self.inner.finish()
+    }
+}
+
+
+fn uint_word(value: u64) -> [u8;32] {
+    let mut word = [0u8;32];
+    for (i,byte) in word[24..].iter_mut().enumerate() {
+        *byte = (value >> (8 * (7 - i))) as u8;
+    }
+    word
+}
+
+
+#[cfg(test)]
+mod tests {
+    use secp256k1::{ecrecover,Signature,Address};
+    use eip712::{StructHasher,TypedDomain};
+    use mimir_util::hex;
+
+    const MAIL_TYPE: &str = "Mail(Person from,Person to,string contents)Person(string name,address wallet)";
+    const PERSON_TYPE: &str = "Person(string name,address wallet)";
+
+    fn word(hex_str: &str) -> [u8;32] {
+        let mut buff = [0u8;32];
+        hex::from(hex_str.as_bytes(),&mut buff).unwrap();
+        buff
+    }
+
+    fn person(name: &str, wallet: &str) -> [u8;32] {
+        let wallet: Address = wallet.parse().unwrap();
+        StructHasher::new(PERSON_TYPE).string(name).address(&wallet).finish()
+    }
+
+    // example from the `eip-712` specification.
+    #[test]
+    fn spec_vector() {
+        let contract: Address = "0xcccccccccccccccccccccccccccccccccccccccc".parse().unwrap();
+        let domain = TypedDomain::new("Ether Mail","1",1,contract);
+        let mail = StructHasher::new(MAIL_TYPE)
+            .word(&person("Cow","0xcd2a3d9f938e13cd947ec05abc7fe734df8dd826"))
+            .word(&person("Bob","0xbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbb"))
+            .string("Hello, Bob!")
+            .finish();
+        assert_eq!(domain.separator(),word("f2cee375fa42b42143804025fc449deafd50cc031ca257e0b194a650a912090f"));
+        assert_eq!(mail,word("c52c0ee5d84264471806290a3f2c4cecfc5490626bf912d01f240d7a274b371e"));
+        let hash = domain.hash(&mail);
+        assert_eq!(hash,word("be609aee343fb3c4b28e1df9e632fca64fcfaede20f02e86244efddf30957bd2"));
+        let signature: Signature = concat!(
+            "0x4355c47d63924e8a72e509b65029052eb6c299d53a04e167c5775fd466751c9d",
+            "07299936d304c153f6443dfa05f40ff007d72911b6f72307f996231605b915621c"
+        ).parse().unwrap();
+        assert_eq!(ecrecover(&hash,&signature).unwrap(),"0xcd2a3d9f938e13cd947ec05abc7fe734df8dd826".parse().unwrap());
+    }
+
+    #[test]
+    fn atomic_members() {
+        let expect = StructHasher::new("Flags(bool a,uint256 b)")
+            .word(&word("0000000000000000000000000000000000000000000000000000000000000001"))
+            .word(&word("0000000000000000000000000000000000000000000000000000000000000102"))
+            .finish();
+        let actual = StructHasher::new("Flags(bool a,uint256 b)")
+            .boolean(true)
+            .uint(0x0102)
+            .finish();
+        assert_eq!(actual,expect);
+        let domain = TypedDomain::new("example","1",1,Default::default());
+        assert!(domain.separator() != TypedDomain::new("example","2",1,Default::default()).separator());
+        assert!(domain.separator() != TypedDomain::new("example","1",3,Default::default()).separator());
+    }
+}
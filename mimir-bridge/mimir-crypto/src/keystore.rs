@@ -0,0 +1,540 @@
+//! ethereum v3 json keystore (aka "web3 secret storage").
+//!
+//! keyfiles hold a single secret, encrypted w/ `aes-128-ctr` under
+//! a key derived from a passphrase via either `scrypt` or `pbkdf2`.
+//! the format is compatible w/ the keyfiles produced by `geth`,
+//! `parity`, and most other ethereum wallets:
+//!
+//! ```
+//! # extern crate mimir_crypto;
+//! # fn main() {
+//! use mimir_crypto::keystore::{KeyFile,Kdf};
+//! use mimir_crypto::secp256k1::dev;
+//!
+//! // encrypt a secret under a passphrase
+//! let keyfile = KeyFile::encrypt(&dev::SECRET,b"hunter2",Kdf::scrypt_light()).unwrap();
+//! assert_eq!(keyfile.address,Some(dev::ADDRESS));
+//!
+//! // round-trip through the json representation
+//! let parsed: KeyFile = keyfile.to_json().parse().unwrap();
+//!
+//! // recover the secret
+//! assert_eq!(parsed.decrypt(b"hunter2").unwrap(),dev::SECRET);
+//! assert!(parsed.decrypt(b"hunter3").is_err());
+//! # }
+//! ```
+//!
+use aes_ctr::Aes128Ctr;
+use aes_ctr::cipher::stream::{NewStreamCipher,SyncStreamCipher};
+use hmac::Hmac;
+use sha2::Sha256;
+use mimir_util::{hex,create_private};
+use mimir_util::types::Either;
+use secp256k1::{Signer,Secret,Address};
+use keccak256::Keccak256;
+use serde::{Serializer,Deserializer,Deserialize};
+use serde::de::Error as DeError;
+use std::io::{Read,Write};
+use std::fs::OpenOptions;
+use std::path::Path;
+use std::str::FromStr;
+use serde_json;
+use scrypt;
+use pbkdf2;
+use rand;
+
+
+/// keyfile format version produced & accepted by this module.
+pub const VERSION: u8 = 3;
+
+
+// upper bounds on the kdf parameters of untrusted keyfiles.  scrypt
+// memory use is `128 * r * n` bytes (1GiB at the bounds), & both kdfs
+// run well within the bounds w/ the parameters used by `geth`.
+const MAX_DKLEN: u32 = 64;
+const MAX_SCRYPT_N: u32 = 1 << 20;
+const MAX_SCRYPT_R: u32 = 8;
+const MAX_SCRYPT_P: u32 = 16;
+const MAX_PBKDF2_C: u32 = 1 << 22;
+
+
+simple_error!(
+    Error,
+    UnsupportedVersion => "unsupported keyfile version",
+    InvalidParams => "invalid key derivation parameters",
+    InvalidLength => "invalid length of iv or ciphertext",
+    MacMismatch => "invalid passphrase or corrupt keyfile",
+    InvalidSecret => "malformed secret key",
+    AddressMismatch => "decrypted secret does not match address",
+    NoSuchFile => "specified file does not exist",
+    OpenFile => "unable to open file",
+    ReadFile => "unable to read from file",
+    WriteFile => "unable to write to file",
+    Deserialize => "unable to deserialize as keyfile",
+);
+
+
+simple_unit!(
+    Cipher,
+    "symmetric cipher used to encrypt secrets",
+    Aes128Ctr => "aes-128-ctr"
+);
+
+
+simple_unit!(
+    Prf,
+    "pseudo-random function used by `pbkdf2`",
+    HmacSha256 => "hmac-sha256"
+);
+
+
+/// ethereum v3 json keyfile.
+///
+#[derive(Debug,Clone,PartialEq,Eq,Serialize,Deserialize)]
+pub struct KeyFile {
+    /// address of the encrypted secret (optional in the v3 spec).
+    #[serde(default,skip_serializing_if = "Option::is_none",with = "bare_address")]
+    pub address: Option<Address>,
+
+    /// encrypted secret & associated parameters.
+    #[serde(alias = "Crypto")]
+    pub crypto: Crypto,
+
+    /// keyfile identifier (a random uuid).
+    pub id: String,
+
+    /// keyfile format version.
+    pub version: u8,
+}
+
+
+impl KeyFile {
+
+    /// encrypt a secret under the supplied passphrase.
+    pub fn encrypt(secret: &Secret, passphrase: &[u8], kdf: Kdf) -> Result<Self,Error> {
+        let signer = Signer::new(secret).map_err(|_| Error::InvalidSecret)?;
+        let derived = kdf.derive(passphrase)?;
+        let iv: [u8;16] = rand::random();
+        let mut ciphertext = secret.as_ref().to_vec();
+        apply_cipher(&derived,&iv,&mut ciphertext)?;
+        let mac = mac(&derived,&ciphertext).to_vec();
+        let cipherparams = CipherParams { iv: iv.to_vec() };
+        let crypto = Crypto { cipher: Cipher::Aes128Ctr, cipherparams, ciphertext, kdf, mac };
+        let address = Some(signer.address());
+        Ok(KeyFile { address, crypto, id: uuid(), version: VERSION })
+    }
+
+    /// decrypt the secret held by this keyfile.
+    pub fn decrypt(&self, passphrase: &[u8]) -> Result<Secret,Error> {
+        if self.version != VERSION { return Err(Error::UnsupportedVersion); }
+        let &Crypto { ref cipherparams, ref ciphertext, ref kdf, mac: ref expected, .. } = &self.crypto;
+        if ciphertext.len() != 32 { return Err(Error::InvalidLength); }
+        let derived = kdf.derive(passphrase)?;
+        if !constant_eq(&mac(&derived,ciphertext),expected) {
+            return Err(Error::MacMismatch);
+        }
+        let mut buff = [0u8;32];
+        buff.copy_from_slice(ciphertext);
+        apply_cipher(&derived,&cipherparams.iv,&mut buff)?;
+        let secret = Secret(buff);
+        let signer = Signer::new(secret).map_err(|_| Error::InvalidSecret)?;
+        match self.address {
+            Some(address) if address != signer.address() => Err(Error::AddressMismatch),
+            _ => Ok(secret),
+        }
+    }
+
+    /// decrypt the secret held by this keyfile & build a signer from it.
+    pub fn signer(&self, passphrase: &[u8]) -> Result<Signer,Error> {
+        let secret = self.decrypt(passphrase)?;
+        Signer::new(secret).map_err(|_| Error::InvalidSecret)
+    }
+
+    /// get the key derivation function of this keyfile.
+    pub fn kdf(&self) -> &Kdf { &self.crypto.kdf }
+
+    /// serialize keyfile as json.
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self)
+            .expect("keyfile always serializable")
+    }
+
+    /// attempt to load keyfile from a json file.
+    pub fn load<P>(path: P) -> Result<Self,Error> where P: AsRef<Path> {
+        let path: &Path = path.as_ref();
+        if path.is_file() {
+            let mut file = OpenOptions::new()
+                .read(true)
+                .open(path)
+                .map_err(|_| Error::OpenFile)?;
+            let mut buff = String::new();
+            file.read_to_string(&mut buff)
+                .map_err(|_| Error::ReadFile)?;
+            buff.parse()
+        } else {
+            Err(Error::NoSuchFile)
+        }
+    }
+
+    /// attempt to save keyfile to a json file (only accessible
+    /// to its owner, see `mimir_util::create_private`).
+    pub fn save<P>(&self, path: P) -> Result<(),Error> where P: AsRef<Path> {
+        let mut file = create_private(path)
+            .map_err(|_| Error::OpenFile)?;
+        file.write_all(self.to_json().as_ref())
+            .map_err(|_| Error::WriteFile)
+    }
+}
+
+
+impl FromStr for KeyFile {
+
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self,Self::Err> {
+        serde_json::from_str(s).map_err(|_| Error::Deserialize)
+    }
+}
+
+
+/// encrypted secret & associated parameters.
+///
+#[derive(Debug,Clone,PartialEq,Eq,Serialize,Deserialize)]
+pub struct Crypto {
+    cipher: Cipher,
+    cipherparams: CipherParams,
+    #[serde(with = "bare_hex")]
+    ciphertext: Vec<u8>,
+    #[serde(flatten)]
+    kdf: Kdf,
+    #[serde(with = "bare_hex")]
+    mac: Vec<u8>,
+}
+
+
+#[derive(Debug,Clone,PartialEq,Eq,Serialize,Deserialize)]
+struct CipherParams {
+    #[serde(with = "bare_hex")]
+    iv: Vec<u8>,
+}
+
+
+/// key derivation function & associated parameters.
+///
+#[derive(Debug,Clone,PartialEq,Eq,Serialize,Deserialize)]
+#[serde(tag = "kdf", content = "kdfparams", rename_all = "lowercase")]
+pub enum Kdf {
+    /// `scrypt` w/ cost `n`, block size `r`, and parallelism `p`.
+    Scrypt {
+        /// derived key length
+        dklen: u32,
+        /// cpu/memory cost (a power of two, less than `2^(16 * r)`)
+        n: u32,
+        /// block size
+        r: u32,
+        /// parallelization
+        p: u32,
+        /// random salt
+        #[serde(with = "bare_hex")]
+        salt: Vec<u8>,
+    },
+    /// `pbkdf2` w/ `c` iterations of `prf`.
+    Pbkdf2 {
+        /// iteration count
+        c: u32,
+        /// derived key length
+        dklen: u32,
+        /// pseudo-random function
+        prf: Prf,
+        /// random salt
+        #[serde(with = "bare_hex")]
+        salt: Vec<u8>,
+    },
+}
+
+
+impl Kdf {
+
+    /// `scrypt` w/ the standard parameters used by `geth`.
+    pub fn scrypt() -> Self {
+        Kdf::Scrypt { dklen: 32, n: 1 << 18, r: 8, p: 1, salt: salt() }
+    }
+
+    /// `scrypt` w/ the "light" parameters used by `geth`.  this is
+    /// significantly cheaper than `Kdf::scrypt`; prefer the latter
+    /// for keys which guard anything of real value.
+    pub fn scrypt_light() -> Self {
+        Kdf::Scrypt { dklen: 32, n: 1 << 12, r: 8, p: 6, salt: salt() }
+    }
+
+    /// `pbkdf2` w/ the parameters suggested by the v3 spec.
+    pub fn pbkdf2() -> Self {
+        Kdf::Pbkdf2 { c: 1 << 18, dklen: 32, prf: Prf::HmacSha256, salt: salt() }
+    }
+
+    /// derive key material from a passphrase, rejecting parameters
+    /// which fall outside of the bounds accepted by this module.
+    fn derive(&self, passphrase: &[u8]) -> Result<Vec<u8>,Error> {
+        match *self {
+            Kdf::Scrypt { dklen, n, r, p, ref salt } => {
+                if dklen < 32 || dklen > MAX_DKLEN || !n.is_power_of_two() || n < 2 || n > MAX_SCRYPT_N {
+                    return Err(Error::InvalidParams);
+                }
+                if r == 0 || r > MAX_SCRYPT_R || p == 0 || p > MAX_SCRYPT_P {
+                    return Err(Error::InvalidParams);
+                }
+                let params = scrypt::ScryptParams::new(n.trailing_zeros() as u8,r,p)
+                    .map_err(|_| Error::InvalidParams)?;
+                let mut derived = vec![0u8;dklen as usize];
+                scrypt::scrypt(passphrase,salt,&params,&mut derived)
+                    .map_err(|_| Error::InvalidParams)?;
+                Ok(derived)
+            },
+            Kdf::Pbkdf2 { c, dklen, prf: Prf::HmacSha256, ref salt } => {
+                if dklen < 32 || dklen > MAX_DKLEN || c == 0 || c > MAX_PBKDF2_C {
+                    return Err(Error::InvalidParams);
+                }
+                let mut derived = vec![0u8;dklen as usize];
+                pbkdf2::pbkdf2::<Hmac<Sha256>>(passphrase,salt,c,&mut derived);
+                Ok(derived)
+            },
+        }
+    }
+}
+
+
+/// apply `aes-128-ctr` keystream (keyed by the first half of
+/// the derived key material) to the supplied buffer.
+fn apply_cipher(derived: &[u8], iv: &[u8], buff: &mut [u8]) -> Result<(),Error> {
+    let mut cipher = Aes128Ctr::new_var(&derived[..16],iv)
+        .map_err(|_| Error::InvalidLength)?;
+    cipher.apply_keystream(buff);
+    Ok(())
+}
+
+
+/// keyfile mac (keyed by the second half of the derived key material).
+fn mac(derived: &[u8], ciphertext: &[u8]) -> [u8;32] {
+    let mut hasher = Keccak256::default();
+    hasher.absorb(&derived[16..32]);
+    hasher.absorb(ciphertext);
+    hasher.finish()
+}
+
+
+/// compare byte slices in constant time (w/ respect to contents).
+fn constant_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0,|acc,(x,y)| acc | (x ^ y)) == 0
+}
+
+
+/// generate a random kdf salt.
+fn salt() -> Vec<u8> {
+    let salt: [u8;32] = rand::random();
+    salt.to_vec()
+}
+
+
+/// generate a random (version 4) uuid.
+fn uuid() -> String {
+    let mut bytes: [u8;16] = rand::random();
+    bytes[6] = (bytes[6] & 0x0f) | 0x40;
+    bytes[8] = (bytes[8] & 0x3f) | 0x80;
+    let mut buff = [0u8;32];
+    let hex_str = hex::as_str(&bytes,&mut buff);
+    format!("{}-{}-{}-{}-{}",&hex_str[..8],&hex_str[8..12],&hex_str[12..16],&hex_str[16..20],&hex_str[20..])
+}
+
+
+/// ser/de of byte vectors as un-prefixed hex strings.
+mod bare_hex {
+    use super::*;
+
+    pub fn serialize<S>(bytes: &[u8], serializer: S) -> Result<S::Ok,S::Error> where S: Serializer {
+        let mut buff = vec![0u8;bytes.len() * 2];
+        serializer.serialize_str(hex::as_str(bytes,&mut buff))
+    }
+
+    pub fn deserialize<'de,D>(deserializer: D) -> Result<Vec<u8>,D::Error> where D: Deserializer<'de> {
+        let target: Either<&str,String> = Deserialize::deserialize(deserializer)?;
+        let hex_str: &str = target.as_ref();
+        let hex_bytes: &[u8] = hex_str.trim().trim_left_matches("0x").as_ref();
+        if hex_bytes.len() % 2 != 0 {
+            return Err(D::Error::custom(hex::ParseHexError::InvalidSize));
+        }
+        let mut buff = vec![0u8;hex_bytes.len() / 2];
+        hex::from(hex_bytes,&mut buff).map_err(D::Error::custom)?;
+        Ok(buff)
+    }
+}
+
+
+/// ser/de of optional addresses as un-prefixed hex strings.
+mod bare_address {
+    use super::*;
+
+    pub fn serialize<S>(address: &Option<Address>, serializer: S) -> Result<S::Ok,S::Error> where S: Serializer {
+        match *address {
+            Some(ref address) => bare_hex::serialize(address.as_ref(),serializer),
+            None => serializer.serialize_none(),
+        }
+    }
+
+    pub fn deserialize<'de,D>(deserializer: D) -> Result<Option<Address>,D::Error> where D: Deserializer<'de> {
+        let target: Option<Either<&str,String>> = Deserialize::deserialize(deserializer)?;
+        match target {
+            Some(target) => {
+                let hex_str: &str = target.as_ref();
+                hex_str.parse().map(Some).map_err(D::Error::custom)
+            },
+            None => Ok(None),
+        }
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use keystore::{KeyFile,Kdf,Prf,Error};
+    use keystore::{MAX_DKLEN,MAX_SCRYPT_N,MAX_SCRYPT_R,MAX_SCRYPT_P,MAX_PBKDF2_C};
+    use secp256k1::dev;
+    use rand;
+
+    // test vector from the web3 secret storage definition.
+    const PBKDF2: &str = r#"{
+        "crypto" : {
+            "cipher" : "aes-128-ctr",
+            "cipherparams" : { "iv" : "6087dab2f9fdbbfaddc31a909735c1e6" },
+            "ciphertext" : "5318b4d5bcd28de64ee5559e671353e16f075ecae9f99c7a79a38af5f869aa46",
+            "kdf" : "pbkdf2",
+            "kdfparams" : {
+                "c" : 262144,
+                "dklen" : 32,
+                "prf" : "hmac-sha256",
+                "salt" : "ae3cd4e7013836a3df6bd7241b12db061dbe2c6785853cce422d148a624ce0bd"
+            },
+            "mac" : "517ead924a9d0dc3124507e3393d175ce3ff7c1e96529c6c555ce9e51205e9b2"
+        },
+        "id" : "3198bc9c-6672-5ab3-d995-4942343ae5b6",
+        "version" : 3
+    }"#;
+
+    // the spec's scrypt vector uses `r = 1` w/ `n = 2^18`, which violates
+    // rfc 7914 (`n < 2^(16 * r)`), so this vector was generated against an
+    // independent implementation instead (same secret, salt, & iv).
+    const SCRYPT: &str = r#"{
+        "crypto" : {
+            "cipher" : "aes-128-ctr",
+            "cipherparams" : { "iv" : "83dbcc02d8ccb40e466191a123791e0e" },
+            "ciphertext" : "01a05c7f05b697274227d8bd0825a6caa89967e24643426c0fcfa2fb663052d7",
+            "kdf" : "scrypt",
+            "kdfparams" : {
+                "dklen" : 32,
+                "n" : 1024,
+                "r" : 8,
+                "p" : 1,
+                "salt" : "ab0c7876052600dd703518d6fc3fe8984592145b591fc8fb5c6d43190334ba19"
+            },
+            "mac" : "d60a6540bbdeaa746e4c7b4359c74e4bb0b679bedce5b4d129ad96150d200274"
+        },
+        "id" : "3198bc9c-6672-5ab3-d995-4942343ae5b6",
+        "version" : 3
+    }"#;
+
+    const VECTOR_SECRET: &str = "0x7a28b5ba57c53603b0b07b56bba752f7784bf506fa95edc395f5cf6c7514fe9d";
+
+    fn cheap_scrypt() -> Kdf {
+        Kdf::Scrypt { dklen: 32, n: 1 << 10, r: 8, p: 1, salt: vec![0xab;32] }
+    }
+
+    fn cheap_pbkdf2() -> Kdf {
+        match Kdf::pbkdf2() {
+            Kdf::Pbkdf2 { dklen, prf, salt, .. } => Kdf::Pbkdf2 { c: 1 << 10, dklen, prf, salt },
+            _ => unreachable!(),
+        }
+    }
+
+    #[test]
+    fn spec_vectors() {
+        for vector in &[PBKDF2,SCRYPT] {
+            let keyfile: KeyFile = vector.parse().unwrap();
+            assert_eq!(keyfile.address,None);
+            assert_eq!(keyfile.decrypt(b"testpassword").unwrap(),VECTOR_SECRET.parse().unwrap());
+            assert_eq!(keyfile.decrypt(b"testpassworD"),Err(Error::MacMismatch));
+        }
+    }
+
+    #[test]
+    fn round_trip() {
+        for kdf in &[cheap_scrypt(),cheap_pbkdf2()] {
+            let secret = rand::random();
+            let keyfile = KeyFile::encrypt(&secret,b"passphrase",kdf.clone()).unwrap();
+            assert_eq!(keyfile.kdf(),kdf);
+            let parsed: KeyFile = keyfile.to_json().parse().unwrap();
+            assert_eq!(parsed,keyfile);
+            assert_eq!(parsed.decrypt(b"passphrase").unwrap(),secret);
+            assert_eq!(parsed.decrypt(b""),Err(Error::MacMismatch));
+        }
+    }
+
+    #[test]
+    fn json_format() {
+        let keyfile = KeyFile::encrypt(&dev::SECRET,b"",cheap_scrypt()).unwrap();
+        let json = keyfile.to_json();
+        assert!(json.contains(r#""address": "00a329c0648769a73afac7f9381e08fb43dbea72""#));
+        assert!(json.contains(r#""kdf": "scrypt""#));
+        assert!(json.contains(r#""version": 3"#));
+        let id = keyfile.id.as_bytes();
+        assert_eq!((id.len(),id[14]),(36,b'4'));
+        // mixed-case field names & prefixed addresses are tolerated.
+        let legacy = json.replace(r#""crypto""#,r#""Crypto""#).replace(r#""00a3"#,r#""0x00a3"#);
+        let parsed: KeyFile = legacy.parse().unwrap();
+        assert_eq!(parsed.signer(b"").unwrap().address(),dev::ADDRESS);
+    }
+
+    #[test]
+    fn rejections() {
+        let keyfile = KeyFile::encrypt(&dev::SECRET,b"",cheap_scrypt()).unwrap();
+        let mut wrong_version = keyfile.clone();
+        wrong_version.version = 1;
+        assert_eq!(wrong_version.decrypt(b""),Err(Error::UnsupportedVersion));
+        let mut wrong_address = keyfile.clone();
+        wrong_address.address = Some(Default::default());
+        assert_eq!(wrong_address.decrypt(b""),Err(Error::AddressMismatch));
+        let mut bad_params = keyfile.clone();
+        bad_params.crypto.kdf = Kdf::Scrypt { dklen: 32, n: 1000, r: 8, p: 1, salt: vec![] };
+        assert_eq!(bad_params.decrypt(b""),Err(Error::InvalidParams));
+        let mut bad_mac = keyfile.clone();
+        bad_mac.crypto.ciphertext[0] ^= 1;
+        assert_eq!(bad_mac.decrypt(b""),Err(Error::MacMismatch));
+        assert!(keyfile.to_json().replace("aes-128-ctr","aes-128-cbc").parse::<KeyFile>().is_err());
+    }
+
+    #[test]
+    fn bounded_params() {
+        let salt = vec![0xab;32];
+        let scrypt = |dklen,n,r,p| Kdf::Scrypt { dklen, n, r, p, salt: salt.clone() };
+        let pbkdf2 = |dklen,c| Kdf::Pbkdf2 { c, dklen, prf: Prf::HmacSha256, salt: salt.clone() };
+        let oversized = [
+            scrypt(MAX_DKLEN + 1,1 << 10,8,1),
+            scrypt(32,MAX_SCRYPT_N << 1,8,1),
+            scrypt(32,1 << 10,MAX_SCRYPT_R + 1,1),
+            scrypt(32,1 << 10,8,MAX_SCRYPT_P + 1),
+            scrypt(32,1 << 10,8,0),
+            pbkdf2(1 << 31,1 << 10),
+            pbkdf2(32,MAX_PBKDF2_C + 1),
+        ];
+        for kdf in oversized.iter() {
+            assert_eq!(kdf.derive(b""),Err(Error::InvalidParams));
+        }
+        for kdf in [scrypt(MAX_DKLEN,1 << 10,8,1),pbkdf2(MAX_DKLEN,1 << 10)].iter() {
+            assert_eq!(kdf.derive(b"").unwrap().len(),MAX_DKLEN as usize);
+        }
+        for kdf in [Kdf::scrypt(),Kdf::scrypt_light(),Kdf::pbkdf2()].iter() {
+            match *kdf {
+                Kdf::Scrypt { dklen, n, r, p, .. } => assert!(dklen <= MAX_DKLEN && n <= MAX_SCRYPT_N && r <= MAX_SCRYPT_R && p <= MAX_SCRYPT_P),
+                Kdf::Pbkdf2 { dklen, c, .. } => assert!(dklen <= MAX_DKLEN && c <= MAX_PBKDF2_C),
+            }
+        }
+    }
+}
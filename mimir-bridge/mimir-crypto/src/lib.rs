@@ -33,9 +33,25 @@ extern crate lazy_static;
 extern crate tiny_keccak;
 extern crate secp256k1 as _secp256k1;
 extern crate rand;
+extern crate serde;
+#[macro_use]
+extern crate serde_derive;
+extern crate serde_json;
+extern crate scrypt;
+extern crate pbkdf2;
+extern crate hmac;
+extern crate sha2;
+extern crate aes_ctr;
+extern crate ripemd160;
+extern crate unicode_normalization;
 
 
+pub mod bip32;
+pub mod bip39;
+pub mod eip191;
+pub mod eip712;
 pub mod keccak256;
+pub mod keystore;
 pub mod secp256k1;
 pub mod traits;
 pub mod util;
@@ -55,5 +71,7 @@ pub use traits::{
     Signer,
 };
 
+pub use keystore::KeyFile;
+
 pub use util::HashVoyeur;
 
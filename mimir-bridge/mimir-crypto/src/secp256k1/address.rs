@@ -13,6 +13,13 @@ newtype!(Address,[u8;20],[u8]);
 hex_array!(Address,20);
 
 
+impl AsRef<[u8]> for Address {
+
+    #[inline]
+    fn as_ref(&self) -> &[u8] { &self.0 }
+}
+
+
 impl<'a> From<&'a Public> for Address {
 
     #[inline]
@@ -53,14 +53,29 @@ pub fn ecrecover(msg: &[u8;32], sig: &Signature) -> Result<Address,Error> {
 
 
 /// context for verification operations
+///
+/// the default verifier accepts any recoverable signature.  a strict
+/// verifier additionally rejects non-canonical signatures (those with a
+/// high `s` component, or a recovery byte other than `27` or `28`), s.t.
+/// each signature has exactly one valid encoding.
+///
 #[derive(Debug,Clone,PartialEq,Eq)]
 pub struct Verifier {
-    ctx: &'static Secp256k1
+    ctx: &'static Secp256k1,
+    strict: bool,
 }
 
 
 impl Verifier {
 
+    /// get a verifier which only accepts canonical signatures.
+    pub fn strict() -> Self {
+        Verifier { strict: true, ..Default::default() }
+    }
+
+    /// check if this verifier only accepts canonical signatures.
+    pub fn is_strict(&self) -> bool { self.strict }
+
     /// attempt to recover address from msg/sig.
     pub fn ecrecover(&self, msg: &[u8;32], sig: &Signature) -> Result<Address,Error> {
         let public = self.recover(msg,sig)?;
@@ -80,6 +95,13 @@ impl Verifier {
 
     /// attempt to convert signature to `RecoverableSignature`.
     fn recoverable(&self, sig: &Signature) -> Result<RecoverableSignature,Error> {
+        if self.strict {
+            match sig.get_v() {
+                27 | 28 => { },
+                _ => return Err(Error::BadRecoveryByte),
+            }
+            if !sig.is_low_s() { return Err(Error::NonCanonical); }
+        }
         let v = match sig.get_v() {
             v @ 0...26 => v,
             v => v - 27
@@ -101,7 +123,7 @@ impl Default for Verifier {
 
     fn default() -> Self {
         let ctx = &SECP256K1;
-        Verifier { ctx }
+        Verifier { ctx, strict: false }
     }
 }
 
@@ -146,7 +168,7 @@ impl Signer {
         buff[0..64].copy_from_slice(&body[0..64]);
         buff[64] = id.to_i32() as u8;
         buff[64] += 27;
-        Signature::from(buff)
+        Signature::from(buff).to_low_s()
     }
 
     /// derive secret upon which signer was based
@@ -169,6 +191,17 @@ impl Signer {
         let public = self.public();
         Address::from(&public)
     }
+
+    /// derive the signer whose secret is the sum of this
+    /// signer's secret & `tweak` (modulo the curve order).
+    pub(crate) fn add_tweak(&self, tweak: &Secret) -> Result<Self,Error> {
+        let tweak = SecretKey::from_slice(self.ctx,tweak.as_ref())
+            .map_err(|_| Error::InvalidSecret)?;
+        let mut key = self.key.clone();
+        key.add_assign(self.ctx,&tweak)
+            .map_err(|_| Error::InvalidSecret)?;
+        Ok(Signer { key, ctx: self.ctx })
+    }
 }
 
 
@@ -214,6 +247,7 @@ simple_error!(
     BadRecoveryByte => "recovery byte invalid",
     BadSignatureFmt => "format invalid",
     RecoveryFailed => "recovery operation failed",
+    NonCanonical => "non-canonical (high-s) signature",
     InvalidSecret => "malformed secret key",
     AddressMismatch => "recovered unexpected address",
 );
@@ -241,7 +275,7 @@ pub mod dev {
 
 #[cfg(test)]
 mod tests {
-    use secp256k1::{self,dev,Signer};
+    use secp256k1::{self,dev,Signer,Verifier,Error};
     use keccak256::Keccak256;
 
     #[test]
@@ -266,6 +300,27 @@ mod tests {
         let address = secp256k1::ecrecover(&msg,&sig).unwrap();
         assert_eq!(address,dev::ADDRESS);
     }
+
+    #[test]
+    fn malleability() {
+        let signer = Signer::new(dev::SECRET).unwrap();
+        let (strict,lenient) = (Verifier::strict(),Verifier::default());
+        for i in 0..16u8 {
+            let msg = Keccak256::hash(&[i]);
+            let sig = signer.sign(&msg);
+            assert!(sig.is_low_s() && (sig.get_v() == 27 || sig.get_v() == 28));
+            assert_eq!(strict.ecrecover(&msg,&sig).unwrap(),dev::ADDRESS);
+            let high = sig.negated();
+            assert!(!high.is_low_s());
+            assert_eq!(high.to_low_s(),sig);
+            assert_eq!(lenient.ecrecover(&msg,&high).unwrap(),dev::ADDRESS);
+            assert_eq!(strict.ecrecover(&msg,&high),Err(Error::NonCanonical));
+            let mut raw = sig;
+            raw.0[64] -= 27;
+            assert_eq!(lenient.ecrecover(&msg,&raw).unwrap(),dev::ADDRESS);
+            assert_eq!(strict.ecrecover(&msg,&raw),Err(Error::BadRecoveryByte));
+        }
+    }
 }
 
 
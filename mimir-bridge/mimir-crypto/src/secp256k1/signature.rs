@@ -3,6 +3,20 @@ use secp256k1::Signer;
 use rand::{Rand,Rng};
 use std::fmt;
 
+/// order of the k1 curve.
+const ORDER: [u8;32] = [
+    0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xfe,
+    0xba, 0xae, 0xdc, 0xe6, 0xaf, 0x48, 0xa0, 0x3b, 0xbf, 0xd2, 0x5e, 0x8c, 0xd0, 0x36, 0x41, 0x41,
+];
+
+
+/// half the order of the k1 curve (max `s` of a canonical signature).
+const HALF_ORDER: [u8;32] = [
+    0x7f, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff,
+    0x5d, 0x57, 0x6e, 0x73, 0x57, 0xa4, 0x50, 0x1d, 0xdf, 0xe9, 0x2f, 0x46, 0x68, 0x1b, 0x20, 0xa0,
+];
+
+
 /// recoverable signature in form `(r,s,v)`
 pub struct Signature(pub [u8;65]);
 
@@ -34,6 +48,39 @@ impl Signature {
     #[inline]
     pub fn get_s(&self) -> &[u8] { &self.0[32..64] }
 
+    /// check if the `s` component lies in the lower half of the curve order.
+    #[inline]
+    pub fn is_low_s(&self) -> bool { self.get_s() <= &HALF_ORDER[..] }
+
+    /// get the canonical (low-s) encoding of this signature.
+    ///
+    /// for each signature `(r,s,v)`, the signature `(r,n-s,v')` (where `v'`
+    /// is the opposite recovery byte) is equally valid.  the canonical
+    /// encoding is the one whose `s` lies in the lower half of the order.
+    ///
+    pub fn to_low_s(&self) -> Self {
+        if self.is_low_s() { *self } else { self.negated() }
+    }
+
+    /// get the equivalent signature `(r,n-s,v')`.
+    pub(crate) fn negated(&self) -> Self {
+        let mut buff = self.0;
+        let mut borrow = 0u16;
+        for i in (0..32).rev() {
+            let (order,sub) = (ORDER[i] as u16,self.0[32 + i] as u16 + borrow);
+            let (byte,next) = if order >= sub { (order - sub,0) } else { (order + 0x100 - sub,1) };
+            buff[32 + i] = byte as u8;
+            borrow = next;
+        }
+        buff[64] = match self.get_v() {
+            0 => 1,
+            1 => 0,
+            27 => 28,
+            28 => 27,
+            other => other,
+        };
+        Signature(buff)
+    }
 }
 
 
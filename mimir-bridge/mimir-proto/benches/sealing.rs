@@ -0,0 +1,97 @@
+//! compares full-walk sealing (`seal::util`) against incremental
+//! sealing (`MessageAccumulator`) across increasingly long messages.
+//!
+#[macro_use]
+extern crate criterion;
+extern crate mimir_crypto;
+extern crate mimir_proto;
+extern crate rand;
+
+use criterion::Criterion;
+use mimir_crypto::secp256k1::Signer;
+use mimir_crypto::{Keccak256,Address};
+use mimir_proto::seal::{Sealer,MessageAccumulator,Domain};
+use mimir_proto::message::{Message,Payload,CircuitSpec,Code,STEP};
+use mimir_proto::message::cert::Cert;
+use mimir_proto::route::RouteTree;
+
+
+/// number of verify circuits per benchmarked message.
+const CIRCUITS: [usize;3] = [8,32,128];
+
+
+fn steps(circuits: usize) -> usize { 4 + circuits * 6 }
+
+
+fn validator_tree() -> RouteTree<Address> {
+    RouteTree::new((0..8).map(|_| rand::random::<Address>())).unwrap()
+}
+
+
+/// build message by re-walking the full message for each seal.
+fn full_walk(spec: &CircuitSpec, signer: &Signer, tree: &RouteTree<Address>, steps: usize) -> Message {
+    let mut message = Message::new(Payload::default());
+    let mut seed = None;
+    for _ in 0..steps {
+        let cert = match message.next_step(spec) {
+            STEP::BLIND => {
+                let clear: [u8;32] = rand::random();
+                seed = Some(clear);
+                Cert::Blind(Keccak256::hash(&clear).into())
+            },
+            STEP::CLEAR => Cert::Clear(seed.take().unwrap().into()),
+            STEP::ORACLE => Cert::Oracle(signer.seal_oracle(spec,&Domain::legacy(),&message)),
+            STEP::NOTARY => Cert::Notary(signer.seal_notary(spec,&Domain::legacy(),&message)),
+            STEP::ROUTE => Cert::Route(signer.seal_route(spec,&Domain::legacy(),&message,tree).unwrap()),
+            STEP::VERIFY => Cert::Verify(signer.seal_verify(spec,&Domain::legacy(),&message,Code::Ok)),
+        };
+        message.append_cert(spec,cert).unwrap();
+    }
+    message
+}
+
+
+/// build message incrementally.
+fn incremental(spec: &CircuitSpec, signer: &Signer, tree: &RouteTree<Address>, steps: usize) -> Message {
+    let mut acc = MessageAccumulator::new(spec.clone(),Domain::legacy(),Payload::default());
+    let mut seed = None;
+    for _ in 0..steps {
+        match acc.next_step() {
+            STEP::BLIND => {
+                let clear: [u8;32] = rand::random();
+                seed = Some(clear);
+                acc.append_cert(Cert::Blind(Keccak256::hash(&clear).into())).unwrap();
+            },
+            STEP::CLEAR => { acc.append_cert(Cert::Clear(seed.take().unwrap().into())).unwrap(); },
+            STEP::ORACLE => { acc.seal_oracle(signer).unwrap(); },
+            STEP::NOTARY => { acc.seal_notary(signer).unwrap(); },
+            STEP::ROUTE => { acc.seal_route(signer,tree).unwrap().unwrap(); },
+            STEP::VERIFY => { acc.seal_verify(signer,Code::Ok).unwrap(); },
+        }
+    }
+    acc.into_inner()
+}
+
+
+fn sealing(c: &mut Criterion) {
+    let spec = CircuitSpec::default();
+    let signer: Signer = rand::random();
+    let tree = validator_tree();
+    c.bench_function_over_inputs("full-walk",move |b,&&circuits| {
+        b.iter(|| full_walk(&spec,&signer,&tree,steps(circuits)))
+    },CIRCUITS.iter());
+    let spec = CircuitSpec::default();
+    let signer: Signer = rand::random();
+    let tree = validator_tree();
+    c.bench_function_over_inputs("incremental",move |b,&&circuits| {
+        b.iter(|| incremental(&spec,&signer,&tree,steps(circuits)))
+    },CIRCUITS.iter());
+}
+
+
+criterion_group!{
+    name = benches;
+    config = Criterion::default().sample_size(10);
+    targets = sealing
+}
+criterion_main!(benches);
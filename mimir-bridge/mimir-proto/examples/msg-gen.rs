@@ -22,8 +22,9 @@ fn main() {
 
 /// generate random values.
 mod gen { 
-    use mimir_crypto::secp256k1::Signer;
+    use mimir_crypto::secp256k1::{Signer,Address};
     use mimir_proto::message::cert::Clear;
+    use mimir_proto::route::RouteTree;
     use mimir_proto::message::{
         Payload,
         Message,
@@ -33,7 +34,7 @@ mod gen {
     pub fn payload() -> Payload {
         let record = r#"{"method":"eth_gasPrice","result":"0xdeadbeef"}"#.into();
         let (address,number,hash) = rand::random();
-        Payload { record, address, number, hash }
+        Payload { record, address, number, hash, version: Default::default() }
     }
 
     pub fn message() -> Message { Message::new(payload()) }
@@ -41,58 +42,66 @@ mod gen {
     pub fn sealer() -> Signer { rand::random() }
 
     pub fn clear() -> Clear { rand::random() }
+
+    pub fn tree() -> RouteTree<Address> {
+        let validators = (0..8).map(|_| rand::random::<Address>());
+        RouteTree::new(validators).unwrap()
+    }
 }
 
 
 /// apply random values.
 mod apply {
     use mimir_crypto::Keccak256;
-    use mimir_proto::seal::Sealer;
+    use mimir_proto::seal::{Sealer,Domain};
+    use mimir_proto::message::cert::Cert;
     use mimir_proto::message::{
+        CircuitSpec,
         Message,
+        Code,
         STEP,
     };
-    use rand;
     use gen;
 
     pub fn certs(message: &mut Message, steps: usize) {
+        let spec = CircuitSpec::default();
+        let domain = Domain::legacy();
+        let tree = gen::tree();
         let mut seed = None;
-        for index in 0..(steps) {
-            match STEP::new(index) {
+        for _ in 0..(steps) {
+            let cert = match message.next_step(&spec) {
                 STEP::ORACLE => {
                     let sealer = gen::sealer();
-                    let cert = sealer.seal_oracle(&message);
-                    message.verify.push(cert);
+                    Cert::Oracle(sealer.seal_oracle(&spec,&domain,&message))
                 },
                 STEP::NOTARY => {
                     let sealer = gen::sealer();
-                    let cert = sealer.seal_notary(&message);
-                    message.notary.push(cert);
+                    Cert::Notary(sealer.seal_notary(&spec,&domain,&message))
                 },
                 STEP::BLIND => {
                     assert!(seed.is_none(),"must consume previous seed first");
                     let clear = gen::clear();
                     let blind = Keccak256::hash(&clear);
                     seed = Some(clear);
-                    message.blind.push(blind.into());
+                    Cert::Blind(blind.into())
                 },
                 STEP::CLEAR => {
                     assert!(seed.is_some(),"must seed prior to clearing");
-                    let clear = seed.take().unwrap();
-                    message.blind.push(clear);
+                    Cert::Clear(seed.take().unwrap())
                 },
                 STEP::ROUTE => {
                     let sealer = gen::sealer();
-                    let route = rand::random();
-                    let cert = sealer.seal_route(&message,route);
-                    message.route.push(cert);
+                    let cert = sealer.seal_route(&spec,&domain,&message,&tree)
+                        .expect("route must follow a completed circuit");
+                    Cert::Route(cert)
                 },
                 STEP::VERIFY => {
                     let sealer = gen::sealer();
-                    let cert = sealer.seal_verify(&message,0x00);
-                    message.verify.push(cert);
+                    Cert::Verify(sealer.seal_verify(&spec,&domain,&message,Code::Ok))
                 },
-            }
+            };
+            message.append_cert(&spec,cert)
+                .expect("cert must match next step");
         }
     }
 }
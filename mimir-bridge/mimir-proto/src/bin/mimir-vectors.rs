@@ -0,0 +1,22 @@
+extern crate mimir_proto;
+extern crate serde_json;
+
+use mimir_proto::testkit::vectors;
+use std::fs::File;
+use std::env;
+
+
+/// write the golden vector corpus to the supplied path (or stdout).
+fn main() {
+    let corpus = vectors::corpus();
+    match env::args().nth(1) {
+        Some(path) => {
+            let file = File::create(&path).expect("unable to create corpus file");
+            serde_json::to_writer_pretty(file,&corpus).expect("unable to write corpus");
+        },
+        None => {
+            let json = serde_json::to_string_pretty(&corpus).expect("unable to serialize corpus");
+            println!("{}",json);
+        },
+    }
+}
@@ -1,20 +1,41 @@
 //! types & utils related to accusation.
 //!
-use visit::{self,ByteVisitor,MessageVisitor};
+use visit::{self,ByteVisitor,MessageVisitor,Reader,Decode};
+use mimir_crypto::secp256k1::Verifier;
 use mimir_crypto::{Signer,Keccak256};
-use mimir_types::Signature;
-use message::Message;
+use mimir_types::{Signature,Address,Bytes,H256};
+use message::record::BATCH_FLAG;
+use message::{Message,Payload,Record,Checkpoint,CircuitSpec,Code};
 use message::cert::{
     Oracle,
     Notary,
     Route,
     Verify
 };
+use route::NodeHash;
+use seal::{Domain,Version,Tag};
 
 
 // accusation flag.
 const ACCFLAG: u8 = 0x01;
 
+// length of fixed-size payload fields (address, number & hash).
+const PAYLOAD_TAIL: usize = 20 + 32 + 32;
+
+// length of a signature.
+const SIG_LEN: usize = 65;
+
+
+simple_error!(
+    AccusationError, "error indicating a malformed accusation",
+    Offset => "invalid offset pointers",
+    Flag => "missing accusation flag",
+    Record => "malformed payload record",
+    Cert => "malformed accused cert",
+    Sig => "unable to recover signer",
+    Version => "unaccepted signing version",
+);
+
 
 /// enum representing a potential accusative action. 
 ///
@@ -49,11 +70,22 @@ pub enum Accuse {
         
         /// cert of accused.
         cert: Route
+    },
+
+    /// accuse a checkpoint (backed by a failing call
+    /// to `Checkpoint::check_history`).
+    Checkpoint {
+        /// history which the checkpoint fails to commit to.
+        inner: Message,
+
+        /// checkpoint of accused.
+        checkpoint: Checkpoint
     }
 }
 
 
 /// a signed accusation, ready for compilation into a contract call.
+#[derive(Debug,Clone,PartialEq,Eq,Serialize,Deserialize)]
 pub struct Accusation {
     /// payload offset pointer
     pub payload: usize,
@@ -65,47 +97,182 @@ pub struct Accusation {
     pub accuser: usize,
 
     /// accusation bytes
-    pub bytes: Vec<u8>
+    pub bytes: Bytes,
+
+    /// signing version declared by the accused message
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Version::is_legacy")]
+    pub version: Version
+}
+
+
+impl Accusation {
+
+    /// parse accusation bytes, checking offset pointers & flag.
+    ///
+    /// the kind of cert under dispute is determined by the length
+    /// of the accused segment: `66` bytes for a verify cert, `131`
+    /// for an oracle cert & its notary seal, and anything else must
+    /// be a well formed route cert.
+    ///
+    pub fn parse(&self) -> Result<Dispute,AccusationError> {
+        let Accusation { payload, accused, accuser, ref bytes, version } = *self;
+        let (payload_end,sig_end) = match (payload.checked_add(PAYLOAD_TAIL),accuser.checked_add(1 + SIG_LEN)) {
+            (Some(payload_end),Some(sig_end)) => (payload_end,sig_end),
+            _ => return Err(AccusationError::Offset),
+        };
+        let well_ordered = payload_end <= accused && accused < accuser
+            && sig_end == bytes.len();
+        if !well_ordered {
+            return Err(AccusationError::Offset);
+        }
+        if bytes[accuser] != ACCFLAG {
+            return Err(AccusationError::Flag);
+        }
+        let payload = parse_payload(&bytes[..payload_end],payload,version)?;
+        let disputed = parse_disputed(&bytes[accused..accuser])?;
+        let sig = parse_sig(&bytes[(accuser + 1)..]);
+        Ok(Dispute { payload, disputed, sig })
+    }
+
+    /// parse accusation & recover the addresses of all signers.
+    ///
+    /// hashes are rebuilt exactly as they were during signing: the
+    /// accused signed everything preceeding its signature, and the
+    /// accuser signed everything up to & including the accusation flag.
+    /// the accused (& its notary) signed under the version declared by the
+    /// accused message, which must be accepted by the supplied domain, and
+    /// the accuser signed under the supplied domain itself.  only canonical
+    /// signatures are accepted (see `Verifier::strict`).
+    ///
+    pub fn verify(&self, domain: &Domain) -> Result<Verified,AccusationError> {
+        let dispute = self.parse()?;
+        let signing = domain.at(self.version)
+            .ok_or(AccusationError::Version)?;
+        let verifier = Verifier::strict();
+        let recover = |domain: &Domain, tag: Tag, signed: &[u8], sig: &Signature| {
+            let hash = domain.hash(tag,&Keccak256::hash(signed));
+            verifier.ecrecover(&hash,sig)
+                .map_err(|_| AccusationError::Sig)
+        };
+        let bytes = &self.bytes;
+        let (accused,notary) = match dispute.disputed {
+            Disputed::Oracle { ref cert, ref seal } => {
+                let accused = recover(&signing,Tag::Oracle,&bytes[..(self.accused + 1)],&cert.sig)?;
+                let notary = recover(&signing,Tag::Notary,&bytes[..(self.accused + 1 + SIG_LEN)],&seal.sig)?;
+                (accused,Some(notary))
+            },
+            Disputed::Verify { ref cert } => {
+                let accused = recover(&signing,Tag::Verify,&bytes[..(self.accused + 1)],&cert.sig)?;
+                (accused,None)
+            },
+            Disputed::Route { ref cert } => {
+                let accused = recover(&signing,Tag::Route,&bytes[..(self.accuser - SIG_LEN)],&cert.sig)?;
+                (accused,None)
+            },
+        };
+        let accuser = recover(domain,Tag::Accuse,&bytes[..(self.accuser + 1)],&dispute.sig)?;
+        Ok(Verified { dispute, accused, accuser, notary })
+    }
+}
+
+
+/// cert under dispute, as recovered from an accusation.
+#[derive(Debug,Clone,PartialEq,Eq,Serialize,Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Disputed {
+    /// disputed oracle cert.
+    Oracle {
+        /// cert of accused.
+        cert: Oracle,
+
+        /// outer notary cert.
+        seal: Notary
+    },
+
+    /// disputed verify cert.
+    Verify {
+        /// cert of accused.
+        cert: Verify
+    },
+
+    /// disputed route cert.
+    Route {
+        /// cert of accused.
+        cert: Route
+    }
+}
+
+
+/// parsed contents of an accusation.
+#[derive(Debug,Clone,PartialEq,Eq,Serialize,Deserialize)]
+pub struct Dispute {
+    /// payload of the disputed message.
+    pub payload: Payload,
+
+    /// cert under dispute.
+    pub disputed: Disputed,
+
+    /// signature of the accuser.
+    pub sig: Signature
+}
+
+
+/// parsed contents of an accusation, with recovered signers.
+#[derive(Debug,Clone,PartialEq,Eq,Serialize,Deserialize)]
+pub struct Verified {
+    /// parsed accusation contents.
+    pub dispute: Dispute,
+
+    /// address of the accused.
+    pub accused: Address,
+
+    /// address of the accuser.
+    pub accuser: Address,
+
+    /// address of the notary which sealed
+    /// the accused cert (oracle only).
+    pub notary: Option<Address>
 }
 
 
 /// generate an accusation against an oracle cert
 ///
-pub fn oracle<S>(signer: S, inner: &Message, cert: &Oracle, seal: &Notary) -> Accusation where S: Signer<Msg=[u8;32],Sig=Signature> {
+pub fn oracle<S>(signer: S, spec: &CircuitSpec, domain: &Domain, inner: &Message, cert: &Oracle, seal: &Notary) -> Accusation where S: Signer<Msg=[u8;32],Sig=Signature> {
     // get basic accusation info
-    let (payload,accused,mut visitor) = begin_accusation(inner);
+    let (payload,accused,mut visitor) = begin_accusation(spec,inner);
     // visit cert of accused
     visitor.visit_oracle(&cert);
     // visit notary seal
     visitor.visit_notary(&seal);
     // pass off to finalizer
-    finish_accusation(signer,payload,accused,visitor)
+    finish_accusation(signer,domain,inner.payload.version,payload,accused,visitor)
 }
 
 
 
 /// generate an accusation against a verify cert
 ///
-pub fn verify<S>(signer: S, inner: &Message, cert: &Verify) -> Accusation where S: Signer<Msg=[u8;32],Sig=Signature> {
+pub fn verify<S>(signer: S, spec: &CircuitSpec, domain: &Domain, inner: &Message, cert: &Verify) -> Accusation where S: Signer<Msg=[u8;32],Sig=Signature> {
     // get basic accusation info
-    let (payload,accused,mut visitor) = begin_accusation(inner);
+    let (payload,accused,mut visitor) = begin_accusation(spec,inner);
     // visit cert of accused
     visitor.visit_verify(&cert);
     // pass off to finalizer
-    finish_accusation(signer,payload,accused,visitor)
+    finish_accusation(signer,domain,inner.payload.version,payload,accused,visitor)
 }
 
 
 
 /// generate an accusation against a route cert
 ///
-pub fn route<S>(signer: S, inner: &Message, cert: &Route) -> Accusation where S: Signer<Msg=[u8;32],Sig=Signature> {
+pub fn route<S>(signer: S, spec: &CircuitSpec, domain: &Domain, inner: &Message, cert: &Route) -> Accusation where S: Signer<Msg=[u8;32],Sig=Signature> {
     // get basic accusation info
-    let (payload,accused,mut visitor) = begin_accusation(inner);
+    let (payload,accused,mut visitor) = begin_accusation(spec,inner);
     // visit cert of accused
     visitor.visit_route(&cert);
     // pass off to finalizer
-    finish_accusation(signer,payload,accused,visitor)
+    finish_accusation(signer,domain,inner.payload.version,payload,accused,visitor)
 }
 
 
@@ -114,13 +281,13 @@ pub fn route<S>(signer: S, inner: &Message, cert: &Route) -> Accusation where S:
 ///
 /// returned values are payload offset pointer, accused cert
 /// offset pointer, and partially seeded visitor.
-fn begin_accusation(inner: &Message) -> (usize,usize,ByteVisitor) {
+fn begin_accusation(spec: &CircuitSpec, inner: &Message) -> (usize,usize,ByteVisitor) {
     // calculate payload offset pointer
-    let payload = inner.payload.record.as_bytes().len();
+    let payload = inner.payload.record.signed_bytes().len();
     // initialize visitor for message byte collection
     let mut visitor = ByteVisitor::default();
     // apply visitor to message
-    visit::apply(&mut visitor,inner);
+    visit::apply(spec,&mut visitor,inner);
     // calculate accused cert offset pointer
     let accused = visitor.as_bytes().len();
     // pass back to caller for completion
@@ -130,7 +297,7 @@ fn begin_accusation(inner: &Message) -> (usize,usize,ByteVisitor) {
 
 
 /// finalization logic common to all accusation builders
-fn finish_accusation<S>(signer: S, payload: usize, accused: usize, visitor: ByteVisitor) -> Accusation 
+fn finish_accusation<S>(signer: S, domain: &Domain, version: Version, payload: usize, accused: usize, visitor: ByteVisitor) -> Accusation 
         where S: Signer<Msg=[u8;32],Sig=Signature> {
     // unwrap byte visitor
     let mut bytes = visitor.finish();
@@ -139,12 +306,282 @@ fn finish_accusation<S>(signer: S, payload: usize, accused: usize, visitor: Byte
     // append accusation flag to message bytes
     bytes.push(ACCFLAG);
     // hash message bytes + flag
-    let hash = Keccak256::hash(&bytes);
+    let hash = domain.hash(Tag::Accuse,&Keccak256::hash(&bytes));
     // generate accuser signature
     let sig = signer.sign(&hash);
     // append accuser signature to message bytes
     bytes.extend_from_slice(sig.as_ref());
     // return completed accusation object
-    Accusation { payload, accused, accuser, bytes }
+    Accusation { payload, accused, accuser, bytes: bytes.into(), version }
+}
+
+
+/// parse payload from leading message bytes.
+///
+/// batched records are recovered in pruned form, since
+/// only the root of a batch is covered by its certs.
+fn parse_payload(bytes: &[u8], record_len: usize, version: Version) -> Result<Payload,AccusationError> {
+    let (record,fixed) = bytes.split_at(record_len);
+    let record = match record.split_first() {
+        Some((&BATCH_FLAG,root)) if root.len() == 32 => {
+            Record::Root(H256::from_bytes(root).expect("length pre-checked"))
+        },
+        _ => {
+            let record = String::from_utf8(record.to_owned())
+                .map_err(|_| AccusationError::Record)?;
+            Record::Single(record)
+        },
+    };
+    let mut reader = Reader::new(fixed);
+    let address = Address::decode(&mut reader).expect("length pre-checked");
+    let number = Decode::decode(&mut reader).expect("length pre-checked");
+    let hash = H256::decode(&mut reader).expect("length pre-checked");
+    Ok(Payload { record, address, number, hash, version })
+}
+
+
+/// parse signature from bytes of known length.
+fn parse_sig(bytes: &[u8]) -> Signature {
+    let mut sig = [0u8;SIG_LEN];
+    sig.copy_from_slice(bytes);
+    Signature(sig)
+}
+
+
+/// parse the accused segment of an accusation.
+fn parse_disputed(bytes: &[u8]) -> Result<Disputed,AccusationError> {
+    // verify certs are encoded as the flag followed by the signature.
+    let parse_verify = |bytes: &[u8]| {
+        let val = Code::from_u8(bytes[0]).ok_or(AccusationError::Cert)?;
+        Ok(Verify { val, sig: parse_sig(&bytes[1..]) })
+    };
+    match bytes.len() {
+        len if len == 1 + SIG_LEN => {
+            let cert = parse_verify(bytes)?;
+            Ok(Disputed::Verify { cert })
+        },
+        len if len == 1 + SIG_LEN * 2 => {
+            let cert = parse_verify(&bytes[..(1 + SIG_LEN)])?;
+            let seal = Notary { sig: parse_sig(&bytes[(1 + SIG_LEN)..]) };
+            Ok(Disputed::Oracle { cert, seal })
+        },
+        len if len > 32 + SIG_LEN => {
+            let (body,sig) = bytes.split_at(len - SIG_LEN);
+            let cert = parse_route(body,parse_sig(sig))
+                .ok_or(AccusationError::Cert)?;
+            Ok(Disputed::Route { cert })
+        },
+        _ => Err(AccusationError::Cert)
+    }
 }
 
+
+/// parse the signed body of a route cert (see `visit::absorb_route`).
+fn parse_route(body: &[u8], sig: Signature) -> Option<Route> {
+    let mut reader = Reader::new(body);
+    let root = H256::decode(&mut reader).ok()?;
+    let (mut val,mut path) = (Vec::new(),Vec::new());
+    while reader.remaining() > 0 {
+        val.push(Address::decode(&mut reader).ok()?);
+        let len = reader.take_u8().ok()?;
+        let mut nodes = Vec::with_capacity(len as usize);
+        for _ in 0..len {
+            let side = reader.take_u8().ok()?;
+            let hash = H256::decode(&mut reader).ok()?;
+            match side {
+                0x00 => nodes.push(NodeHash::Left(hash)),
+                0x01 => nodes.push(NodeHash::Right(hash)),
+                _ => return None,
+            }
+        }
+        path.push(nodes);
+    }
+    if val.is_empty() { return None; }
+    Some(Route { sig, root, val, path })
+}
+
+
+
+#[cfg(test)]
+mod tests {
+    use mimir_crypto::{Keccak256,Address};
+    use mimir_crypto::secp256k1::Signer;
+    use judge::{accuse,Accusation,AccusationError,Disputed};
+    use message::{Message,Payload,Record,Batch,CircuitSpec,Code};
+    use route::RouteTree;
+    use seal::{Sealer,Domain,Version};
+    use serde_json;
+    use rand;
+
+    /// signing domain of the test deployment.
+    fn domain() -> Domain {
+        Domain::new(1u64.into(),Address::default())
+    }
+
+    fn payload() -> Payload {
+        let record = r#"{"method":"eth_blockNumber","result":"0x2a"}"#.into();
+        let (address,number,hash) = rand::random();
+        Payload { record, address, number, hash, version: Version::V1 }
+    }
+
+    /// build message up to the route step of the first verify circuit.
+    fn routed(signer: &Signer, tree: &RouteTree<Address>) -> Message {
+        let spec = CircuitSpec::default();
+        let mut message = Message::new(payload());
+        let clear: [u8;32] = rand::random();
+        message.blind.push(Keccak256::hash(&clear).into());
+        let cert = signer.seal_oracle(&spec,&domain(),&message);
+        message.verify.push(cert);
+        let cert = signer.seal_notary(&spec,&domain(),&message);
+        message.notary.push(cert);
+        message.blind.push(clear.into());
+        let clear: [u8;32] = rand::random();
+        message.blind.push(Keccak256::hash(&clear).into());
+        let cert = signer.seal_route(&spec,&domain(),&message,tree).unwrap();
+        message.route.push(cert);
+        message
+    }
+
+    #[test]
+    fn oracle_accusation() {
+        let spec = CircuitSpec::default();
+        let (oracle,notary,accuser): (Signer,Signer,Signer) = rand::random();
+        let mut inner = Message::new(payload());
+        inner.blind.push(rand::random());
+        let cert = oracle.seal_oracle(&spec,&domain(),&inner);
+        let mut sealed = inner.clone();
+        sealed.verify.push(cert);
+        let seal = notary.seal_notary(&spec,&domain(),&sealed);
+        let accusation = accuse::oracle(&accuser,&spec,&domain(),&inner,&cert,&seal);
+        let verified = accusation.verify(&domain()).unwrap();
+        assert_eq!(verified.dispute.payload,inner.payload);
+        assert_eq!(verified.dispute.disputed,Disputed::Oracle { cert, seal });
+        assert_eq!(verified.accused,oracle.address());
+        assert_eq!(verified.accuser,accuser.address());
+        assert_eq!(verified.notary,Some(notary.address()));
+    }
+
+    #[test]
+    fn legacy_accusation() {
+        let spec = CircuitSpec::default();
+        let (validator,accuser): (Signer,Signer) = rand::random();
+        let inner = Message::new(Payload { version: Version::Legacy, ..payload() });
+        let cert = validator.seal_verify(&spec,&Domain::legacy(),&inner,Code::Mismatch);
+        let accusation = accuse::verify(&accuser,&spec,&domain(),&inner,&cert);
+        // the accused message is verified under its own version, if accepted.
+        assert_eq!(accusation.verify(&domain()),Err(AccusationError::Version));
+        let verified = accusation.verify(&domain().accepting(Version::Legacy)).unwrap();
+        assert_eq!(verified.dispute.payload,inner.payload);
+        assert_eq!((verified.accused,verified.accuser),(validator.address(),accuser.address()));
+    }
+
+    #[test]
+    fn batched_accusation() {
+        let spec = CircuitSpec::default();
+        let (oracle,notary,accuser): (Signer,Signer,Signer) = rand::random();
+        let records = (0..3).map(|i| format!(r#"{{"id":{}}}"#,i));
+        let record = Record::from(Batch::new(records).unwrap());
+        let mut inner = Message::new(Payload { record, ..payload() });
+        inner.blind.push(rand::random());
+        let cert = oracle.seal_oracle(&spec,&domain(),&inner);
+        let mut sealed = inner.clone();
+        sealed.verify.push(cert);
+        let seal = notary.seal_notary(&spec,&domain(),&sealed);
+        let accusation = accuse::oracle(&accuser,&spec,&domain(),&inner,&cert,&seal);
+        let verified = accusation.verify(&domain()).unwrap();
+        // only the root of the batch is recoverable from the accusation.
+        inner.payload.record.prune();
+        assert_eq!(verified.dispute.payload,inner.payload);
+        assert_eq!(verified.accused,oracle.address());
+        assert_eq!(verified.notary,Some(notary.address()));
+    }
+
+    #[test]
+    fn verify_accusation() {
+        let spec = CircuitSpec::default();
+        let accuser: Signer = rand::random();
+        let validators: Vec<Signer> = (0..4).map(|_| rand::random()).collect();
+        let tree = RouteTree::new(validators.iter().map(|v| v.address())).unwrap();
+        let inner = routed(&validators[0],&tree);
+        let verifier = validators.iter()
+            .find(|v| v.address() == inner.route[0].val[0]).unwrap();
+        let cert = verifier.seal_verify(&spec,&domain(),&inner,Code::Mismatch);
+        let accusation = accuse::verify(&accuser,&spec,&domain(),&inner,&cert);
+        let verified = accusation.verify(&domain()).unwrap();
+        assert_eq!(verified.dispute.disputed,Disputed::Verify { cert });
+        assert_eq!(verified.accused,verifier.address());
+        assert_eq!(verified.accuser,accuser.address());
+        assert_eq!(verified.notary,None);
+    }
+
+    #[test]
+    fn route_accusation() {
+        let spec = CircuitSpec::default();
+        let accuser: Signer = rand::random();
+        let validators: Vec<Signer> = (0..4).map(|_| rand::random()).collect();
+        let tree = RouteTree::new(validators.iter().map(|v| v.address())).unwrap();
+        let mut inner = routed(&validators[0],&tree);
+        let cert = inner.route.pop().unwrap();
+        let accusation = accuse::route(&accuser,&spec,&domain(),&inner,&cert);
+        let verified = accusation.verify(&domain()).unwrap();
+        assert_eq!(verified.dispute.payload,inner.payload);
+        assert_eq!(verified.dispute.disputed,Disputed::Route { cert });
+        assert_eq!(verified.accused,validators[0].address());
+        assert_eq!(verified.accuser,accuser.address());
+    }
+
+    #[test]
+    fn malformed_accusation() {
+        let spec = CircuitSpec::default();
+        let (oracle,accuser): (Signer,Signer) = rand::random();
+        let inner = Message::new(payload());
+        let cert = oracle.seal_oracle(&spec,&domain(),&inner);
+        let accusation = accuse::verify(&accuser,&spec,&domain(),&inner,&cert);
+        let check = |accusation: Accusation| accusation.parse().unwrap_err();
+        let mut bad = accusation.clone();
+        bad.accused = bad.payload;
+        assert_eq!(check(bad),AccusationError::Offset);
+        let mut bad = accusation.clone();
+        bad.bytes.0.pop();
+        assert_eq!(check(bad),AccusationError::Offset);
+        // offsets which would overflow must not panic or wrap.
+        let mut bad = accusation.clone();
+        bad.payload = usize::max_value();
+        assert_eq!(check(bad),AccusationError::Offset);
+        let mut bad = accusation.clone();
+        bad.accuser = usize::max_value();
+        assert_eq!(check(bad),AccusationError::Offset);
+        let mut bad = accusation.clone();
+        bad.payload = usize::max_value();
+        bad.accused = usize::max_value();
+        bad.accuser = usize::max_value();
+        assert_eq!(check(bad),AccusationError::Offset);
+        let mut bad = accusation.clone();
+        bad.bytes.0[accusation.accuser] = 0x00;
+        assert_eq!(check(bad),AccusationError::Flag);
+        let mut bad = accusation.clone();
+        bad.bytes.0[0] = 0xff;
+        assert_eq!(check(bad),AccusationError::Record);
+        // tampering with the accused cert changes the recovered signer.
+        let mut bad = accusation.clone();
+        bad.bytes.0[accusation.accused] ^= 0x01;
+        if let Ok(verified) = bad.verify(&domain()) {
+            assert_ne!(verified.accused,oracle.address());
+        }
+    }
+
+    #[test]
+    fn serde_round_trip() {
+        let spec = CircuitSpec::default();
+        let (oracle,accuser): (Signer,Signer) = rand::random();
+        let inner = Message::new(payload());
+        let cert = oracle.seal_oracle(&spec,&domain(),&inner);
+        let accusation = accuse::verify(&accuser,&spec,&domain(),&inner,&cert);
+        let json = serde_json::to_string(&accusation).unwrap();
+        let parsed: Accusation = serde_json::from_str(&json).unwrap();
+        assert_eq!(parsed,accusation);
+        let verified = accusation.verify(&domain()).unwrap();
+        let json = serde_json::to_string(&verified).unwrap();
+        assert_eq!(serde_json::from_str::<::judge::Verified>(&json).unwrap(),verified);
+    }
+}
@@ -1,6 +1,6 @@
 //! errors which indicate a bad judgement.
 //!
-use mimir_types::Address;
+use mimir_types::{Address,U256,H256};
 use visit::CertError;
 use std::{fmt,error};
 
@@ -18,6 +18,39 @@ pub enum JudgeError {
     Entity {
         /// address which failed lookup
         addr: Address
+    },
+
+    /// unknown routing tree root
+    Root {
+        /// root which failed lookup
+        root: H256
+    },
+
+    /// verify cert from a validator which was not routed to
+    Unrouted {
+        /// address of verifier
+        addr: Address
+    },
+
+    /// verify cert from a routed validator which already signed
+    Duplicate {
+        /// address of verifier
+        addr: Address
+    },
+
+    /// stale or unknown block
+    Block {
+        /// block number of payload
+        number: U256,
+
+        /// block hash of payload
+        hash: H256
+    },
+
+    /// checkpoint which does not end on a circuit boundary
+    Checkpoint {
+        /// number of steps claimed by the checkpoint
+        steps: u32
     }
 }
 
@@ -28,7 +61,12 @@ impl JudgeError {
     pub fn as_str(&self) -> &'static str {
         match *self {
             JudgeError::Cert { ref err } => err.as_str(),
-            JudgeError::Entity { .. } => "nonexistent entity"
+            JudgeError::Entity { .. } => "nonexistent entity",
+            JudgeError::Root { .. } => "unknown routing root",
+            JudgeError::Unrouted { .. } => "unrouted verifier",
+            JudgeError::Duplicate { .. } => "duplicate verifier",
+            JudgeError::Block { .. } => "stale or unknown block",
+            JudgeError::Checkpoint { .. } => "checkpoint off circuit boundary"
         }
     }
 }
@@ -41,6 +79,21 @@ impl fmt::Display for JudgeError {
             JudgeError::Cert { ref err } => err.fmt(f),
             JudgeError::Entity { ref addr } => {
                 write!(f,"entity `{:?}` does not exist",addr)
+            },
+            JudgeError::Root { ref root } => {
+                write!(f,"routing root `{:?}` does not match validator set",root)
+            },
+            JudgeError::Unrouted { ref addr } => {
+                write!(f,"verifier `{:?}` is not a routed destination",addr)
+            },
+            JudgeError::Duplicate { ref addr } => {
+                write!(f,"verifier `{:?}` has already signed",addr)
+            },
+            JudgeError::Block { ref number, ref hash } => {
+                write!(f,"block `{:?}` (`{:?}`) is stale or unknown",number,hash)
+            },
+            JudgeError::Checkpoint { ref steps } => {
+                write!(f,"checkpoint of `{}` steps does not end on a circuit boundary",steps)
             }
         }
     }
@@ -3,9 +3,18 @@
 
 pub mod accuse;
 mod visit;
+mod verdict;
 mod error;
 
-pub use self::accuse::{Accuse,Accusation};
+pub use self::accuse::{
+    Accuse,
+    Accusation,
+    AccusationError,
+    Disputed,
+    Dispute,
+    Verified,
+};
 pub use self::visit::JudgeVisitor;
+pub use self::verdict::{Verdict,StepVerdict};
 pub use self::error::JudgeError;
 
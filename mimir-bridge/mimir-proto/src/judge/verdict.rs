@@ -0,0 +1,116 @@
+//! structured report of the judgement(s) passed on a message.
+//!
+use mimir_types::Address;
+use message::{STEP,Role,Code};
+use judge::{Accuse,JudgeError};
+use visit::CertError;
+use std::fmt;
+
+
+/// judgement passed on a single cert of a message.
+///
+#[derive(Debug,Clone,PartialEq,Eq)]
+pub struct StepVerdict {
+    /// step filled by the cert
+    pub step: STEP,
+
+    /// role expected to have produced the cert
+    pub role: Role,
+
+    /// address recovered from the cert signature (if any)
+    pub signer: Option<Address>,
+
+    /// indicates that the signer is a known entity of the expected role
+    pub known: bool,
+
+    /// error raised during cryptographic checks (if any)
+    pub cert: Option<CertError>,
+
+    /// all other errors attributed to the cert
+    pub errors: Vec<JudgeError>,
+
+    /// accusation raised against the signer (if any)
+    pub accusation: Option<Accuse>,
+}
+
+
+impl StepVerdict {
+
+    /// instantiate a new, passing verdict for the specified step.
+    pub fn new(step: STEP) -> Self {
+        let role = Role::of_step(step);
+        let (signer,known,cert,errors,accusation) = Default::default();
+        Self { step, role, signer, known, cert, errors, accusation }
+    }
+
+    /// check if the cert passed all checks.
+    pub fn is_ok(&self) -> bool {
+        self.cert.is_none() && self.errors.is_empty()
+    }
+}
+
+
+impl fmt::Display for StepVerdict {
+
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f,"`{:?}` cert",self.step)?;
+        if let Some(ref signer) = self.signer {
+            let known = if self.known { "known" } else { "unknown" };
+            write!(f," from {} {} `{:?}`",known,self.role,signer)?;
+        }
+        if let Some(ref err) = self.cert {
+            write!(f,": {}",err)?;
+        }
+        for err in self.errors.iter() {
+            write!(f,": {}",err)?;
+        }
+        if self.accusation.is_some() {
+            f.write_str(" (accused)")?;
+        }
+        Ok(())
+    }
+}
+
+
+/// judgement passed on an entire message.
+///
+/// produced by `JudgeVisitor::finish`.
+///
+#[derive(Debug,Clone,PartialEq,Eq)]
+pub struct Verdict {
+    /// visited message bytes
+    pub bytes: Vec<u8>,
+
+    /// errors attributed to the message payload
+    pub payload: Vec<JudgeError>,
+
+    /// per-cert verdicts, in order of visitation
+    pub steps: Vec<StepVerdict>,
+
+    /// address & code of each verifier which disputed the payload
+    pub disputes: Vec<(Address,Code)>,
+}
+
+
+impl Verdict {
+
+    /// check if payload & all certs passed all checks.
+    pub fn is_ok(&self) -> bool {
+        self.payload.is_empty() && self.steps.iter().all(|step| step.is_ok())
+    }
+
+    /// get verdicts of all certs which failed one or more checks.
+    pub fn failures(&self) -> Vec<&StepVerdict> {
+        self.steps.iter().filter(|step| !step.is_ok()).collect()
+    }
+
+    /// get all accusations raised during judgement.
+    pub fn accusations(&self) -> Vec<&Accuse> {
+        self.steps.iter().filter_map(|step| step.accusation.as_ref()).collect()
+    }
+
+    /// consume verdict, returning all accusations raised during judgement.
+    pub fn into_accusations(self) -> Vec<Accuse> {
+        self.steps.into_iter().filter_map(|step| step.accusation).collect()
+    }
+}
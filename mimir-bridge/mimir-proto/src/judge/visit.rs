@@ -5,9 +5,11 @@ use visit::{
     CertVisitor,
     CertError,
 };
-use judge::{Accuse,JudgeError};
-use mimir_types::Address;
-use message::Payload;
+use judge::{Accuse,JudgeError,Verdict,StepVerdict};
+use mimir_types::{Address,H256};
+use message::{Payload,Checkpoint,CircuitSpec,Code,Role,STEP};
+use route::ProofError;
+use seal::Domain;
 use message::cert::{
     Oracle,
     Notary,
@@ -22,8 +24,11 @@ use message::cert::{
 ///
 #[derive(Debug)]
 pub struct JudgeVisitor<'v,B> {
-    /// buffer of potential accusations
-    accuse: Vec<Accuse>,
+    /// verdicts of visited certs
+    steps: Vec<StepVerdict>,
+
+    /// errors attributed to the message payload
+    payload: Vec<JudgeError>,
 
     /// inner visitor which performs cryptographic checks
     inner: CertVisitor,
@@ -34,8 +39,15 @@ pub struct JudgeVisitor<'v,B> {
     /// verifies set membership for block
     block: B,
 
-    /// indicates truth of message payload
-    truth: bool,
+    /// disputes raised by verifiers
+    disputes: Vec<(Address,Code)>,
+
+    /// destinations of the current verify circuit, and
+    /// whether or not each has signed.
+    dests: Vec<(Address,bool)>,
+
+    /// spec dictating the number of destinations per route
+    spec: CircuitSpec,
     
     /// result indicating whether an error
     /// has been seen
@@ -43,39 +55,77 @@ pub struct JudgeVisitor<'v,B> {
 }
 
 
-impl<'v,B> Default for JudgeVisitor<'v,B> where B: Default {
-
-    fn default() -> Self { Self::new(Default::default()) } 
-}
-
-
 impl<'v,B> JudgeVisitor<'v,B> {
 
-    /// instantiate new judge instance
-    pub fn new(block: B) -> Self {
-        let (accuse,inner,state) = Default::default();
-        let (truth,seen) = (true,Ok(()));
-        Self { accuse, inner, state, block, truth, seen }
+    /// instantiate new judge instance which verifies
+    /// signatures under the supplied domain.
+    pub fn new(block: B, domain: Domain) -> Self {
+        let (steps,payload,state,disputes,dests,spec) = Default::default();
+        let (inner,seen) = (CertVisitor::new(domain),Ok(()));
+        Self { steps, payload, inner, state, block, disputes, dests, spec, seen }
     }
 
-    /// check if internal state-tracking values
-    /// are all in thier `ok` cases.
+    /// instantiate new judge instance which verifies signatures
+    /// under the supplied domain, and expects routes of the width
+    /// dictated by the supplied spec (the default spec otherwise).
+    pub fn with_spec(block: B, spec: &CircuitSpec, domain: Domain) -> Self {
+        let mut judge = Self::new(block,domain);
+        judge.spec = spec.clone();
+        judge
+    }
+
+    /// check if no error has been seen (disputes raised by
+    /// verifiers are recorded separately, see `disputes`).
     pub fn is_ok(&self) -> bool {
-        self.truth && self.seen.is_ok()
+        self.seen.is_ok()
     }
 
-    /// consume visitor, returning collected
-    /// message bytes and potential accusations.
-    pub fn finish(self) -> (Vec<u8>,Vec<Accuse>) {
+    /// get address & code of each verifier which
+    /// disputed the message payload.
+    pub fn disputes(&self) -> &[(Address,Code)] { &self.disputes }
+
+    /// consume visitor, returning collected message
+    /// bytes and the verdict of each visited cert.
+    pub fn finish(self) -> Verdict {
         let bytes = self.inner.finish();
-        (bytes,self.accuse)
+        let JudgeVisitor { payload, steps, disputes, .. } = self;
+        Verdict { bytes, payload, steps, disputes }
+    }
+
+    /// begin the verdict of a newly visited cert.
+    fn visit_step(&mut self, step: STEP) {
+        self.steps.push(StepVerdict::new(step));
+    }
+
+    /// record the signer of the current cert.
+    fn visit_signer(&mut self, addr: &Address, known: bool) {
+        if let Some(step) = self.steps.last_mut() {
+            step.signer = Some(*addr);
+            step.known = known;
+        }
     }
 
-    /// visit an error.  updates internal error tracker
-    /// if an earlier error is not already set.
+    /// record an accusation against the signer of the current cert.
+    fn visit_accusation(&mut self, acc: Accuse) {
+        if let Some(step) = self.steps.last_mut() {
+            step.accusation = Some(acc);
+        }
+    }
+
+    /// visit an error.  attributes error to the current cert (or
+    /// the payload), and updates internal error tracker if an
+    /// earlier error is not already set.
     fn visit_error<E>(&mut self, err: &E) where E: Into<JudgeError> + Clone {
+        let err = err.clone().into();
         if self.seen.is_ok() {
-            self.seen = Err(err.clone().into())
+            self.seen = Err(err);
+        }
+        match self.steps.last_mut() {
+            Some(step) => match err {
+                JudgeError::Cert { err } if step.cert.is_none() => step.cert = Some(err),
+                err => step.errors.push(err),
+            },
+            None => self.payload.push(err),
         }
     }
 }
@@ -83,8 +133,8 @@ impl<'v,B> JudgeVisitor<'v,B> {
 
 impl<'v,B> JudgeVisitor<'v,B> where B: BlockVisitor<Out=bool> {
 
-    fn validator_exists(&mut self, addr: &Address) -> bool {
-        let exists = self.block.visit_validator(addr);
+    fn entity_exists(&mut self, role: Role, addr: &Address) -> bool {
+        let exists = self.block.visit_entity(role,addr);
         if !exists { self.visit_error(addr); }
         exists
     }
@@ -100,6 +150,45 @@ impl<'v,B> JudgeVisitor<'v,B> where B: BlockVisitor<Out=bool> {
         if !exists { self.visit_error(addr); }
         exists
     }
+
+    fn block_matches(&mut self, payload: &Payload) -> bool {
+        let fresh = self.block.visit_number(&payload.number);
+        // the hash of the pinned block can only be checked if its state is
+        // held (see `BlockRegistry`).  payloads pinned to any other block
+        // within the window are accepted by number alone.
+        let verified = match self.block.get_number() {
+            Some(number) if number != &payload.number => true,
+            _ => self.block.visit_hash(&payload.hash),
+        };
+        let matches = fresh && verified;
+        if !matches {
+            let (number,hash) = (payload.number,payload.hash);
+            self.visit_error(&JudgeError::Block { number, hash });
+        }
+        matches
+    }
+
+    fn dest_matches(&mut self, addr: &Address) -> bool {
+        let pending = self.dests.iter()
+            .position(|&(dest,signed)| dest == *addr && !signed);
+        if let Some(index) = pending {
+            self.dests[index].1 = true;
+            return true;
+        }
+        let addr = *addr;
+        if self.dests.iter().any(|&(dest,_)| dest == addr) {
+            self.visit_error(&JudgeError::Duplicate { addr });
+        } else {
+            self.visit_error(&JudgeError::Unrouted { addr });
+        }
+        false
+    }
+
+    fn root_matches(&mut self, root: &H256) -> bool {
+        let matches = self.block.visit_root(root);
+        if !matches { self.visit_error(&JudgeError::Root { root: *root }); }
+        matches
+    }
 }
 
 
@@ -110,26 +199,34 @@ impl<'v,B> MessageVisitor<'v> for JudgeVisitor<'v,B> where B: BlockVisitor<Out=b
 
     /// visit a message payload.
     fn visit_payload(&mut self, payload: &'v Payload) -> Self::Out {
+        // certs are judged against the block to which the payload is pinned.
+        self.block.select(&payload.number);
         let _ = self.inner.visit_payload(payload)
             .map_err(|e| { self.visit_error(&e); e });
+        // an error here results in accusation of the oracle
+        // which signs off on the payload.
+        let _ = self.block_matches(payload);
         self.state.visit_payload(payload);
     }
 
     /// visit an oracle cert.
     fn visit_oracle(&mut self, cert: &'v Oracle) -> Self::Out {
+        self.visit_step(STEP::ORACLE);
         let preprocess = self.inner.visit_oracle(cert)
             .map_err(|e| { self.visit_error(&e); e });
         match preprocess {
             // match cases where address is known
-            Ok(Some(addr)) | Err(CertError::Flag { addr }) => {
-                // if validator exists and state is bad,
+            Ok(Some(addr)) | Err(CertError::Flag { addr, .. }) => {
+                // if oracle exists and state is bad,
                 // attempt to build accusation.
-                if self.validator_exists(&addr) && !self.is_ok() { 
+                let known = self.entity_exists(Role::Oracle,&addr);
+                self.visit_signer(&addr,known);
+                if known && !self.is_ok() { 
                     if let Some(inner) = self.state.as_msg() {
                         let cert = cert.clone();
                         let seal = None;
                         let acc = Accuse::Oracle { inner, cert, seal };
-                        self.accuse.push(acc);
+                        self.visit_accusation(acc);
                     } else {
                         // this state is not necessarily an error... but
                         // shouldn't happen during normal use either.
@@ -146,12 +243,14 @@ impl<'v,B> MessageVisitor<'v> for JudgeVisitor<'v,B> where B: BlockVisitor<Out=b
 
     /// visit an notary cert.
     fn visit_notary(&mut self, cert: &'v Notary) -> Self::Out { 
+        self.visit_step(STEP::NOTARY);
         let preprocess = self.inner.visit_notary(cert)
             .map_err(|e| { self.visit_error(&e); e });
         match preprocess {
             // match cases where address is known
-            Ok(Some(addr)) | Err(CertError::Flag { addr }) => {
-                let _ = self.notary_exists(&addr);
+            Ok(Some(addr)) | Err(CertError::Flag { addr, .. }) => {
+                let known = self.notary_exists(&addr);
+                self.visit_signer(&addr,known);
             },
             // otherwise, ignore
             _ => { }
@@ -162,6 +261,7 @@ impl<'v,B> MessageVisitor<'v> for JudgeVisitor<'v,B> where B: BlockVisitor<Out=b
  
     /// visit a hash-blinding cert.   
     fn visit_blind(&mut self, cert: &'v Blind) -> Self::Out {
+        self.visit_step(STEP::BLIND);
         let _ = self.inner.visit_blind(cert)
             .map_err(|e| { self.visit_error(&e); e });
         self.state.visit_blind(cert);
@@ -169,6 +269,7 @@ impl<'v,B> MessageVisitor<'v> for JudgeVisitor<'v,B> where B: BlockVisitor<Out=b
  
     /// visit a blind-clearing cert.   
     fn visit_clear(&mut self, cert: &'v Clear) -> Self::Out {
+        self.visit_step(STEP::CLEAR);
         let _ = self.inner.visit_clear(cert)
             .map_err(|e| { self.visit_error(&e); e });
         self.state.visit_clear(cert);
@@ -176,24 +277,37 @@ impl<'v,B> MessageVisitor<'v> for JudgeVisitor<'v,B> where B: BlockVisitor<Out=b
  
     /// visit a routing cert.   
     fn visit_route(&mut self, cert: &'v Route) -> Self::Out {
+        self.visit_step(STEP::ROUTE);
         let preprocess = self.inner.visit_route(cert)
             .map_err(|e| { self.visit_error(&e); e });
         match preprocess {
             // match cases where address is known
-            Ok(Some(addr)) | Err(CertError::Flag { addr }) => {
-                // iteratively pass route to `validator_exists` check.
+            Ok(Some(addr)) | Err(CertError::Flag { addr, .. }) | Err(CertError::Route { addr, .. }) => {
+                // router must select exactly as many destinations as the spec dictates.
+                if cert.val.len() != self.spec.dests() {
+                    let error = ProofError::Path;
+                    self.visit_error(&CertError::Route { addr, error });
+                }
+                // iteratively pass route to `entity_exists` check.
                 for address in cert.val.iter() {
                     // can ignore result here.  inner state will be
                     // `err` case if any validators did not exist.
-                    let _ = self.validator_exists(&address);
+                    let _ = self.entity_exists(Role::Verifier,&address);
                 }
+                // proofs have been checked against the cert's own root
+                // by the inner visitor; ensure root matches validator set.
+                let _ = self.root_matches(&cert.root);
+                // subsequent verify certs must come from these destinations.
+                self.dests = cert.val.iter().map(|addr| (*addr,false)).collect();
                 // if router exists and state is bad,
                 // attempt to build accusation.
-                if self.router_exists(&addr) && !self.is_ok() { 
+                let known = self.router_exists(&addr);
+                self.visit_signer(&addr,known);
+                if known && !self.is_ok() { 
                     if let Some(inner) = self.state.as_msg() {
                         let cert = cert.clone();
                         let acc = Accuse::Route { inner, cert };
-                        self.accuse.push(acc);
+                        self.visit_accusation(acc);
                     } else {
                         // this state is not necessarily an error... but
                         // shouldn't happen during normal use either.
@@ -210,18 +324,28 @@ impl<'v,B> MessageVisitor<'v> for JudgeVisitor<'v,B> where B: BlockVisitor<Out=b
  
     /// visit a verification cert.   
     fn visit_verify(&mut self, cert: &'v Verify) -> Self::Out {
+        self.visit_step(STEP::VERIFY);
         let preprocess = self.inner.visit_verify(cert)
             .map_err(|e| { self.visit_error(&e); e });
         match preprocess {
             // match cases where address is known
-            Ok(Some(addr)) | Err(CertError::Flag { addr }) => {
-                // if validator exists and state is bad,
+            Ok(Some(addr)) | Err(CertError::Flag { addr, .. }) => {
+                // verifier must be a routed destination which has not
+                // yet signed.  can ignore result here, as with existence.
+                let _ = self.dest_matches(&addr);
+                // record any dispute raised by the validator.
+                if !cert.val.is_ok() {
+                    self.disputes.push((addr,cert.val));
+                }
+                // if verifier exists and state is bad,
                 // attempt to build accusation.
-                if self.validator_exists(&addr) && !self.is_ok() { 
+                let known = self.entity_exists(Role::Verifier,&addr);
+                self.visit_signer(&addr,known);
+                if known && !self.is_ok() { 
                     if let Some(inner) = self.state.as_msg() {
                         let cert = cert.clone();
                         let acc = Accuse::Verify { inner, cert };
-                        self.accuse.push(acc);
+                        self.visit_accusation(acc);
                     } else {
                         // this state is not necessarily an error... but
                         // shouldn't happen during normal use either.
@@ -235,6 +359,579 @@ impl<'v,B> MessageVisitor<'v> for JudgeVisitor<'v,B> where B: BlockVisitor<Out=b
         // update interval message state record
         self.state.visit_verify(cert);
     }
+
+    /// visit a checkpoint of collapsed circuits.
+    ///
+    /// checkpoints are judged as notary certs, since the collapsed
+    /// certs are vouched for by the checkpointing notary.  the root
+    /// can only be checked against the collapsed history (see
+    /// `Checkpoint::accuse`), but a checkpoint which does not end
+    /// on a circuit boundary cannot match any history.
+    fn visit_checkpoint(&mut self, checkpoint: &'v Checkpoint) -> Self::Out {
+        self.visit_step(STEP::NOTARY);
+        let preprocess = self.inner.visit_checkpoint(checkpoint)
+            .map_err(|e| { self.visit_error(&e); e });
+        let bounded = self.spec.is_boundary(checkpoint.steps as usize);
+        if !bounded {
+            self.visit_error(&JudgeError::Checkpoint { steps: checkpoint.steps });
+        }
+        if let Ok(Some(addr)) = preprocess {
+            let known = self.notary_exists(&addr);
+            self.visit_signer(&addr,known);
+            if known && !bounded {
+                // the message state preceeding a checkpoint serves as
+                // history, which cannot match an unbounded checkpoint.
+                if let Some(inner) = self.state.as_msg() {
+                    let checkpoint = *checkpoint;
+                    let acc = Accuse::Checkpoint { inner, checkpoint };
+                    self.visit_accusation(acc);
+                } else {
+                    warn!("[judge-checkpoint] missing message payload");
+                }
+            }
+        }
+        // update interval message state record
+        self.state.visit_checkpoint(checkpoint);
+    }
 }
 
 
+
+
+#[cfg(test)]
+mod tests {
+    use mimir_crypto::{Keccak256,Address};
+    use mimir_types::H256;
+    use mimir_crypto::secp256k1::Signer;
+    use judge::{JudgeVisitor,JudgeError,Accuse};
+    use visit::{self,BlockVisitor,BlockState,BlockRegistry,ByteVisitor,CertError,EmptyVisitor,MessageVisitor,Entity};
+    use message::{Message,Payload,Checkpoint,CircuitSpec,Code,Role,STEP};
+    use route::{RouteTree,NodeHash};
+    use seal::{Sealer,Domain,Version,Tag};
+    use testkit::{Fixture,Corruption};
+    use rand;
+
+    /// signing domain of the test deployment.
+    fn domain() -> Domain {
+        Domain::new(1u64.into(),Address::default())
+    }
+
+    /// payload declaring the signing version of the test deployment.
+    fn payload() -> Payload {
+        Payload { version: domain().version, ..Default::default() }
+    }
+
+    /// build a message with one oracle & one verify circuit, routed
+    /// via a tree of the supplied validators.
+    fn routed_message(validators: &[Signer], tree: &RouteTree<Address>) -> Message {
+        disputed_message(validators,tree,Code::Ok)
+    }
+
+    /// build a routed message, with all verifiers raising the supplied code.
+    fn disputed_message(validators: &[Signer], tree: &RouteTree<Address>, code: Code) -> Message {
+        build_message(validators,tree,code,|dests| dests.to_vec())
+    }
+
+    /// build a routed message, with verify certs signed by the
+    /// validators selected from the routed destinations.
+    fn build_message<F>(validators: &[Signer], tree: &RouteTree<Address>, code: Code, select: F) -> Message
+            where F: Fn(&[Address]) -> Vec<Address> {
+        let spec = CircuitSpec::default();
+        let mut message = Message::new(payload());
+        let sealer = validators[0].clone();
+        for _ in 0..2 {
+            let clear: [u8;32] = rand::random();
+            message.blind.push(Keccak256::hash(&clear).into());
+            if message.route.is_empty() && message.verify.is_empty() {
+                let cert = sealer.seal_oracle(&spec,&domain(),&message);
+                message.verify.push(cert);
+            } else {
+                let cert = sealer.seal_route(&spec,&domain(),&message,tree).unwrap();
+                message.route.push(cert.clone());
+                for dest in select(&cert.val).iter() {
+                    let signer = validators.iter()
+                        .find(|signer| signer.address() == *dest).unwrap();
+                    let cert = signer.seal_verify(&spec,&domain(),&message,code);
+                    message.verify.push(cert);
+                }
+            }
+            let cert = sealer.seal_notary(&spec,&domain(),&message);
+            message.notary.push(cert);
+            message.blind.push(clear.into());
+        }
+        message
+    }
+
+    fn judge<B: BlockVisitor<Out=bool>>(message: &Message, block: B) -> Vec<Accuse> {
+        let mut judge = JudgeVisitor::new(block,domain());
+        visit::apply(&CircuitSpec::default(),&mut judge,message);
+        judge.finish().into_accusations()
+    }
+
+    /// build a message with a single oracle circuit.
+    fn oracle_message(oracle: &Signer, payload: Payload) -> Message {
+        let spec = CircuitSpec::default();
+        let mut message = Message::new(payload);
+        let clear: [u8;32] = rand::random();
+        message.blind.push(Keccak256::hash(&clear).into());
+        let cert = oracle.seal_oracle(&spec,&domain(),&message);
+        message.verify.push(cert);
+        let cert = oracle.seal_notary(&spec,&domain(),&message);
+        message.notary.push(cert);
+        message.blind.push(clear.into());
+        message
+    }
+
+    fn setup() -> (Vec<Signer>,RouteTree<Address>,BlockState) {
+        let validators: Vec<Signer> = (0..5).map(|_| rand::random()).collect();
+        let tree = RouteTree::new(validators.iter().map(|v| v.address())).unwrap();
+        let block = BlockState::new()
+            .validators(validators.iter().map(|v| v.address()).collect());
+        (validators,tree,block)
+    }
+
+    #[test]
+    fn route_ok() {
+        let (validators,tree,block) = setup();
+        let message = routed_message(&validators,&tree);
+        assert_eq!(message.route.len(),1);
+        assert_eq!(block.root.as_ref().map(|root| root.0),Some(*tree.root()));
+        // judged bytes are the same bytes which seals commit to.
+        let mut visitor = JudgeVisitor::new(block.clone(),domain());
+        visit::apply(&CircuitSpec::default(),&mut visitor,&message);
+        let mut bytes = ByteVisitor::default();
+        visit::apply(&CircuitSpec::default(),&mut bytes,&message);
+        let verdict = visitor.finish();
+        assert!(verdict.is_ok());
+        assert_eq!(verdict.bytes,bytes.finish());
+        assert!(judge(&message,block).is_empty());
+    }
+
+    #[test]
+    fn route_width() {
+        let (validators,tree,block) = setup();
+        let spec = CircuitSpec::default();
+        let mut message = oracle_message(&validators[0],payload());
+        message.blind.push(rand::random());
+        // routers may not select fewer or more destinations than the spec dictates.
+        for &dests in [1,spec.dests() + 1].iter() {
+            let mut verify = vec![STEP::BLIND,STEP::ROUTE];
+            verify.extend(vec![STEP::VERIFY;dests]);
+            verify.extend(vec![STEP::NOTARY,STEP::CLEAR]);
+            let other = CircuitSpec::new(spec.oracle().to_vec(),verify,dests).unwrap();
+            let mut routed = message.clone();
+            let cert = validators[0].seal_route(&other,&domain(),&message,&tree).unwrap();
+            assert_eq!(cert.val.len(),dests);
+            routed.route.push(cert);
+            let mut judge = JudgeVisitor::with_spec(block.clone(),&spec,domain());
+            visit::apply(&spec,&mut judge,&routed);
+            assert!(!judge.is_ok());
+            let accusations = judge.finish().into_accusations();
+            assert_eq!(accusations.len(),1);
+            match accusations[0] {
+                Accuse::Route { ref cert, .. } => assert_eq!(cert,&routed.route[0]),
+                ref other => panic!("unexpected accusation: {:?}",other),
+            }
+            // the route is acceptable to a judge of the router's own spec.
+            let mut judge = JudgeVisitor::with_spec(block.clone(),&other,domain());
+            visit::apply(&other,&mut judge,&routed);
+            assert!(judge.is_ok());
+        }
+    }
+
+    #[test]
+    fn route_bad_proof() {
+        let (validators,tree,block) = setup();
+        let mut message = routed_message(&validators,&tree);
+        message.route[0].path[0].push(NodeHash::Left(rand::random()));
+        let accusations = judge(&message,block);
+        assert!(!accusations.is_empty());
+        match accusations[0] {
+            Accuse::Route { .. } => { },
+            ref other => panic!("unexpected accusation: {:?}",other),
+        }
+    }
+
+    #[test]
+    fn route_unknown_root() {
+        let (validators,tree,_) = setup();
+        let message = routed_message(&validators,&tree);
+        let block = BlockState::new().root(rand::random());
+        let accusations = judge(&message,block);
+        // route is accused, as are the verifiers which signed off on it.
+        assert_eq!(accusations.len(),3);
+        match accusations[0] {
+            Accuse::Route { ref cert, .. } => assert_eq!(cert,&message.route[0]),
+            ref other => panic!("unexpected accusation: {:?}",other),
+        }
+    }
+
+    #[test]
+    fn block_ok() {
+        let oracle: Signer = rand::random();
+        let payload = Payload { number: 100u64.into(), hash: rand::random(), ..payload() };
+        let message = oracle_message(&oracle,payload.clone());
+        let block = BlockState::new().number(payload.number).hash(payload.hash);
+        assert!(judge(&message,block).is_empty());
+        // earlier blocks within the window are accepted by number if no hash is known.
+        let block = BlockState::new().number(103u64.into()).window(3);
+        assert!(judge(&message,block).is_empty());
+    }
+
+    #[test]
+    fn block_stale() {
+        let oracle: Signer = rand::random();
+        let payload = Payload { number: 100u64.into(), hash: rand::random(), ..payload() };
+        let message = oracle_message(&oracle,payload.clone());
+        for &(number,window) in [(104u64,3),(101,0),(99,8)].iter() {
+            let block = BlockState::new().number(number.into()).window(window);
+            let mut judge = JudgeVisitor::new(block,domain());
+            visit::apply(&CircuitSpec::default(),&mut judge,&message);
+            let expect = JudgeError::Block { number: payload.number, hash: payload.hash };
+            assert_eq!(judge.seen,Err(expect));
+            let verdict = judge.finish();
+            assert_eq!(verdict.payload,vec![expect]);
+            let accusations = verdict.accusations();
+            assert_eq!(accusations.len(),1);
+            match *accusations[0] {
+                Accuse::Oracle { ref cert, .. } => assert_eq!(cert,&message.verify[0]),
+                ref other => panic!("unexpected accusation: {:?}",other),
+            }
+        }
+    }
+
+    #[test]
+    fn block_wrong_hash() {
+        let oracle: Signer = rand::random();
+        let payload = Payload { number: 100u64.into(), hash: rand::random(), ..payload() };
+        let message = oracle_message(&oracle,payload.clone());
+        let block = BlockState::new().number(payload.number).hash(rand::random()).window(3);
+        let accusations = judge(&message,block);
+        assert_eq!(accusations.len(),1);
+        // hashes of earlier blocks within the window cannot be checked.
+        let block = BlockState::new().number(103u64.into()).hash(rand::random()).window(3);
+        assert!(judge(&message,block).is_empty());
+        // ...unless the pinned block is known to a registry.
+        let mut registry = BlockRegistry::default();
+        registry.insert(BlockState::new().number(payload.number).hash(payload.hash));
+        registry.insert(BlockState::new().number(103u64.into()).hash(rand::random()).window(3));
+        assert!(judge(&message,registry.clone()).is_empty());
+        let mut registry = BlockRegistry::default();
+        registry.insert(BlockState::new().number(payload.number).hash(rand::random()));
+        registry.insert(BlockState::new().number(103u64.into()).hash(rand::random()).window(3));
+        assert_eq!(judge(&message,registry).len(),1);
+    }
+
+    #[test]
+    fn checkpoint_boundary() {
+        let spec = CircuitSpec::default();
+        let notary: Signer = rand::random();
+        let mut message = Message::new(payload());
+        // a notary signs a checkpoint which ends mid-circuit.
+        let mut checkpoint = Checkpoint { steps: 7, ..Default::default() };
+        let mut bytes = ByteVisitor::default();
+        bytes.visit_payload(&message.payload);
+        let mut hasher = Keccak256::default();
+        hasher.absorb(bytes.as_bytes());
+        visit::absorb_checkpoint(&mut hasher,&checkpoint);
+        checkpoint.sig = notary.sign(&domain().hash(Tag::Checkpoint,&hasher.finish()));
+        message.checkpoint = Some(checkpoint);
+        let mut judge = JudgeVisitor::new(EmptyVisitor,domain());
+        visit::apply(&spec,&mut judge,&message);
+        let verdict = judge.finish();
+        assert_eq!(verdict.steps.len(),1);
+        assert_eq!(verdict.steps[0].signer,Some(notary.address()));
+        assert_eq!(verdict.steps[0].errors,vec![JudgeError::Checkpoint { steps: 7 }]);
+        // the accusation is backed by the checkpoint's failure to match its history.
+        match verdict.steps[0].accusation {
+            Some(ref acc @ Accuse::Checkpoint { .. }) => {
+                let inner = Message::new(message.payload.clone());
+                assert_eq!(checkpoint.accuse(&spec,&inner).as_ref(),Some(acc));
+            },
+            ref other => panic!("unexpected accusation: {:?}",other),
+        }
+    }
+
+    #[test]
+    fn block_window() {
+        let oracle: Signer = rand::random();
+        let payload = Payload { number: 100u64.into(), hash: rand::random(), ..payload() };
+        let message = oracle_message(&oracle,payload.clone());
+        let mut registry = BlockRegistry::default();
+        registry.insert(BlockState::new().number(102u64.into()).hash(rand::random()).window(3));
+        registry.insert(BlockState::new().number(103u64.into()).hash(rand::random()).window(3));
+        // unheld blocks within the window of the latest state are accepted by number.
+        assert!(judge(&message,registry.clone()).is_empty());
+        // held blocks are checked by hash, regardless of the window.
+        registry.insert(BlockState::new().number(payload.number).hash(rand::random()).window(3));
+        assert_eq!(judge(&message,registry.clone()).len(),1);
+        registry.insert(BlockState::new().number(payload.number).hash(payload.hash).window(3));
+        assert!(judge(&message,registry.clone()).is_empty());
+        // unheld blocks outside of the window are rejected.
+        let mut registry = BlockRegistry::default();
+        registry.insert(BlockState::new().number(104u64.into()).hash(rand::random()).window(3));
+        assert_eq!(judge(&message,registry).len(),1);
+    }
+
+    #[test]
+    fn judge_generated() {
+        let fixture = Fixture::new(CircuitSpec::default(),domain());
+        for circuits in 0..4 {
+            let message = fixture.message(circuits);
+            let verdict = fixture.judge(&message);
+            assert!(verdict.is_ok());
+            assert_eq!(verdict.steps.len(),message.step_index());
+            assert!(verdict.steps.iter().all(|step| step.signer.is_none() || step.known));
+            // messages judged against a foreign block are rejected.
+            let mut judge = JudgeVisitor::new(Fixture::default().block(),domain());
+            visit::apply(&fixture.spec,&mut judge,&message);
+            assert!(!judge.is_ok());
+        }
+    }
+
+    #[test]
+    fn judge_corrupted() {
+        let fixture = Fixture::new(CircuitSpec::default(),domain());
+        let corruptions = Corruption::all().iter()
+            .filter(|corruption| **corruption != Corruption::Truncate);
+        for corruption in corruptions {
+            for circuits in 0..3 {
+                let verdict = fixture.judge(&fixture.corrupted(circuits,*corruption));
+                assert!(!verdict.is_ok(),"{:?} not rejected",corruption);
+                assert!(!verdict.failures().is_empty() || !verdict.payload.is_empty());
+            }
+        }
+    }
+
+    #[test]
+    fn notary_unknown() {
+        let (oracle,notary): (Signer,Signer) = (rand::random(),rand::random());
+        let message = oracle_message(&oracle,payload());
+        // notaries are checked against the notary set (not the router set).
+        let block = BlockState::new().routers(vec![oracle.address()].into_iter().collect());
+        let mut judge = JudgeVisitor::new(block,domain());
+        visit::apply(&CircuitSpec::default(),&mut judge,&message);
+        assert!(judge.is_ok());
+        let block = BlockState::new().notaries(vec![notary.address()].into_iter().collect());
+        let mut judge = JudgeVisitor::new(block,domain());
+        visit::apply(&CircuitSpec::default(),&mut judge,&message);
+        assert_eq!(judge.seen,Err(JudgeError::from(oracle.address())));
+        let verdict = judge.finish();
+        let step = verdict.steps.iter().find(|step| step.step == STEP::NOTARY).unwrap();
+        assert!(!step.known);
+    }
+
+    #[test]
+    fn block_registry() {
+        let (validators,tree,block) = setup();
+        let message = routed_message(&validators,&tree);
+        let pinned = block.number(message.payload.number).hash(message.payload.hash);
+        // validator set changes in the block following the payload.
+        let (_,_,latest) = setup();
+        let latest = latest.number(1u64.into()).window(8);
+        let mut registry = BlockRegistry::default();
+        registry.insert(pinned.clone());
+        registry.insert(latest.clone());
+        assert!(!judge(&message,latest.clone()).is_empty());
+        let mut judge = JudgeVisitor::new(registry.clone(),domain());
+        visit::apply(&CircuitSpec::default(),&mut judge,&message);
+        assert!(judge.finish().is_ok());
+        // payloads pinned to evicted blocks are judged against the latest.
+        let mut registry = BlockRegistry::new(1);
+        registry.insert(pinned);
+        registry.insert(latest);
+        let mut judge = JudgeVisitor::new(registry,domain());
+        visit::apply(&CircuitSpec::default(),&mut judge,&message);
+        assert!(!judge.finish().is_ok());
+    }
+
+    #[test]
+    fn verify_dispute() {
+        let (validators,tree,block) = setup();
+        let message = disputed_message(&validators,&tree,Code::Timeout);
+        let mut judge = JudgeVisitor::new(block,domain());
+        visit::apply(&CircuitSpec::default(),&mut judge,&message);
+        // disputes are not errors on the part of the verifier.
+        assert!(judge.is_ok());
+        let expect: Vec<_> = message.route[0].val.iter()
+            .map(|addr| (*addr,Code::Timeout)).collect();
+        assert_eq!(judge.disputes(),&expect[..]);
+        let verdict = judge.finish();
+        assert_eq!(verdict.disputes,expect);
+        assert!(verdict.is_ok());
+        assert!(verdict.accusations().is_empty());
+    }
+
+    /// check verify certs signed by the selected validators, returning
+    /// the judge error & accused verifiers.
+    fn check_verifiers<F>(select: F) -> (Result<(),JudgeError>,Vec<Address>,Message)
+            where F: Fn(&[Signer],&[Address]) -> Vec<Address> {
+        let (validators,tree,block) = setup();
+        // ensure routed destinations are distinct.
+        let message = loop {
+            let message = build_message(&validators,&tree,Code::Ok,|dests| select(&validators,dests));
+            let dests = &message.route[0].val;
+            if dests[0] != dests[1] { break message; }
+        };
+        let selected = select(&validators,&message.route[0].val);
+        let mut judge = JudgeVisitor::new(block,domain());
+        visit::apply(&CircuitSpec::default(),&mut judge,&message);
+        let seen = judge.seen;
+        let accusations = judge.finish().into_accusations();
+        let accused = accusations.iter().map(|accusation| {
+            match *accusation {
+                Accuse::Verify { ref cert, .. } => {
+                    message.verify.iter().position(|c| c == cert)
+                        .map(|index| selected[index - 1]).unwrap()
+                },
+                ref other => panic!("unexpected accusation: {:?}",other),
+            }
+        }).collect();
+        (seen,accused,message)
+    }
+
+    #[test]
+    fn verifiers_swapped() {
+        // destinations may sign in any order.
+        let (seen,accused,_) = check_verifiers(|_,dests| vec![dests[1],dests[0]]);
+        assert_eq!(seen,Ok(()));
+        assert!(accused.is_empty());
+    }
+
+    #[test]
+    fn verifiers_duplicated() {
+        let (seen,accused,message) = check_verifiers(|_,dests| vec![dests[0],dests[0]]);
+        let addr = message.route[0].val[0];
+        assert_eq!(seen,Err(JudgeError::Duplicate { addr }));
+        assert_eq!(accused,vec![addr]);
+    }
+
+    #[test]
+    fn verifiers_foreign() {
+        let (seen,accused,message) = check_verifiers(|validators,dests| {
+            let foreign = validators.iter().map(|v| v.address())
+                .find(|addr| !dests.contains(addr)).unwrap();
+            vec![dests[0],foreign]
+        });
+        assert_eq!(accused.len(),1);
+        assert_eq!(seen,Err(JudgeError::Unrouted { addr: accused[0] }));
+        assert!(!message.route[0].val.contains(&accused[0]));
+    }
+
+    #[test]
+    fn verdict_steps() {
+        let (validators,tree,block) = setup();
+        let message = routed_message(&validators,&tree);
+        let mut judge = JudgeVisitor::new(block.clone(),domain());
+        visit::apply(&CircuitSpec::default(),&mut judge,&message);
+        let verdict = judge.finish();
+        let steps: Vec<_> = verdict.steps.iter().map(|step| step.step).collect();
+        assert_eq!(steps.len(),10);
+        assert_eq!(&steps[..],&(0..10).map(STEP::new).collect::<Vec<_>>()[..]);
+        for step in verdict.steps.iter() {
+            assert_eq!(step.role,Role::of_step(step.step));
+            match step.step {
+                STEP::BLIND | STEP::CLEAR => assert_eq!(step.signer,None),
+                _ => assert!(step.signer.is_some() && step.known),
+            }
+        }
+        assert!(verdict.is_ok() && verdict.failures().is_empty());
+        // a foreign verifier is attributed to the offending cert only.
+        let foreign: Signer = rand::random();
+        let mut signers = validators.clone();
+        signers.push(foreign.clone());
+        let message = build_message(&signers,&tree,Code::Ok,|dests| vec![dests[0],foreign.address()]);
+        let mut judge = JudgeVisitor::new(block,domain());
+        visit::apply(&CircuitSpec::default(),&mut judge,&message);
+        let verdict = judge.finish();
+        assert!(!verdict.is_ok());
+        let failures = verdict.failures();
+        assert_eq!(failures.len(),1);
+        assert_eq!(failures[0].step,STEP::VERIFY);
+        assert_eq!(failures[0].signer,Some(foreign.address()));
+        assert!(!failures[0].known);
+        let addr = foreign.address();
+        assert_eq!(failures[0].errors,vec![JudgeError::Unrouted { addr },JudgeError::Entity { addr }]);
+    }
+
+    #[test]
+    fn role_lookup() {
+        let (validators,tree,_) = setup();
+        let oracle = validators[0].address();
+        // the first validator may only act as an oracle, the rest only as verifiers.
+        let block = validators[1..].iter()
+            .fold(BlockState::new().entity(Entity::new(oracle,Role::Oracle)),|block,verifier| {
+                block.entity(Entity::new(verifier.address(),Role::Verifier))
+            })
+            .root(H256(*tree.root()));
+        let message = routed_message(&validators,&tree);
+        let mut judge = JudgeVisitor::new(block.clone(),domain());
+        visit::apply(&CircuitSpec::default(),&mut judge,&message);
+        let verdict = judge.finish();
+        for step in verdict.steps.iter() {
+            match step.step {
+                STEP::ORACLE => assert!(step.known && step.is_ok()),
+                STEP::VERIFY => {
+                    let cross = step.signer == Some(oracle);
+                    assert_eq!(step.known,!cross);
+                    assert_eq!(step.errors.contains(&JudgeError::Entity { addr: oracle }),cross);
+                },
+                _ => { },
+            }
+        }
+        // verifiers may not sign off on payloads as oracles.
+        let message = oracle_message(&validators[1],payload());
+        let mut judge = JudgeVisitor::new(block,domain());
+        visit::apply(&CircuitSpec::default(),&mut judge,&message);
+        let verdict = judge.finish();
+        let failures = verdict.failures();
+        assert_eq!(failures.len(),1);
+        assert_eq!(failures[0].step,STEP::ORACLE);
+        assert!(!failures[0].known);
+        assert_eq!(failures[0].errors,vec![JudgeError::Entity { addr: validators[1].address() }]);
+    }
+
+    #[test]
+    fn signing_domains() {
+        let (validators,tree,block) = setup();
+        let message = routed_message(&validators,&tree);
+        // validator certs do not verify under a foreign domain.
+        let mut judge = JudgeVisitor::new(block.clone(),Domain::new(3u64.into(),Address::default()));
+        visit::apply(&CircuitSpec::default(),&mut judge,&message);
+        let verdict = judge.finish();
+        assert!(!verdict.is_ok());
+        let validated = verdict.steps.iter()
+            .filter(|step| step.step == STEP::ORACLE || step.step == STEP::VERIFY);
+        for step in validated {
+            assert!(step.signer.is_some() && !step.known);
+        }
+        // nor are messages of an unaccepted version verified at all.
+        let mut judge = JudgeVisitor::new(block.clone(),Domain::legacy());
+        visit::apply(&CircuitSpec::default(),&mut judge,&message);
+        let verdict = judge.finish();
+        let err = CertError::Version { version: Version::V1 };
+        assert!(verdict.payload.contains(&JudgeError::Cert { err }));
+        assert!(verdict.steps.iter().all(|step| step.signer.is_none()));
+        // legacy messages verify under the legacy version, if accepted.
+        let spec = CircuitSpec::default();
+        let mut message = Message::new(Payload::default());
+        message.blind.push(rand::random());
+        let cert = validators[0].seal_oracle(&spec,&Domain::legacy(),&message);
+        message.verify.push(cert);
+        let migrating = domain().accepting(Version::Legacy);
+        for &(domain,accepted) in [(Domain::legacy(),true),(domain(),false),(migrating,true)].iter() {
+            let mut judge = JudgeVisitor::new(block.clone(),domain);
+            visit::apply(&spec,&mut judge,&message);
+            let verdict = judge.finish();
+            assert_eq!(verdict.is_ok(),accepted);
+            assert_eq!(verdict.steps[1].signer.is_some(),accepted);
+        }
+        // the declared version is bound by the seals.
+        message.payload.version = Version::V1;
+        let mut judge = JudgeVisitor::new(block,migrating);
+        visit::apply(&spec,&mut judge,&message);
+        let verdict = judge.finish();
+        assert!(!verdict.is_ok());
+        assert!(verdict.steps[1].signer != Some(validators[0].address()));
+    }
+}
@@ -10,10 +10,16 @@ extern crate serde_derive;
 extern crate serde;
 #[macro_use]
 extern crate log;
+#[cfg(test)]
+extern crate serde_json;
+#[cfg(any(test,feature = "testkit"))]
+extern crate rand;
 
 pub mod message;
 pub mod visit;
 pub mod judge;
 pub mod route;
 pub mod seal;
+#[cfg(any(test,feature = "testkit"))]
+pub mod testkit;
 
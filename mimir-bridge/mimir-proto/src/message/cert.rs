@@ -6,7 +6,8 @@
 //! referring to these commitments as certs at some point...
 //!
 use mimir_types::{H256,Signature,Address};
-use message::DESTS;
+use message::{Code,STEP};
+use route::NodeHash;
 
 
 // -------------------------------------------------------------------
@@ -30,21 +31,23 @@ pub struct Notary {
 ///
 /// the route cert serves as cryptographic commitment  by the router 
 /// that some set of validators are the legitimate set as per the 
-/// trustless routing algorithm.
+/// trustless routing algorithm.  each destination is accompanied by
+/// a proof of traversal of the validator routing tree, allowing the
+/// selection to be checked against the known validator set.
 
-// NOTE: this is a placeholder payload, for testing purposes
-// only. proper implementation requires succinct proof of
-// validator graph traversal. ye be warned.  (merkle proof 
-// derivative w/ the existing trustless randomization proto
-// would do in a pinch tho)
-
-#[derive(Debug,Copy,Clone,Default,PartialEq,Eq,Serialize,Deserialize)]
+#[derive(Debug,Clone,Default,PartialEq,Eq,Serialize,Deserialize)]
 pub struct Route {
     /// signature of the router.
     pub sig: Signature,
 
+    /// root hash of the validator routing tree.
+    pub root: H256,
+
     /// validator destination addresses.
-    pub val: [Address;DESTS]
+    pub val: Vec<Address>,
+
+    /// traversal proof of each destination.
+    pub path: Vec<Vec<NodeHash>>
 }
 
 
@@ -59,10 +62,8 @@ pub struct Verify {
     /// signature of the validator.
     pub sig: Signature,
 
-    /// flag indicating validator's argument.
-    /// zero is okay/agreement case.  full set
-    /// of supported codes is TBD.
-    pub val: u8
+    /// code indicating validator's argument.
+    pub val: Code
 }
 
 
@@ -79,3 +80,49 @@ pub type Blind = H256;
 pub type Clear = H256;
 
 
+
+// -------------------------------------------------------------------
+// ------------------------------- any -------------------------------
+
+
+/// any single cert, tagged with the step which it fills.
+///
+/// used when appending certs to a message (see `Message::append_cert`),
+/// since several steps share the same underlying cert type.
+
+#[derive(Debug,Clone,PartialEq,Eq)]
+pub enum Cert {
+    /// oracle cert.
+    Oracle(Oracle),
+
+    /// notary cert.
+    Notary(Notary),
+
+    /// blinding hash.
+    Blind(Blind),
+
+    /// revealed blind seed.
+    Clear(Clear),
+
+    /// route cert.
+    Route(Route),
+
+    /// verify cert.
+    Verify(Verify),
+}
+
+
+impl Cert {
+
+    /// get the step which this cert fills.
+    pub fn step(&self) -> STEP {
+        match *self {
+            Cert::Oracle(_) => STEP::ORACLE,
+            Cert::Notary(_) => STEP::NOTARY,
+            Cert::Blind(_) => STEP::BLIND,
+            Cert::Clear(_) => STEP::CLEAR,
+            Cert::Route(_) => STEP::ROUTE,
+            Cert::Verify(_) => STEP::VERIFY,
+        }
+    }
+}
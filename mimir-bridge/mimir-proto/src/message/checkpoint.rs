@@ -0,0 +1,251 @@
+//! checkpointing of completed circuits.
+//!
+//! every cert signs all bytes of the message which preceed it, so each
+//! additional circuit makes every later seal & judge pass more costly.
+//! a checkpoint collapses a completed prefix of circuits into a single
+//! commitment, signed by a notary.  certs following the checkpoint sign
+//! the payload & checkpoint in place of the collapsed certs.
+//!
+//! the collapsed certs (the message "history") are returned when the
+//! checkpoint is applied, and may later be checked against it (see
+//! `Checkpoint::check_history`).  accusations against collapsed certs
+//! are built from the history, as it is an ordinary message, and a
+//! checkpoint which does not match its history may itself be accused
+//! (see `Checkpoint::accuse`).
+//!
+use mimir_types::{H256,Signature};
+use mimir_crypto::Keccak256;
+use message::cert::{Cert,Clear,Notary};
+use message::{Message,CircuitSpec,STEP};
+use visit::{self,ByteVisitor,SeedVisitor};
+use judge::Accuse;
+
+
+simple_error!(
+    CheckpointError, "error indicating a checkpoint which does not match its history",
+    Steps => "checkpoint does not end on a circuit boundary within the message",
+    Root => "history does not match checkpoint root",
+    Seed => "history does not match checkpoint seed",
+);
+
+
+/// commitment to a completed prefix of circuits, generated
+/// by the mimir `notary` microservice.
+///
+/// the `clear` & `seal` of the final collapsed circuit are retained
+/// in order to seed routing of the circuit which follows.
+///
+#[derive(Debug,Copy,Clone,Default,PartialEq,Eq,Serialize,Deserialize)]
+pub struct Checkpoint {
+    /// number of steps collapsed into the checkpoint.
+    pub steps: u32,
+
+    /// `keccak-256` hash of the collapsed message bytes.
+    pub root: H256,
+
+    /// revealed blind seed of the final collapsed circuit.
+    pub clear: Clear,
+
+    /// notary cert of the final collapsed circuit.
+    pub seal: Notary,
+
+    /// signature of the checkpointing notary.
+    pub sig: Signature,
+}
+
+
+impl Checkpoint {
+
+    /// check that the supplied history is the collapsed prefix
+    /// committed to by this checkpoint.
+    pub fn check_history(&self, spec: &CircuitSpec, history: &Message) -> Result<(),CheckpointError> {
+        let steps = self.steps as usize;
+        if history.step_index() != steps || !spec.is_boundary(steps) {
+            return Err(CheckpointError::Steps);
+        }
+        let (mut bytes,mut seed) = (ByteVisitor::default(),SeedVisitor::default());
+        visit::apply(spec,&mut bytes,history);
+        visit::apply(spec,&mut seed,history);
+        if Keccak256::hash(bytes.as_bytes()) != self.root.0 {
+            return Err(CheckpointError::Root);
+        }
+        match seed.get_seed() {
+            Some((clear,seal)) if *clear == self.clear && *seal == self.seal => Ok(()),
+            _ => Err(CheckpointError::Seed),
+        }
+    }
+
+    /// get an accusation against the checkpointing notary if the
+    /// supplied history is not the prefix committed to.
+    pub fn accuse(&self, spec: &CircuitSpec, history: &Message) -> Option<Accuse> {
+        match self.check_history(spec,history) {
+            Ok(()) => None,
+            Err(_) => Some(Accuse::Checkpoint { inner: history.clone(), checkpoint: *self }),
+        }
+    }
+}
+
+
+impl Message {
+
+    /// get the message made up of the first `steps` steps.
+    ///
+    /// Returns `None` unless `steps` falls on a circuit boundary
+    /// following the current checkpoint (if any), and the message
+    /// holds all certs up to that boundary.
+    ///
+    pub fn prefix(&self, spec: &CircuitSpec, steps: usize) -> Option<Message> {
+        let offset = self.collapsed();
+        if !spec.is_boundary(steps) || steps <= offset || steps > self.step_index() {
+            return None;
+        }
+        let mut prefix = Message::new(self.payload.clone());
+        prefix.checkpoint = self.checkpoint;
+        let &Message { ref verify, ref notary, ref route, ref blind, .. } = self;
+        let (mut verify,mut notary) = (verify.iter().cloned(),notary.iter().cloned());
+        let (mut route,mut blind) = (route.iter().cloned(),blind.iter().cloned());
+        for index in offset..steps {
+            let cert = match spec.step(index) {
+                STEP::ORACLE => verify.next().map(Cert::Oracle),
+                STEP::VERIFY => verify.next().map(Cert::Verify),
+                STEP::NOTARY => notary.next().map(Cert::Notary),
+                STEP::BLIND => blind.next().map(Cert::Blind),
+                STEP::CLEAR => blind.next().map(Cert::Clear),
+                STEP::ROUTE => route.next().map(Cert::Route),
+            }?;
+            prefix.append_cert(spec,cert).expect("always matches next step");
+        }
+        Some(prefix)
+    }
+
+    /// collapse the prefix committed to by a checkpoint, returning
+    /// the collapsed history.
+    ///
+    /// *note*: the checkpoint signature is not checked here (it is
+    /// checked by `CertVisitor` along with all other signatures).
+    ///
+    pub fn collapse(&mut self, spec: &CircuitSpec, checkpoint: Checkpoint) -> Result<Message,CheckpointError> {
+        let history = self.prefix(spec,checkpoint.steps as usize)
+            .ok_or(CheckpointError::Steps)?;
+        checkpoint.check_history(spec,&history)?;
+        self.verify.drain(..history.verify.len());
+        self.notary.drain(..history.notary.len());
+        self.route.drain(..history.route.len());
+        self.blind.drain(..history.blind.len());
+        self.checkpoint = Some(checkpoint);
+        Ok(history)
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use mimir_crypto::secp256k1::Signer;
+    use mimir_crypto::Address;
+    use message::{Message,Payload,CircuitSpec,CheckpointError,Circuit,Code,STEP};
+    use message::cert::Cert;
+    use seal::{Sealer,Domain};
+    use visit::{self,EmptyVisitor};
+    use judge::{JudgeVisitor,Accuse};
+    use route::RouteTree;
+    use rand;
+
+    /// build a message w/ `circuits` complete verify circuits.
+    fn message(signer: &Signer, circuits: usize) -> Message {
+        let (spec,domain) = (CircuitSpec::default(),Domain::legacy());
+        let tree = RouteTree::new(vec![signer.address()]).unwrap();
+        let mut message = Message::new(Payload::default());
+        for _ in 0..(4 + 6 * circuits) {
+            let cert = match message.next_step(&spec) {
+                STEP::ORACLE => Cert::Oracle(signer.seal_oracle(&spec,&domain,&message)),
+                STEP::NOTARY => Cert::Notary(signer.seal_notary(&spec,&domain,&message)),
+                STEP::BLIND => Cert::Blind(rand::random()),
+                STEP::CLEAR => Cert::Clear(rand::random()),
+                STEP::ROUTE => Cert::Route(signer.seal_route(&spec,&domain,&message,&tree).unwrap()),
+                STEP::VERIFY => Cert::Verify(signer.seal_verify(&spec,&domain,&message,Code::Ok)),
+            };
+            message.append_cert(&spec,cert).unwrap();
+        }
+        message
+    }
+
+    #[test]
+    fn prefixes() {
+        let spec = CircuitSpec::default();
+        let message = message(&rand::random(),2);
+        assert!(message.prefix(&spec,0).is_none());
+        assert!(message.prefix(&spec,5).is_none());
+        assert!(message.prefix(&spec,22).is_none());
+        let prefix = message.prefix(&spec,10).unwrap();
+        assert_eq!(prefix.step_index(),10);
+        assert_eq!(prefix.next_step(&spec),STEP::BLIND);
+        assert_eq!(message.prefix(&spec,16).unwrap(),message);
+    }
+
+    #[test]
+    fn collapse_history() {
+        let spec = CircuitSpec::default();
+        let signer: Signer = rand::random();
+        let message = message(&signer,2);
+        let checkpoint = signer.seal_checkpoint(&spec,&Domain::legacy(),&message).unwrap();
+        assert_eq!(checkpoint.steps,16);
+        // checkpoints may not collapse a partial prefix.
+        let mut bad = checkpoint;
+        bad.steps = 12;
+        assert_eq!(message.clone().collapse(&spec,bad),Err(CheckpointError::Steps));
+        let mut bad = checkpoint;
+        bad.root.0[0] ^= 0x01;
+        assert_eq!(message.clone().collapse(&spec,bad),Err(CheckpointError::Root));
+        let mut bad = checkpoint;
+        bad.clear = rand::random();
+        assert_eq!(message.clone().collapse(&spec,bad),Err(CheckpointError::Seed));
+        let mut collapsed = message.clone();
+        let history = collapsed.collapse(&spec,checkpoint).unwrap();
+        assert_eq!(history,message);
+        assert_eq!(checkpoint.check_history(&spec,&history),Ok(()));
+        assert!(checkpoint.accuse(&spec,&history).is_none());
+        // a checkpoint may be accused by anyone holding a mismatched history.
+        let partial = message.prefix(&spec,10).unwrap();
+        match checkpoint.accuse(&spec,&partial) {
+            Some(Accuse::Checkpoint { ref inner, checkpoint: ref accused }) => {
+                assert_eq!((inner,accused),(&partial,&checkpoint));
+            },
+            other => panic!("unexpected accusation: {:?}",other),
+        }
+        assert_eq!(collapsed.step_index(),16);
+        assert_eq!(collapsed.next_step(&spec),STEP::BLIND);
+        assert!(collapsed.verify.is_empty() && collapsed.blind.is_empty());
+        assert!(collapsed.circuits(&spec).is_empty());
+        assert_eq!(visit::validate(&spec,&collapsed),Ok(STEP::BLIND));
+    }
+
+    #[test]
+    fn seal_after_checkpoint() {
+        let (spec,domain) = (CircuitSpec::default(),Domain::legacy());
+        let signer: Signer = rand::random();
+        let tree = RouteTree::new(vec![signer.address()]).unwrap();
+        let mut message = message(&signer,1);
+        let checkpoint = signer.seal_checkpoint(&spec,&domain,&message).unwrap();
+        message.collapse(&spec,checkpoint).unwrap();
+        // later certs are sealed against the checkpoint.
+        message.append_cert(&spec,Cert::Blind(rand::random())).unwrap();
+        let route = signer.seal_route(&spec,&domain,&message,&tree).unwrap();
+        message.append_cert(&spec,Cert::Route(route)).unwrap();
+        let verify = signer.seal_verify(&spec,&domain,&message,Code::Ok);
+        message.append_cert(&spec,Cert::Verify(verify)).unwrap();
+        let verdict = {
+            let mut judge = JudgeVisitor::new(EmptyVisitor,domain);
+            visit::apply(&spec,&mut judge,&message);
+            judge.finish()
+        };
+        assert!(verdict.is_ok());
+        let signers: Vec<Address> = verdict.steps.iter().filter_map(|step| step.signer).collect();
+        assert_eq!(signers,vec![signer.address();3]);
+        match message.circuits(&spec)[0] {
+            Circuit::Verify(ref circuit) => assert_eq!(circuit.index,1),
+            ref other => panic!("unexpected circuit: {:?}",other),
+        }
+        // a second checkpoint may not be sealed until the circuit completes.
+        assert!(signer.seal_checkpoint(&spec,&domain,&message).is_none());
+    }
+}
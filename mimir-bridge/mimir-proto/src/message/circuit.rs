@@ -0,0 +1,298 @@
+//! read-only views over the circuits of a message.
+//!
+//! the certs of a message are stored in parallel vectors which are
+//! interleaved according to the circuit spec.  the views in this module
+//! perform the necessary index math once, grouping the certs of each
+//! circuit together (see `Message::circuits`).
+//!
+use mimir_types::Address;
+use message::cert::{Oracle,Notary,Blind,Clear,Route,Verify};
+use message::{Message,CircuitSpec,STEP};
+use std::slice;
+
+
+/// view over the certs of the oracle circuit.
+///
+#[derive(Debug,Clone,PartialEq,Eq)]
+pub struct OracleCircuit<'a> {
+    /// requester blind opening the circuit
+    pub blind: Option<&'a Blind>,
+
+    /// oracle cert certifying the payload
+    pub oracle: Option<&'a Oracle>,
+
+    /// notary cert(s) of the circuit
+    pub notary: Vec<&'a Notary>,
+
+    /// requester clear closing the circuit
+    pub clear: Option<&'a Clear>,
+
+    /// indicates that all steps of the circuit are filled
+    pub complete: bool,
+}
+
+
+/// view over the certs of a verify circuit.
+///
+#[derive(Debug,Clone,PartialEq,Eq)]
+pub struct VerifyCircuit<'a> {
+    /// position of the circuit among all verify circuits
+    pub index: usize,
+
+    /// requester blind opening the circuit
+    pub blind: Option<&'a Blind>,
+
+    /// route cert selecting the verifiers
+    pub route: Option<&'a Route>,
+
+    /// verify certs, in order of signing
+    pub verify: Vec<&'a Verify>,
+
+    /// notary cert(s) of the circuit
+    pub notary: Vec<&'a Notary>,
+
+    /// requester clear closing the circuit
+    pub clear: Option<&'a Clear>,
+
+    /// indicates that all steps of the circuit are filled
+    pub complete: bool,
+}
+
+
+impl<'a> VerifyCircuit<'a> {
+
+    /// get the destinations selected by the router (if routed).
+    pub fn dests(&self) -> &'a [Address] {
+        match self.route {
+            Some(route) => &route.val,
+            None => &[],
+        }
+    }
+
+    /// get the first destination which follows the collected verify
+    /// certs, for requesters which forward in route order.
+    pub fn next_dest(&self) -> Option<Address> {
+        self.dests().get(self.verify.len()).cloned()
+    }
+}
+
+
+/// view over a single circuit of a message.
+///
+#[derive(Debug,Clone,PartialEq,Eq)]
+pub enum Circuit<'a> {
+    /// the initial oracle circuit
+    Oracle(OracleCircuit<'a>),
+
+    /// a subsequent verify circuit
+    Verify(VerifyCircuit<'a>),
+}
+
+
+impl<'a> Circuit<'a> {
+
+    /// check if all steps of the circuit are filled.
+    pub fn is_complete(&self) -> bool {
+        match *self {
+            Circuit::Oracle(ref circuit) => circuit.complete,
+            Circuit::Verify(ref circuit) => circuit.complete,
+        }
+    }
+}
+
+
+impl Message {
+
+    /// get views over the circuits of the message under the supplied spec.
+    ///
+    /// circuits are yielded in order, ending with the first circuit
+    /// for which the message is missing one or more certs.  certs
+    /// which lie outside of the circuit spec are ignored (see
+    /// `visit::validate`).  circuits collapsed into a checkpoint
+    /// are omitted (verify circuits retain their original index).
+    ///
+    pub fn circuits<'a>(&'a self, spec: &CircuitSpec) -> Vec<Circuit<'a>> {
+        let mut certs = Certs::new(self);
+        let mut circuits = Vec::new();
+        let collapsed = self.collapsed();
+        if collapsed > 0 {
+            // an empty verify circuit admits no certs past the oracle circuit.
+            let width = spec.verify().len();
+            if width == 0 { return circuits; }
+            let start = collapsed.saturating_sub(spec.oracle().len()) / width;
+            certs.fill_verify(spec,start,&mut circuits);
+            return circuits;
+        }
+        let oracle = certs.fill(spec.oracle());
+        if oracle.count == 0 { return circuits; }
+        let complete = oracle.count == spec.oracle().len();
+        circuits.push(Circuit::Oracle(OracleCircuit {
+            blind: oracle.blind,
+            oracle: oracle.verify.first().cloned(),
+            notary: oracle.notary,
+            clear: oracle.clear,
+            complete,
+        }));
+        if !complete { return circuits; }
+        certs.fill_verify(spec,0,&mut circuits);
+        circuits
+    }
+}
+
+
+/// certs of a single circuit, independent of circuit kind.
+#[derive(Default)]
+struct Filled<'a> {
+    blind: Option<&'a Blind>,
+    clear: Option<&'a Clear>,
+    route: Option<&'a Route>,
+    verify: Vec<&'a Verify>,
+    notary: Vec<&'a Notary>,
+    count: usize,
+}
+
+
+/// iterators over the remaining certs of a message.
+struct Certs<'a> {
+    verify: slice::Iter<'a,Verify>,
+    notary: slice::Iter<'a,Notary>,
+    route: slice::Iter<'a,Route>,
+    blind: slice::Iter<'a,Blind>,
+}
+
+
+impl<'a> Certs<'a> {
+
+    fn new(message: &'a Message) -> Self {
+        let &Message { ref verify, ref notary, ref route, ref blind, .. } = message;
+        Certs { verify: verify.iter(), notary: notary.iter(), route: route.iter(), blind: blind.iter() }
+    }
+
+    /// take certs for the supplied steps, stopping at the first missing cert.
+    fn fill(&mut self, steps: &[STEP]) -> Filled<'a> {
+        let mut filled = Filled::default();
+        for step in steps.iter() {
+            let found = match *step {
+                STEP::BLIND => self.blind.next().map(|cert| filled.blind = Some(cert)),
+                STEP::CLEAR => self.blind.next().map(|cert| filled.clear = Some(cert)),
+                STEP::ROUTE => self.route.next().map(|cert| filled.route = Some(cert)),
+                STEP::ORACLE | STEP::VERIFY => self.verify.next().map(|cert| filled.verify.push(cert)),
+                STEP::NOTARY => self.notary.next().map(|cert| filled.notary.push(cert)),
+            };
+            if found.is_none() { break; }
+            filled.count += 1;
+        }
+        filled
+    }
+
+    /// push views of successive verify circuits, beginning at `start`.
+    fn fill_verify(&mut self, spec: &CircuitSpec, start: usize, circuits: &mut Vec<Circuit<'a>>) {
+        for index in start.. {
+            let verify = self.fill(spec.verify());
+            if verify.count == 0 { break; }
+            let complete = verify.count == spec.verify().len();
+            circuits.push(Circuit::Verify(VerifyCircuit {
+                index,
+                blind: verify.blind,
+                route: verify.route,
+                verify: verify.verify,
+                notary: verify.notary,
+                clear: verify.clear,
+                complete,
+            }));
+            if !complete { break; }
+        }
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use mimir_types::Address;
+    use message::{Message,Payload,CircuitSpec,Circuit};
+
+    /// message w/ a complete oracle circuit, one complete
+    /// verify circuit & one partial verify circuit.
+    fn message() -> Message {
+        let mut message = Message::new(Payload::default());
+        for i in 0..5u8 {
+            message.blind.push([i;32].into());
+        }
+        for _ in 0..4 {
+            message.verify.push(Default::default());
+        }
+        for _ in 0..2 {
+            message.notary.push(Default::default());
+            message.route.push(Default::default());
+        }
+        message
+    }
+
+    #[test]
+    fn circuit_views() {
+        let spec = CircuitSpec::default();
+        let message = message();
+        let circuits = message.circuits(&spec);
+        assert_eq!(circuits.len(),3);
+        let complete: Vec<_> = circuits.iter().map(|c| c.is_complete()).collect();
+        assert_eq!(complete,vec![true,true,false]);
+        match circuits[0] {
+            Circuit::Oracle(ref circuit) => {
+                assert_eq!(circuit.blind,Some(&message.blind[0]));
+                assert_eq!(circuit.oracle,Some(&message.verify[0]));
+                assert_eq!(circuit.notary,vec![&message.notary[0]]);
+                assert_eq!(circuit.clear,Some(&message.blind[1]));
+            },
+            ref other => panic!("unexpected circuit: {:?}",other),
+        }
+        match circuits[1] {
+            Circuit::Verify(ref circuit) => {
+                assert_eq!(circuit.index,0);
+                assert_eq!(circuit.blind,Some(&message.blind[2]));
+                assert_eq!(circuit.route,Some(&message.route[0]));
+                assert_eq!(circuit.verify,vec![&message.verify[1],&message.verify[2]]);
+                assert_eq!(circuit.notary,vec![&message.notary[1]]);
+                assert_eq!(circuit.clear,Some(&message.blind[3]));
+            },
+            ref other => panic!("unexpected circuit: {:?}",other),
+        }
+        // partial circuit stops at the first missing cert (the second verify).
+        match circuits[2] {
+            Circuit::Verify(ref circuit) => {
+                assert_eq!(circuit.index,1);
+                assert_eq!(circuit.blind,Some(&message.blind[4]));
+                assert_eq!(circuit.route,Some(&message.route[1]));
+                assert_eq!(circuit.verify,vec![&message.verify[3]]);
+                assert!(circuit.notary.is_empty() && circuit.clear.is_none());
+                assert_eq!(circuit.next_dest(),None);
+            },
+            ref other => panic!("unexpected circuit: {:?}",other),
+        }
+    }
+
+    #[test]
+    fn next_dest() {
+        let spec = CircuitSpec::default();
+        let mut message = message();
+        let dests: Vec<Address> = (1..3u8).map(|i| [i;20].into()).collect();
+        message.route[1].val = dests.clone();
+        match message.circuits(&spec).pop() {
+            Some(Circuit::Verify(ref circuit)) => {
+                assert_eq!(circuit.dests(),&dests[..]);
+                assert_eq!(circuit.next_dest(),Some(dests[1]));
+            },
+            ref other => panic!("unexpected circuit: {:?}",other),
+        }
+    }
+
+    #[test]
+    fn empty_circuits() {
+        let spec = CircuitSpec::default();
+        let mut message = Message::new(Payload::default());
+        assert!(message.circuits(&spec).is_empty());
+        message.blind.push(Default::default());
+        let circuits = message.circuits(&spec);
+        assert_eq!(circuits.len(),1);
+        assert!(!circuits[0].is_complete());
+    }
+}
@@ -0,0 +1,132 @@
+//! dispute codes carried by `verify` certs.
+//!
+//! codes are encoded as a single byte both in signed message bytes
+//! and during serialization.  numeric values are part of the protocol
+//! and must never be reassigned.
+//!
+use serde::de::{self,Deserialize,Deserializer};
+use serde::ser::{Serialize,Serializer};
+use std::fmt;
+
+
+/// flag indicating a validator's argument regarding a message payload.
+///
+/// `Ok` is the agreement case (and is the only legal value for oracle
+/// certs); all other values indicate a dispute & the reason for it.
+///
+#[derive(Debug,Copy,Clone,Hash,PartialEq,Eq)]
+pub enum Code {
+    /// payload is correct.
+    Ok = 0x00,
+
+    /// rpc result does not match the result observed by the validator.
+    Mismatch = 0x01,
+
+    /// payload is pinned to the wrong block.
+    Block = 0x02,
+
+    /// payload record is malformed.
+    Malformed = 0x03,
+
+    /// rpc method is not supported.
+    Unsupported = 0x04,
+
+    /// validator was unable to resolve the request in time.
+    Timeout = 0x05,
+}
+
+
+impl Code {
+
+    /// get code from numeric value (if known).
+    pub fn from_u8(val: u8) -> Option<Self> {
+        match val {
+            0x00 => Some(Code::Ok),
+            0x01 => Some(Code::Mismatch),
+            0x02 => Some(Code::Block),
+            0x03 => Some(Code::Malformed),
+            0x04 => Some(Code::Unsupported),
+            0x05 => Some(Code::Timeout),
+            _ => None,
+        }
+    }
+
+    /// get numeric value of code.
+    pub fn as_u8(&self) -> u8 { *self as u8 }
+
+    /// check if this is the agreement case.
+    pub fn is_ok(&self) -> bool { *self == Code::Ok }
+
+    /// get simple static description of code.
+    pub fn as_str(&self) -> &'static str {
+        match *self {
+            Code::Ok => "ok",
+            Code::Mismatch => "result mismatch",
+            Code::Block => "wrong block",
+            Code::Malformed => "malformed record",
+            Code::Unsupported => "unsupported method",
+            Code::Timeout => "timeout",
+        }
+    }
+}
+
+
+impl Default for Code {
+
+    fn default() -> Self { Code::Ok }
+}
+
+
+impl fmt::Display for Code {
+
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+
+impl Serialize for Code {
+
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok,S::Error> {
+        serializer.serialize_u8(self.as_u8())
+    }
+}
+
+
+impl<'de> Deserialize<'de> for Code {
+
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self,D::Error> {
+        let val = u8::deserialize(deserializer)?;
+        Code::from_u8(val).ok_or_else(|| {
+            de::Error::custom(format!("unknown dispute code `{}`",val))
+        })
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use message::Code;
+    use serde_json;
+
+    #[test]
+    fn stable_values() {
+        let expect = [
+            (Code::Ok,0x00),
+            (Code::Mismatch,0x01),
+            (Code::Block,0x02),
+            (Code::Malformed,0x03),
+            (Code::Unsupported,0x04),
+            (Code::Timeout,0x05),
+        ];
+        for &(code,val) in expect.iter() {
+            assert_eq!(code.as_u8(),val);
+            assert_eq!(Code::from_u8(val),Some(code));
+            let json = serde_json::to_string(&code).unwrap();
+            assert_eq!(json,val.to_string());
+            assert_eq!(serde_json::from_str::<Code>(&json).unwrap(),code);
+        }
+        assert_eq!(Code::from_u8(0x06),None);
+        assert!(serde_json::from_str::<Code>("255").is_err());
+    }
+}
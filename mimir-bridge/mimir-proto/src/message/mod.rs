@@ -3,10 +3,17 @@
 //! This module provides the core primitives necessary to generate and
 //! verify messages passing through the mimir-bridge.  A set of constants
 //! are also provided dictating the ordering and members of the
-//! oracle and verify circuits.
+//! oracle and verify circuits (see `CircuitSpec` for runtime-configurable
+//! circuits; the constants below make up the default spec).
 pub mod types;
+pub mod record;
 pub mod step;
+pub mod spec;
+pub mod code;
+pub mod role;
 pub mod cert;
+pub mod circuit;
+pub mod checkpoint;
 
 
 pub use self::types::{
@@ -14,10 +21,30 @@ pub use self::types::{
     Payload,
     Message,
 };
-pub use self::step::STEP;
+pub use self::record::{
+    Record,
+    Batch,
+    BatchProof,
+};
+pub use self::step::{STEP,StepError};
+pub use self::code::Code;
+pub use self::role::Role;
+pub use self::circuit::{
+    Circuit,
+    OracleCircuit,
+    VerifyCircuit,
+};
+pub use self::checkpoint::{
+    Checkpoint,
+    CheckpointError,
+};
+pub use self::spec::{
+    CircuitSpec,
+    SpecError,
+};
 
 
-/// number of validator destinations specified by a router.
+/// number of validator destinations specified by a router (default spec).
 pub const DESTS: usize = 2;
 
 
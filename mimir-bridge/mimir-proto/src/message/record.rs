@@ -0,0 +1,412 @@
+//! payload records & batched records.
+//!
+//! a payload normally carries a single query record.  a batched payload
+//! instead carries many records, which are committed to by a merkle
+//! root.  certs over a batched payload sign the root rather than the
+//! records themselves, s.t. any single record may later be extracted
+//! along with a `BatchProof` and checked against the signed root by a
+//! third party (see `Record::prune`).
+//!
+//! the batch tree is built with the same nodes as the routing tree
+//! (leaves are the `keccak-256` hash of a record, branches the hash
+//! of their children).  batches which are not a power of two in size
+//! are padded by cycling through the records from the beginning.  the
+//! root is the hash of the record count (as a 32 byte big-endian
+//! integer) followed by the hash of the tree, s.t. padding cannot be
+//! mistaken for additional records.
+//!
+use serde::de::{self,Deserialize,Deserializer};
+use serde::ser::{Serialize,Serializer,SerializeMap};
+use mimir_util::types::Either;
+use mimir_crypto::Keccak256;
+use mimir_types::{H256,U256};
+use route::node::{self,hash_of};
+use route::{policy,Leaf,NodeHash,ProofError};
+use std::borrow::Cow;
+
+
+/// leading byte of the signed bytes of a batched record.
+///
+/// since `0xff` never occurs in valid utf-8, batched records cannot
+/// collide with single records.
+///
+pub(crate) const BATCH_FLAG: u8 = 0xff;
+
+
+/// query record of a payload.
+///
+/// serialized as a plain string for single records, as a sequence of
+/// strings for batches, and as a `{"root": ...}` map for pruned batches.
+///
+#[derive(Debug,Clone,PartialEq,Eq)]
+pub enum Record {
+    /// single query record
+    Single(String),
+
+    /// batch of query records
+    Batch(Batch),
+
+    /// root of a batch whose records have been withheld
+    Root(H256),
+}
+
+
+impl Record {
+
+    /// check if this record is a (possibly pruned) batch.
+    pub fn is_batch(&self) -> bool {
+        match *self {
+            Record::Single(_) => false,
+            Record::Batch(_) | Record::Root(_) => true,
+        }
+    }
+
+    /// get the merkle root of a batched record.
+    pub fn root(&self) -> Option<H256> {
+        match *self {
+            Record::Single(_) => None,
+            Record::Batch(ref batch) => Some(batch.root()),
+            Record::Root(ref root) => Some(*root),
+        }
+    }
+
+    /// get the bytes of the record which are covered by certs.
+    ///
+    /// single records are signed as-is; batched records are signed
+    /// as `BATCH_FLAG` followed by the merkle root.
+    ///
+    pub fn signed_bytes(&self) -> Cow<'_,[u8]> {
+        match *self {
+            Record::Single(ref record) => Cow::Borrowed(record.as_bytes()),
+            _ => {
+                let root = self.root().expect("batched record");
+                let mut bytes = Vec::with_capacity(33);
+                bytes.push(BATCH_FLAG);
+                bytes.extend_from_slice(root.as_ref());
+                Cow::Owned(bytes)
+            },
+        }
+    }
+
+    /// generate an inclusion proof for the specified record of a batch.
+    pub fn prove(&self, index: usize) -> Option<BatchProof> {
+        match *self {
+            Record::Batch(ref batch) => batch.prove(index),
+            _ => None,
+        }
+    }
+
+    /// withhold the records of a batch, retaining only its root.
+    ///
+    /// certs are unaffected, as the signed bytes of a pruned batch
+    /// are identical to those of the full batch.
+    ///
+    pub fn prune(&mut self) {
+        let root = match *self {
+            Record::Batch(ref batch) => batch.root(),
+            _ => return,
+        };
+        *self = Record::Root(root);
+    }
+}
+
+
+impl Default for Record {
+
+    fn default() -> Self { Record::Single(Default::default()) }
+}
+
+
+impl From<String> for Record {
+
+    fn from(record: String) -> Self { Record::Single(record) }
+}
+
+
+impl<'a> From<&'a str> for Record {
+
+    fn from(record: &'a str) -> Self { Record::Single(record.into()) }
+}
+
+
+impl From<Batch> for Record {
+
+    fn from(batch: Batch) -> Self { Record::Batch(batch) }
+}
+
+
+impl Serialize for Record {
+
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok,S::Error> {
+        match *self {
+            Record::Single(ref record) => serializer.serialize_str(record),
+            Record::Batch(ref batch) => batch.records().serialize(serializer),
+            Record::Root(ref root) => {
+                let mut map = serializer.serialize_map(Some(1))?;
+                map.serialize_entry("root",root)?;
+                map.end()
+            },
+        }
+    }
+}
+
+
+impl<'de> Deserialize<'de> for Record {
+
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self,D::Error> {
+        #[derive(Deserialize)]
+        #[serde(untagged)]
+        enum Repr {
+            Single(String),
+            Batch(Vec<String>),
+            Root { root: H256 },
+        }
+        match Repr::deserialize(deserializer)? {
+            Repr::Single(record) => Ok(Record::Single(record)),
+            Repr::Batch(records) => Batch::new(records).map(Record::Batch)
+                .ok_or_else(|| de::Error::custom("empty record batch")),
+            Repr::Root { root } => Ok(Record::Root(root)),
+        }
+    }
+}
+
+
+/// non-empty batch of query records.
+///
+#[derive(Debug,Clone,Hash,PartialEq,Eq)]
+pub struct Batch {
+    records: Vec<String>,
+}
+
+
+impl Batch {
+
+    /// build batch from a set of records.
+    ///
+    /// Returns `None` if no records were supplied.
+    ///
+    pub fn new<I>(records: I) -> Option<Self> where I: IntoIterator<Item=String> {
+        let records: Vec<String> = records.into_iter().collect();
+        if records.is_empty() { return None; }
+        Some(Batch { records })
+    }
+
+    /// get reference to records of batch
+    pub fn records(&self) -> &[String] { &self.records }
+
+    /// number of records in batch
+    pub fn count(&self) -> usize { self.records.len() }
+
+    /// get the merkle root of the batch.
+    pub fn root(&self) -> H256 {
+        let tree = self.tree();
+        H256(commit(self.count(),hash_of(as_ref(&tree))))
+    }
+
+    /// generate an inclusion proof for the specified record.
+    ///
+    /// the path of sibling hashes is in construction order (as with
+    /// `RouteTree::route`).  Returns `None` if `index` is out of bounds.
+    ///
+    pub fn prove(&self, index: usize) -> Option<BatchProof> {
+        let record = self.records.get(index)?.to_owned();
+        let tree = self.tree();
+        let mut path = Vec::new();
+        let mut height = depth(self.count()).expect("held records are bounded by memory");
+        let mut node = as_ref(&tree);
+        while let Either::A(branch) = node {
+            height -= 1;
+            if (index >> height) & 1 == 1 {
+                path.push(NodeHash::Left(H256(*hash_of(branch.left()))));
+                node = branch.right();
+            } else {
+                path.push(NodeHash::Right(H256(*hash_of(branch.right()))));
+                node = branch.left();
+            }
+        }
+        let count = self.count();
+        Some(BatchProof { record, index, count, path })
+    }
+
+    /// build the (padded) tree of the batch.
+    fn tree(&self) -> Either<node::Branch<&str>,Leaf<&str>> {
+        let size = self.records.len();
+        let leaves = (0..size.next_power_of_two())
+            .map(|index| Leaf::new(self.records[index % size].as_str()))
+            .collect();
+        node::build(leaves)
+    }
+}
+
+
+/// proof of inclusion of a single record in a batch.
+///
+#[derive(Debug,Clone,PartialEq,Eq,Serialize,Deserialize)]
+pub struct BatchProof {
+    /// the proven record
+    pub record: String,
+
+    /// position of the record within the batch
+    pub index: usize,
+
+    /// number of records in the batch
+    pub count: usize,
+
+    /// sibling hashes from root to leaf
+    pub path: Vec<NodeHash>,
+}
+
+
+impl BatchProof {
+
+    /// attempt to recover the merkle root of the batch.
+    ///
+    /// Returns a `ProofError` if the path does not lead to the
+    /// claimed index of a batch of the claimed size.
+    ///
+    pub fn recover_root(&self) -> Result<H256,ProofError> {
+        let &BatchProof { ref record, index, count, ref path } = self;
+        if index >= count || Some(path.len()) != depth(count) {
+            return Err(ProofError::Path);
+        }
+        let mut base_hash = Keccak256::hash(record.as_bytes());
+        // iteratively rebuild from leaf to root (reverse of construction order).
+        for (height,node_hash) in path.iter().rev().enumerate() {
+            let right_child = (index >> height) & 1 == 1;
+            base_hash = match *node_hash {
+                NodeHash::Left(ref left_hash) if right_child => {
+                    policy::hash_pair(left_hash,&base_hash)
+                },
+                NodeHash::Right(ref right_hash) if !right_child => {
+                    policy::hash_pair(&base_hash,right_hash)
+                },
+                _ => return Err(ProofError::Path),
+            };
+        }
+        Ok(H256(commit(count,&base_hash)))
+    }
+
+    /// verify proof against a signed merkle root.
+    pub fn verify(&self, root: &H256) -> Result<(),ProofError> {
+        if self.recover_root()? == *root {
+            Ok(())
+        } else {
+            Err(ProofError::Root)
+        }
+    }
+}
+
+
+/// depth of the tree of a batch w/ `count` records (`None`
+/// if the padded tree is too large to be represented).
+fn depth(count: usize) -> Option<usize> {
+    count.checked_next_power_of_two().map(|size| size.trailing_zeros() as usize)
+}
+
+
+/// commit to the record count of a batch alongside its tree hash.
+fn commit(count: usize, tree: &[u8;32]) -> [u8;32] {
+    let count = U256::from(count as u64);
+    policy::hash_pair(count.as_ref(),tree)
+}
+
+
+/// borrow root node of tree.
+fn as_ref<T>(tree: &Either<node::Branch<T>,Leaf<T>>) -> Either<&node::Branch<T>,&Leaf<T>> {
+    match *tree {
+        Either::A(ref branch) => Either::A(branch),
+        Either::B(ref leaf) => Either::B(leaf),
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use message::{Record,Batch};
+    use route::{NodeHash,ProofError};
+    use mimir_types::H256;
+    use serde_json;
+
+    fn batch(count: usize) -> Batch {
+        let records = (0..count).map(|i| format!(r#"{{"id":{}}}"#,i));
+        Batch::new(records).unwrap()
+    }
+
+    #[test]
+    fn inclusion_proofs() {
+        for count in 1..10 {
+            let batch = batch(count);
+            let root = batch.root();
+            for index in 0..count {
+                let proof = batch.prove(index).unwrap();
+                assert_eq!(proof.record,batch.records()[index]);
+                assert_eq!(proof.verify(&root),Ok(()));
+            }
+            assert!(batch.prove(count).is_none());
+        }
+    }
+
+    #[test]
+    fn bad_proofs() {
+        let batch = batch(5);
+        let root = batch.root();
+        let proof = batch.prove(2).unwrap();
+        let mut bad = proof.clone();
+        bad.record.push(' ');
+        assert_eq!(bad.verify(&root),Err(ProofError::Root));
+        // claiming a different position flips the expected handedness.
+        let mut bad = proof.clone();
+        bad.index = 3;
+        assert_eq!(bad.verify(&root),Err(ProofError::Path));
+        // padding records may not be proven.
+        let mut bad = batch.prove(0).unwrap();
+        bad.index = 5;
+        assert_eq!(bad.verify(&root),Err(ProofError::Path));
+        let mut bad = proof.clone();
+        bad.path.pop();
+        assert_eq!(bad.verify(&root),Err(ProofError::Path));
+        let mut bad = proof.clone();
+        bad.path[0] = NodeHash::Right(H256([0u8;32]));
+        assert!(bad.verify(&root).is_err());
+        // counts w/o a representable tree are rejected, not wrapped.
+        let mut bad = proof.clone();
+        bad.count = usize::max_value();
+        assert_eq!(bad.verify(&root),Err(ProofError::Path));
+        bad.path = vec![NodeHash::Left(H256([0u8;32]));64];
+        assert_eq!(bad.verify(&root),Err(ProofError::Path));
+    }
+
+    #[test]
+    fn padding_is_committed() {
+        let records = batch(3).records().to_vec();
+        let mut padded = records.clone();
+        padded.push(records[0].clone());
+        assert!(batch(3).root() != Batch::new(padded).unwrap().root());
+    }
+
+    #[test]
+    fn pruned_records() {
+        let mut record = Record::from(batch(4));
+        let signed = record.signed_bytes().into_owned();
+        assert_eq!(signed.len(),33);
+        let proof = record.prove(1).unwrap();
+        record.prune();
+        assert_eq!(record,Record::Root(batch(4).root()));
+        assert_eq!(record.signed_bytes().into_owned(),signed);
+        assert_eq!(proof.verify(&record.root().unwrap()),Ok(()));
+        let single = Record::from("{}");
+        assert_eq!(&*single.signed_bytes(),b"{}");
+        assert!(single.root().is_none());
+    }
+
+    #[test]
+    fn serde_forms() {
+        let forms = vec![Record::from("{}"),Record::from(batch(3)),Record::Root(batch(3).root())];
+        for record in forms {
+            let json = serde_json::to_string(&record).unwrap();
+            assert_eq!(serde_json::from_str::<Record>(&json).unwrap(),record);
+        }
+        assert_eq!(serde_json::to_string(&Record::from("{}")).unwrap(),r#""{}""#);
+        assert!(serde_json::from_str::<Record>("[]").is_err());
+    }
+}
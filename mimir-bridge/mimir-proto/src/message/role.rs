@@ -0,0 +1,29 @@
+//! roles of the entities which participate in a message.
+//!
+use message::STEP;
+
+
+simple_unit!(
+    Role, "basic client roles",
+    Oracle    => "oracle",
+    Notary    => "notary",
+    Requester => "requester",
+    Router    => "router",
+    Verifier  => "verifier",
+    Admin     => "admin",
+);
+
+
+impl Role {
+
+    /// get the role responsible for producing certs of the specified step.
+    pub fn of_step(step: STEP) -> Self {
+        match step {
+            STEP::ORACLE => Role::Oracle,
+            STEP::NOTARY => Role::Notary,
+            STEP::BLIND | STEP::CLEAR => Role::Requester,
+            STEP::ROUTE => Role::Router,
+            STEP::VERIFY => Role::Verifier,
+        }
+    }
+}
@@ -0,0 +1,252 @@
+//! runtime-configurable circuit specifications.
+//!
+//! a `CircuitSpec` dictates the ordering and members of the oracle
+//! and verify circuits, as well as the number of validator destinations
+//! selected by each router.  the default spec corresponds to the
+//! `OCIRCUIT`, `VCIRCUIT` & `DESTS` constants.  specs may be loaded
+//! from toml files of the form:
+//!
+//! ```toml
+//! dests = 3
+//! verify = ["blind","route","verify","verify","verify","notary","notary","clear"]
+//! ```
+//!
+//! omitted fields take on thier default values.
+//!
+use serde::de::{self,Deserialize,Deserializer};
+use mimir_util::toml::{self,TomlError};
+use message::{STEP,OCIRCUIT,VCIRCUIT,DESTS};
+use std::borrow::Cow;
+use std::path::Path;
+use std::str::FromStr;
+
+
+simple_error!(
+    SpecError, "error indicating an invalid circuit spec",
+    Empty => "circuit contains no steps",
+    Blind => "circuit must begin with `blind` & end with `clear`",
+    Notary => "circuit must contain at least one `notary` step",
+    Oracle => "oracle circuit must contain exactly one `oracle` step",
+    Route => "verify circuit must contain exactly one `route` step, preceeding all `verify` steps",
+    Dests => "destination count must be nonzero & match number of `verify` steps",
+);
+
+
+/// specification of message circuits.
+///
+/// ```
+/// # extern crate mimir_proto;
+/// # fn main() {
+/// use mimir_proto::message::{CircuitSpec,STEP};
+///
+/// let spec: CircuitSpec = r#"
+///     dests = 1
+///     verify = ["blind","route","verify","notary","clear"]
+/// "#.parse().unwrap();
+///
+/// assert_eq!(spec.dests(),1);
+/// assert_eq!(spec.step(4),STEP::BLIND);
+/// assert_eq!(spec.step(6),STEP::VERIFY);
+/// assert_eq!(spec.step(9),STEP::BLIND);
+/// # }
+/// ```
+///
+#[derive(Debug,Clone,PartialEq,Eq,Serialize)]
+pub struct CircuitSpec {
+    oracle: Cow<'static,[STEP]>,
+    verify: Cow<'static,[STEP]>,
+    dests: usize,
+}
+
+
+impl CircuitSpec {
+
+    /// build a new circuit spec, checking that it is well formed.
+    pub fn new(oracle: Vec<STEP>, verify: Vec<STEP>, dests: usize) -> Result<Self,SpecError> {
+        let spec = CircuitSpec { oracle: oracle.into(), verify: verify.into(), dests };
+        spec.check()?;
+        Ok(spec)
+    }
+
+    /// ordered steps of the oracle circuit
+    pub fn oracle(&self) -> &[STEP] { &self.oracle }
+
+    /// ordered steps of each verify circuit
+    pub fn verify(&self) -> &[STEP] { &self.verify }
+
+    /// number of validator destinations specified by a router
+    pub fn dests(&self) -> usize { self.dests }
+
+    /// get the step corresponding to the numeric step index.
+    pub fn step(&self, index: usize) -> STEP {
+        let osteps = self.oracle.len();
+        if index < osteps {
+            self.oracle[index]
+        } else {
+            let index = (index - osteps) % self.verify.len();
+            self.verify[index]
+        }
+    }
+
+    /// check if the numeric step index falls on the boundary
+    /// following a completed circuit.
+    pub fn is_boundary(&self, index: usize) -> bool {
+        let osteps = self.oracle.len();
+        index >= osteps && (index - osteps) % self.verify.len() == 0
+    }
+
+    /// attempt to load from target if exists, else return default value.
+    pub fn init<P: AsRef<Path>>(path: P) -> Result<Self,TomlError> {
+        if path.as_ref().exists() {
+            toml::load(path)
+        } else {
+            Ok(Default::default())
+        }
+    }
+
+    /// attempt to load from target file.
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self,TomlError> { toml::load(path) }
+
+    /// save to target file.
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> Result<(),TomlError> { toml::save(self,path) }
+
+    /// check that spec is well formed.
+    fn check(&self) -> Result<(),SpecError> {
+        for circuit in [&self.oracle,&self.verify].iter() {
+            match (circuit.first(),circuit.last()) {
+                (Some(&STEP::BLIND),Some(&STEP::CLEAR)) => { },
+                (None,_) | (_,None) => return Err(SpecError::Empty),
+                _ => return Err(SpecError::Blind),
+            }
+            if count(circuit,STEP::BLIND) != 1 || count(circuit,STEP::CLEAR) != 1 {
+                return Err(SpecError::Blind);
+            }
+            if count(circuit,STEP::NOTARY) < 1 {
+                return Err(SpecError::Notary);
+            }
+        }
+        let oracle_ok = count(&self.oracle,STEP::ORACLE) == 1
+            && count(&self.oracle,STEP::ROUTE) == 0
+            && count(&self.oracle,STEP::VERIFY) == 0
+            && count(&self.verify,STEP::ORACLE) == 0;
+        if !oracle_ok { return Err(SpecError::Oracle); }
+        let route_ok = count(&self.verify,STEP::ROUTE) == 1 && {
+            let route = self.verify.iter().position(|s| *s == STEP::ROUTE);
+            let verify = self.verify.iter().position(|s| *s == STEP::VERIFY);
+            route < verify
+        };
+        if !route_ok { return Err(SpecError::Route); }
+        if self.dests == 0 || count(&self.verify,STEP::VERIFY) != self.dests {
+            return Err(SpecError::Dests);
+        }
+        Ok(())
+    }
+}
+
+
+impl Default for CircuitSpec {
+
+    fn default() -> Self {
+        CircuitSpec {
+            oracle: Cow::Borrowed(&OCIRCUIT),
+            verify: Cow::Borrowed(&VCIRCUIT),
+            dests: DESTS,
+        }
+    }
+}
+
+
+impl FromStr for CircuitSpec {
+
+    type Err = TomlError;
+
+    fn from_str(s: &str) -> Result<Self,Self::Err> { toml::from_str(s) }
+}
+
+
+impl<'de> Deserialize<'de> for CircuitSpec {
+
+    fn deserialize<D>(deserializer: D) -> Result<Self,D::Error> where D: Deserializer<'de> {
+        let RawSpec { oracle, verify, dests } = RawSpec::deserialize(deserializer)?;
+        CircuitSpec::new(oracle,verify,dests)
+            .map_err(|err| de::Error::custom(err.as_ref()))
+    }
+}
+
+
+/// unchecked spec values (used during deserialization).
+#[derive(Deserialize)]
+struct RawSpec {
+    #[serde(default = "defaults::oracle")]
+    oracle: Vec<STEP>,
+
+    #[serde(default = "defaults::verify")]
+    verify: Vec<STEP>,
+
+    #[serde(default = "defaults::dests")]
+    dests: usize,
+}
+
+
+mod defaults {
+    use message::{STEP,OCIRCUIT,VCIRCUIT,DESTS};
+
+    pub fn oracle() -> Vec<STEP> { OCIRCUIT.to_vec() }
+
+    pub fn verify() -> Vec<STEP> { VCIRCUIT.to_vec() }
+
+    pub fn dests() -> usize { DESTS }
+}
+
+
+/// count occurrences of a step within a circuit.
+fn count(circuit: &[STEP], step: STEP) -> usize {
+    circuit.iter().filter(|s| **s == step).count()
+}
+
+
+#[cfg(test)]
+mod tests {
+    use message::{CircuitSpec,SpecError,STEP,DESTS};
+
+    #[test]
+    fn default_spec() {
+        let spec = CircuitSpec::default();
+        assert_eq!(spec.dests(),DESTS);
+        let parsed: CircuitSpec = "".parse().unwrap();
+        assert_eq!(parsed,spec);
+        for index in 0..32 {
+            assert_eq!(spec.step(index),STEP::new(index));
+        }
+    }
+
+    #[test]
+    fn custom_spec() {
+        use message::step::STEP::*;
+        let verify = vec![BLIND,ROUTE,VERIFY,VERIFY,VERIFY,NOTARY,NOTARY,CLEAR];
+        let spec = CircuitSpec::new(vec![BLIND,ORACLE,NOTARY,CLEAR],verify.clone(),3).unwrap();
+        assert_eq!(spec.step(4 + verify.len() + 4),VERIFY);
+        assert_eq!(spec.step(4 + verify.len() + 6),NOTARY);
+        let toml_str = r#"
+            dests = 3
+            verify = ["blind","route","verify","verify","verify","notary","notary","clear"]
+        "#;
+        assert_eq!(toml_str.parse::<CircuitSpec>().unwrap(),spec);
+    }
+
+    #[test]
+    fn bad_specs() {
+        use message::step::STEP::*;
+        let oracle = vec![BLIND,ORACLE,NOTARY,CLEAR];
+        let check = |verify: Vec<STEP>, dests: usize| {
+            CircuitSpec::new(oracle.clone(),verify,dests).unwrap_err()
+        };
+        assert_eq!(check(vec![],2),SpecError::Empty);
+        assert_eq!(check(vec![ROUTE,VERIFY,VERIFY,NOTARY,CLEAR],2),SpecError::Blind);
+        assert_eq!(check(vec![BLIND,ROUTE,VERIFY,VERIFY,CLEAR],2),SpecError::Notary);
+        assert_eq!(check(vec![BLIND,ORACLE,ROUTE,VERIFY,NOTARY,CLEAR],1),SpecError::Oracle);
+        assert_eq!(check(vec![BLIND,VERIFY,ROUTE,VERIFY,NOTARY,CLEAR],2),SpecError::Route);
+        assert_eq!(check(vec![BLIND,ROUTE,VERIFY,VERIFY,NOTARY,CLEAR],3),SpecError::Dests);
+        assert!("dests = 0".parse::<CircuitSpec>().is_err());
+    }
+}
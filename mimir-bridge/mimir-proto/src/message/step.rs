@@ -1,11 +1,13 @@
 //! utilities for working with message steps 
 use message::{OSTEPS,VSTEPS,OCIRCUIT,VCIRCUIT};
+use std::{fmt,error};
 
 
 /// flag representing the various certification steps of the
 /// mimir bridge protocol.
 ///
-#[derive(Debug,Copy,Clone,PartialEq,Eq)]
+#[derive(Debug,Copy,Clone,Hash,PartialEq,Eq,Serialize,Deserialize)]
+#[serde(rename_all = "lowercase")]
 pub enum STEP {
     /// initial worker cert, generated by the validator that constructs
     /// the message payload.  same as a `verify` cert except that
@@ -36,7 +38,8 @@ pub enum STEP {
 
 
 impl STEP {
-    /// generate a new `STEP` instance based on the numeric step index.
+    /// generate a new `STEP` instance based on the numeric step index
+    /// under the default circuit spec (see `CircuitSpec::step`).
     pub fn new(index: usize) -> Self {
         if index < OSTEPS {
             OCIRCUIT[index]
@@ -47,6 +50,31 @@ impl STEP {
     }
 }
 
+/// error indicating an attempt to append a cert out of order.
+#[derive(Debug,Copy,Clone,PartialEq,Eq)]
+pub struct StepError {
+    /// step expected by the circuit spec
+    pub expect: STEP,
+
+    /// step of the supplied cert
+    pub found: STEP,
+}
+
+
+impl fmt::Display for StepError {
+
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f,"expected `{:?}` cert, found `{:?}`",self.expect,self.found)
+    }
+}
+
+
+impl error::Error for StepError {
+
+    fn description(&self) -> &str { "out-of-order cert" }
+}
+
+
 #[cfg(test)]
 mod tests {
     use message::{OCIRCUIT,VCIRCUIT};
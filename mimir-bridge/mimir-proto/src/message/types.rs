@@ -5,7 +5,9 @@
 //! necessary for serialization, deserialization, hashing, etc...
 //!
 use mimir_types::{Address,U256,H256};
-use message::cert;
+use message::cert::{self,Cert};
+use message::{CircuitSpec,Checkpoint,Record,STEP,StepError};
+use seal::Version;
 
 
 /// generic request value.
@@ -29,7 +31,8 @@ pub struct Request<Q> {
 /// core values of a message.
 ///
 /// The payload object describes an instance of a served query.
-/// the `record` field contians the actual rpc call/response record.
+/// the `record` field contians the actual rpc call/response record
+/// (or a batch of records, see `Record`).
 /// this datastructure is generated by the oracle and is unchanged
 /// by subsequent steps.
 ///
@@ -38,7 +41,7 @@ pub struct Payload {
     /// query record serialized as json. this component is treated 
     /// as a black-box bytearray by most systems (only validators
     /// directly examine query contents).
-    pub record: String,
+    pub record: Record,
     
     /// address of the requester.
     pub address: Address,
@@ -49,7 +52,13 @@ pub struct Payload {
     
     /// current block hash during which
     /// the request was resolved.
-    pub hash: H256
+    pub hash: H256,
+
+    /// version of the signing domain under which all certs of the
+    /// message are signed (see `seal::Domain`).
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Version::is_legacy")]
+    pub version: Version
 }
 
 
@@ -80,7 +89,12 @@ pub struct Message {
     /// user-blind seeds/reveals.
     #[serde(default = "Vec::new")]
     #[serde(skip_serializing_if = "Vec::is_empty")]
-    pub blind: Vec<H256>
+    pub blind: Vec<H256>,
+
+    /// commitment to collapsed circuits (see `Checkpoint`).
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub checkpoint: Option<Checkpoint>
 }
 
 
@@ -93,9 +107,43 @@ impl Message {
             verify: Vec::new(),
             notary: Vec::new(),
             route: Vec::new(),
-            blind: Vec::new()
+            blind: Vec::new(),
+            checkpoint: None
         }
     }
+
+    /// number of steps collapsed into the checkpoint (if any).
+    pub fn collapsed(&self) -> usize {
+        self.checkpoint.as_ref().map(|checkpoint| checkpoint.steps as usize).unwrap_or(0)
+    }
+
+    /// numeric index of the next step (total number of certs,
+    /// including those collapsed into the checkpoint).
+    pub fn step_index(&self) -> usize {
+        let &Message { ref verify, ref notary, ref route, ref blind, .. } = self;
+        self.collapsed() + verify.len() + notary.len() + route.len() + blind.len()
+    }
+
+    /// get the next step expected under the supplied spec.
+    pub fn next_step(&self, spec: &CircuitSpec) -> STEP {
+        spec.step(self.step_index())
+    }
+
+    /// append a cert, rejecting it if it does not fill
+    /// the next step expected under the supplied spec.
+    pub fn append_cert(&mut self, spec: &CircuitSpec, cert: Cert) -> Result<(),StepError> {
+        let (expect,found) = (self.next_step(spec),cert.step());
+        if expect != found {
+            return Err(StepError { expect, found });
+        }
+        match cert {
+            Cert::Oracle(cert) | Cert::Verify(cert) => self.verify.push(cert),
+            Cert::Notary(cert) => self.notary.push(cert),
+            Cert::Blind(cert) | Cert::Clear(cert) => self.blind.push(cert),
+            Cert::Route(cert) => self.route.push(cert),
+        }
+        Ok(())
+    }
 }
 
 impl From<Payload> for Message {
@@ -106,3 +154,53 @@ impl From<Payload> for Message {
 }
 
 
+#[cfg(test)]
+mod tests {
+    use mimir_crypto::secp256k1::Signer;
+    use message::{Message,Payload,CircuitSpec,StepError,STEP,Code};
+    use message::cert::Cert;
+    use visit::{self,ByteVisitor};
+    use seal::{Sealer,Domain};
+    use rand;
+
+    #[test]
+    fn append_in_order() {
+        let (spec,domain) = (CircuitSpec::default(),Domain::legacy());
+        let signer: Signer = rand::random();
+        let mut message = Message::new(Payload::default());
+        assert_eq!(message.next_step(&spec),STEP::BLIND);
+        let steps = [
+            STEP::BLIND,STEP::ORACLE,STEP::NOTARY,STEP::CLEAR,
+            STEP::BLIND,STEP::ROUTE,STEP::VERIFY,STEP::VERIFY,STEP::NOTARY,
+        ];
+        for step in steps.iter() {
+            let cert = match *step {
+                STEP::BLIND => Cert::Blind(rand::random()),
+                STEP::CLEAR => Cert::Clear(rand::random()),
+                STEP::ORACLE => Cert::Oracle(signer.seal_oracle(&spec,&domain,&message)),
+                STEP::NOTARY => Cert::Notary(signer.seal_notary(&spec,&domain,&message)),
+                STEP::VERIFY => Cert::Verify(signer.seal_verify(&spec,&domain,&message,Code::Ok)),
+                STEP::ROUTE => Cert::Route(Default::default()),
+            };
+            message.append_cert(&spec,cert).unwrap();
+            let next = visit::apply(&spec,ByteVisitor::default(),&message);
+            assert_eq!(message.next_step(&spec),next);
+        }
+        assert_eq!(message.step_index(),steps.len());
+        assert_eq!(message.next_step(&spec),STEP::CLEAR);
+    }
+
+    #[test]
+    fn append_out_of_order() {
+        let spec = CircuitSpec::default();
+        let mut message = Message::new(Payload::default());
+        let expect = StepError { expect: STEP::BLIND, found: STEP::CLEAR };
+        assert_eq!(message.append_cert(&spec,Cert::Clear(rand::random())),Err(expect));
+        assert_eq!(message,Message::new(Payload::default()));
+        message.append_cert(&spec,Cert::Blind(rand::random())).unwrap();
+        let cert = Cert::Verify(Default::default());
+        let expect = StepError { expect: STEP::ORACLE, found: STEP::VERIFY };
+        assert_eq!(message.append_cert(&spec,cert),Err(expect));
+        assert!(message.verify.is_empty());
+    }
+}
@@ -1,22 +1,42 @@
 //! trustless routing helpers.
 //!
 
+use message::cert::{Clear,Notary};
+use mimir_crypto::Keccak256;
+
 mod proof;
 mod tree;
-mod node;
+pub(crate) mod node;
 
 
 pub use self::proof::{
     ProofError,
     NodeHash,
-    recover_root
+    recover_root,
+    MAX_PROOF_LEN,
 };
+pub use self::tree::RouteTree;
 pub use self::node::{
     Branch,
     Leaf
 };
 
 
+/// derive the traversal key for a given destination.
+///
+/// keys are seeded by the `clear` & `notary` certs which completed
+/// the preceding circuit, s.t. neither the router nor the requester
+/// can unilaterally select the destinations of a verify circuit.
+///
+pub fn traversal_key(clear: &Clear, notary: &Notary, dest: usize) -> [u8;32] {
+    let mut hasher = Keccak256::default();
+    hasher.absorb(clear.as_ref());
+    hasher.absorb(notary.sig.as_ref());
+    hasher.absorb(&[dest as u8]);
+    hasher.finish()
+}
+
+
 /// simple policy definitions
 pub(crate) mod policy {
     use mimir_crypto::Keccak256;
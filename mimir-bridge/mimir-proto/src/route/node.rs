@@ -16,6 +16,15 @@ impl<T> Branch<T> where T: AsRef<[u8]> {
     /// build branch from pair of leaf values
     pub fn from_values(left: T, right: T) -> Self {
         let (left,right) = (Leaf::new(left),Leaf::new(right));
+        Self::from_leaves(left,right)
+    }
+}
+
+
+impl<T> Branch<T> {
+
+    /// build branch from pair of leaves
+    pub fn from_leaves(left: Leaf<T>, right: Leaf<T>) -> Self {
         let mut hasher = Keccak256::default();
         hasher.absorb(&left.hash);
         hasher.absorb(&right.hash);
@@ -24,11 +33,6 @@ impl<T> Branch<T> where T: AsRef<[u8]> {
         Branch { hash, inner }
     }
 
-}
-
-
-impl<T> Branch<T> {
-
     /// build branch from pair of sub-branches
     pub fn from_branches(left: Branch<T>, right: Branch<T>) -> Self {
         let mut hasher = Keccak256::default();
@@ -39,6 +43,9 @@ impl<T> Branch<T> {
         Branch { hash, inner }
     }
 
+    /// get reference to hash of this node
+    pub fn hash(&self) -> &[u8;32] { &self.hash }
+
     /// get reference to lefthand child node
     pub fn left(&self) -> Either<&Branch<T>,&Leaf<T>> {
         match self.inner {
@@ -57,6 +64,42 @@ impl<T> Branch<T> {
 }
 
 
+/// build a complete tree from a power-of-two number of leaves,
+/// returning the root node.
+///
+/// *note*: panics if no leaves are supplied.
+///
+pub(crate) fn build<T>(mut leaves: Vec<Leaf<T>>) -> Either<Branch<T>,Leaf<T>> {
+    debug_assert!(leaves.len().is_power_of_two());
+    if leaves.len() == 1 {
+        return Either::B(leaves.remove(0));
+    }
+    let mut branches = Vec::with_capacity(leaves.len() / 2);
+    let mut leaves = leaves.into_iter();
+    while let (Some(left),Some(right)) = (leaves.next(),leaves.next()) {
+        branches.push(Branch::from_leaves(left,right));
+    }
+    while branches.len() > 1 {
+        let mut next = Vec::with_capacity(branches.len() / 2);
+        let mut prev = branches.into_iter();
+        while let (Some(left),Some(right)) = (prev.next(),prev.next()) {
+            next.push(Branch::from_branches(left,right));
+        }
+        branches = next;
+    }
+    Either::A(branches.remove(0))
+}
+
+
+/// get hash of an arbitrary child node
+pub(crate) fn hash_of<'a,T>(node: Either<&'a Branch<T>,&'a Leaf<T>>) -> &'a [u8;32] {
+    match node {
+        Either::A(branch) => branch.hash(),
+        Either::B(leaf) => &leaf.hash,
+    }
+}
+
+
 /// pair of values with "handedness".
 #[derive(Debug,Clone,PartialEq,Eq,PartialOrd,Ord)]
 struct Pair<T> {
@@ -1,3 +1,4 @@
+use mimir_types::H256;
 use route::policy;
 
 
@@ -8,13 +9,41 @@ simple_error!(
 );
 
 
+/// maximum number of nodes in a proof.  proof lengths are signed as a
+/// single byte (see `visit::absorb_route`), and no tree is deep enough
+/// to require a longer proof, so longer proofs are always rejected.
+pub const MAX_PROOF_LEN: usize = 255;
+
+
 /// hash of a child node
-#[derive(Debug,Clone,PartialEq,Eq)]
+#[derive(Debug,Copy,Clone,PartialEq,Eq,Serialize,Deserialize)]
+#[serde(rename_all = "lowercase")]
 pub enum NodeHash {
     /// hash of left child node
-    Left([u8;32]),
+    Left(H256),
     /// hash of right child node
-    Right([u8;32]),
+    Right(H256),
+}
+
+
+impl NodeHash {
+
+    /// get flag indicating "handedness" of node (`0x00` for
+    /// left, and `0x01` for right).
+    pub fn side(&self) -> u8 {
+        match *self {
+            NodeHash::Left(_) => 0x00,
+            NodeHash::Right(_) => 0x01,
+        }
+    }
+
+    /// get reference to inner hash value
+    pub fn hash(&self) -> &H256 {
+        match *self {
+            NodeHash::Left(ref hash) => hash,
+            NodeHash::Right(ref hash) => hash,
+        }
+    }
 }
 
 
@@ -22,12 +51,16 @@ pub enum NodeHash {
 ///
 /// Requires the traversal key in order to perform proper checks &
 /// the leaf hash in order to seed the path.  Returns a `ProofError`
-/// if traversal was not performed correctly.
+/// if traversal was not performed correctly, or if the path is longer
+/// than `MAX_PROOF_LEN`.
 ///
 /// *note*: returns the value of the leaf hash in the event of an
 /// empty path.
 ///
 pub fn recover_root(key: &[u8;32], leaf: &[u8;32], path: &[NodeHash]) -> Result<[u8;32],ProofError> {
+    if path.len() > MAX_PROOF_LEN {
+        return Err(ProofError::Path);
+    }
     let mut base_hash = leaf.to_owned();
     // iteratively verify proof (reverse of construction order).
     for node_hash in path.iter().rev() {
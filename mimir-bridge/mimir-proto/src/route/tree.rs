@@ -1 +1,216 @@
-// TODO
+use mimir_util::types::Either;
+use mimir_types::H256;
+use route::node::{self,hash_of};
+use route::{policy,Branch,Leaf,NodeHash};
+
+
+/// complete routing tree.
+///
+/// Constructed from a sorted & deduplicated set of items (typically
+/// validator addresses).  Sets which are not a power of two in size
+/// are padded by cycling through the sorted set from the beginning,
+/// s.t. any given set always produces the same tree.
+///
+/// Since traversal selects each leaf with equal probability, padding
+/// biases selection towards the lowest items of the set.  The bias is
+/// bounded: the tree holds fewer than twice as many leaves as items,
+/// so each item fills either one or two leaves, and no item is selected
+/// more than twice as often as any other.  Padding w/ leaves which hold
+/// no item would remove the bias, but would also leave some traversals
+/// without a valid destination.
+///
+#[derive(Debug,Clone,PartialEq,Eq)]
+pub struct RouteTree<T> {
+    root: Either<Branch<T>,Leaf<T>>,
+    size: usize,
+}
+
+
+impl<T> RouteTree<T> where T: AsRef<[u8]> + Ord + Clone {
+
+    /// build tree from a set of items.
+    ///
+    /// Returns `None` if no items were supplied.
+    ///
+    pub fn new<I>(items: I) -> Option<Self> where I: IntoIterator<Item=T> {
+        let mut items: Vec<T> = items.into_iter().collect();
+        items.sort();
+        items.dedup();
+        let size = items.len();
+        if size == 0 { return None; }
+        let padding = size.next_power_of_two() - size;
+        for index in 0..padding {
+            let item = items[index % size].clone();
+            items.push(item);
+        }
+        let leaves = items.into_iter().map(Leaf::new).collect();
+        let root = node::build(leaves);
+        Some(RouteTree { root, size })
+    }
+}
+
+
+impl<T> RouteTree<T> {
+
+    /// get root hash of tree
+    pub fn root(&self) -> &[u8;32] {
+        match self.root {
+            Either::A(ref branch) => branch.hash(),
+            Either::B(ref leaf) => &leaf.hash,
+        }
+    }
+
+    /// number of unique items in tree (excludes padding)
+    pub fn size(&self) -> usize { self.size }
+
+    /// get all leaves of the tree from left to right (includes padding).
+    pub fn leaves(&self) -> Vec<&Leaf<T>> {
+        let mut leaves = Vec::new();
+        let mut pending = vec![match self.root {
+            Either::A(ref branch) => Either::A(branch),
+            Either::B(ref leaf) => Either::B(leaf),
+        }];
+        while let Some(node) = pending.pop() {
+            match node {
+                Either::A(branch) => {
+                    pending.push(branch.right());
+                    pending.push(branch.left());
+                },
+                Either::B(leaf) => leaves.push(leaf),
+            }
+        }
+        leaves
+    }
+
+    /// traverse tree with specified key.
+    ///
+    /// Returns the selected leaf & the path of sibling hashes
+    /// (in construction order) which may be passed to `recover_root`
+    /// in order to verify the traversal.
+    ///
+    pub fn route(&self, key: &[u8;32]) -> (&Leaf<T>,Vec<NodeHash>) {
+        let mut path = Vec::new();
+        let mut node = match self.root {
+            Either::A(ref branch) => branch,
+            Either::B(ref leaf) => return (leaf,path),
+        };
+        loop {
+            let (next,sibling) = if policy::turn_right(key,node.hash()) {
+                (node.right(),NodeHash::Left(H256(*hash_of(node.left()))))
+            } else {
+                (node.left(),NodeHash::Right(H256(*hash_of(node.right()))))
+            };
+            path.push(sibling);
+            match next {
+                Either::A(branch) => { node = branch; },
+                Either::B(leaf) => return (leaf,path),
+            }
+        }
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use mimir_crypto::Address;
+    use route::{RouteTree,recover_root,ProofError};
+    use rand;
+
+    fn addrs(count: usize) -> Vec<Address> {
+        (0..count).map(|_| Address(rand::random())).collect()
+    }
+
+    fn check_routes(tree: &RouteTree<Address>, items: &[Address]) {
+        for _ in 0..64 {
+            let key: [u8;32] = rand::random();
+            let (leaf,path) = tree.route(&key);
+            assert!(items.contains(&leaf.item));
+            let root = recover_root(&key,&leaf.hash,&path).unwrap();
+            assert_eq!(&root,tree.root());
+        }
+    }
+
+    #[test]
+    fn single_leaf() {
+        let items = addrs(1);
+        let tree = RouteTree::new(items.clone()).unwrap();
+        assert_eq!(tree.size(),1);
+        let key: [u8;32] = rand::random();
+        let (leaf,path) = tree.route(&key);
+        assert_eq!(leaf.item,items[0]);
+        assert!(path.is_empty());
+        assert_eq!(tree.root(),&leaf.hash);
+        check_routes(&tree,&items);
+    }
+
+    #[test]
+    fn odd_size() {
+        for count in &[3,5,7,13] {
+            let items = addrs(*count);
+            let tree = RouteTree::new(items.clone()).unwrap();
+            assert_eq!(tree.size(),*count);
+            check_routes(&tree,&items);
+        }
+    }
+
+    #[test]
+    fn duplicate_items() {
+        let items = addrs(5);
+        let mut duped = items.clone();
+        duped.extend(items.iter().rev().cloned());
+        let tree = RouteTree::new(items.clone()).unwrap();
+        let duped_tree = RouteTree::new(duped).unwrap();
+        assert_eq!(duped_tree.size(),items.len());
+        assert_eq!(tree.root(),duped_tree.root());
+        check_routes(&duped_tree,&items);
+    }
+
+    #[test]
+    fn empty_set() {
+        assert!(RouteTree::<Address>::new(Vec::new()).is_none());
+    }
+
+    #[test]
+    fn oversized_path() {
+        let tree = RouteTree::new(addrs(8)).unwrap();
+        let key: [u8;32] = rand::random();
+        let (leaf,mut path) = tree.route(&key);
+        // a padded path must not be accepted in place of the real one.
+        let padding = path.len();
+        path.extend(vec![path[0];256 - padding]);
+        assert_eq!(recover_root(&key,&leaf.hash,&path),Err(ProofError::Path));
+    }
+
+    #[test]
+    fn padding_bias() {
+        for count in 1..34 {
+            let items = addrs(count);
+            let tree = RouteTree::new(items.clone()).unwrap();
+            let leaves = tree.leaves();
+            assert_eq!(leaves.len(),count.next_power_of_two());
+            // every item fills one or two leaves, and only padding is duplicated.
+            let mut sorted = items.clone();
+            sorted.sort();
+            let padding = leaves.len() - count;
+            for (index,item) in sorted.iter().enumerate() {
+                let filled = leaves.iter().filter(|leaf| &leaf.item == item).count();
+                assert_eq!(filled,if index < padding { 2 } else { 1 });
+            }
+        }
+    }
+
+    #[test]
+    fn bad_path() {
+        let tree = RouteTree::new(addrs(8)).unwrap();
+        let key: [u8;32] = rand::random();
+        let (leaf,path) = tree.route(&key);
+        // a proof fails under any key which routes to a different leaf.
+        let other = (0..).map(|_| rand::random::<[u8;32]>())
+            .find(|other| tree.route(other).0.item != leaf.item)
+            .unwrap();
+        assert_eq!(recover_root(&other,&leaf.hash,&path),Err(ProofError::Path));
+        let mut tampered = path.clone();
+        tampered.pop();
+        assert_ne!(recover_root(&key,&leaf.hash,&tampered),Ok(*tree.root()));
+    }
+}
@@ -0,0 +1,288 @@
+//! incremental sealing of messages.
+//!
+//! sealing a cert requires hashing every byte of the message which
+//! preceeds it.  the helpers in `seal::util` re-walk the entire message
+//! on each call, making the cost of building a message quadratic in its
+//! length.  the `MessageAccumulator` instead caches the accumulated bytes
+//! and the running `keccak-256` state, s.t. each successive seal only
+//! costs as much as the cert being added.
+//!
+use mimir_types::{Signature,Address,H256};
+use mimir_crypto::{Keccak256,Signer};
+use visit::{ByteVisitor,SeedVisitor,MessageVisitor,absorb_route};
+use message::{Message,Payload,CircuitSpec,Checkpoint,Code,STEP,StepError};
+use message::cert::{Cert,Oracle,Notary,Route,Verify};
+use route::RouteTree;
+use seal::{Domain,Tag};
+
+
+/// message which caches the state necessary to seal
+/// the next cert without re-walking prior certs.
+///
+pub struct MessageAccumulator {
+    spec: CircuitSpec,
+    domain: Domain,
+    message: Message,
+    bytes: ByteVisitor,
+    hasher: Keccak256,
+    seed: SeedVisitor,
+}
+
+
+impl MessageAccumulator {
+
+    /// begin accumulating a new message with no certs (sealed
+    /// under the signing version declared by the payload).
+    pub fn new(spec: CircuitSpec, domain: Domain, payload: Payload) -> Self {
+        let (mut bytes,seed,mut hasher) = (ByteVisitor::default(),SeedVisitor::default(),Keccak256::default());
+        bytes.visit_payload(&payload);
+        hasher.absorb(bytes.as_bytes());
+        let domain = domain.sealing(payload.version);
+        let message = Message::new(payload);
+        Self { spec, domain, message, bytes, hasher, seed }
+    }
+
+    /// accumulate an existing message (walks the message once).
+    ///
+    /// Returns `None` if the certs of the message do not
+    /// fill a contiguous sequence of steps under `spec`.
+    ///
+    pub fn from_message(spec: CircuitSpec, domain: Domain, message: Message) -> Option<Self> {
+        let (start,total) = (message.collapsed(),message.step_index());
+        let Message { payload, verify, notary, route, blind, checkpoint } = message;
+        let (mut verify,mut notary) = (verify.into_iter(),notary.into_iter());
+        let (mut route,mut blind) = (route.into_iter(),blind.into_iter());
+        let mut accumulator = Self::new(spec,domain,payload);
+        if let Some(checkpoint) = checkpoint {
+            accumulator.visit_checkpoint(checkpoint);
+        }
+        for _ in start..total {
+            let cert = match accumulator.next_step() {
+                STEP::ORACLE => verify.next().map(Cert::Oracle),
+                STEP::VERIFY => verify.next().map(Cert::Verify),
+                STEP::NOTARY => notary.next().map(Cert::Notary),
+                STEP::BLIND => blind.next().map(Cert::Blind),
+                STEP::CLEAR => blind.next().map(Cert::Clear),
+                STEP::ROUTE => route.next().map(Cert::Route),
+            }?;
+            accumulator.append_cert(cert).expect("always matches next step");
+        }
+        Some(accumulator)
+    }
+
+    /// get reference to the circuit spec.
+    pub fn spec(&self) -> &CircuitSpec { &self.spec }
+
+    /// get reference to the signing domain.
+    pub fn domain(&self) -> &Domain { &self.domain }
+
+    /// get reference to the accumulated message.
+    pub fn message(&self) -> &Message { &self.message }
+
+    /// get the accumulated message bytes (as produced by `ByteVisitor`).
+    pub fn as_bytes(&self) -> &[u8] { self.bytes.as_bytes() }
+
+    /// get the next step expected by the circuit spec.
+    pub fn next_step(&self) -> STEP { self.message.next_step(&self.spec) }
+
+    /// consume accumulator, returning the inner message.
+    pub fn into_inner(self) -> Message { self.message }
+
+    /// append a cert, rejecting it if it does not fill the next step.
+    pub fn append_cert(&mut self, cert: Cert) -> Result<(),StepError> {
+        self.check_step(cert.step())?;
+        let start = self.bytes.as_bytes().len();
+        match cert {
+            Cert::Oracle(ref cert) => { self.bytes.visit_oracle(cert); self.seed.visit_oracle(cert); },
+            Cert::Verify(ref cert) => { self.bytes.visit_verify(cert); self.seed.visit_verify(cert); },
+            Cert::Notary(ref cert) => { self.bytes.visit_notary(cert); self.seed.visit_notary(cert); },
+            Cert::Blind(ref cert) => { self.bytes.visit_blind(cert); self.seed.visit_blind(cert); },
+            Cert::Clear(ref cert) => { self.bytes.visit_clear(cert); self.seed.visit_clear(cert); },
+            Cert::Route(ref cert) => { self.bytes.visit_route(cert); self.seed.visit_route(cert); },
+        }
+        self.hasher.absorb(&self.bytes.as_bytes()[start..]);
+        self.message.append_cert(&self.spec,cert)
+    }
+
+    /// seal & append an oracle cert.
+    pub fn seal_oracle<S>(&mut self, signer: S) -> Result<Oracle,StepError> where S: Signer<Msg=[u8;32],Sig=Signature> {
+        self.check_step(STEP::ORACLE)?;
+        let cert = self.sign_verify(signer,Tag::Oracle,Code::Ok);
+        self.append_cert(Cert::Oracle(cert))?;
+        Ok(cert)
+    }
+
+    /// seal & append a notary cert.
+    pub fn seal_notary<S>(&mut self, signer: S) -> Result<Notary,StepError> where S: Signer<Msg=[u8;32],Sig=Signature> {
+        self.check_step(STEP::NOTARY)?;
+        let hash = self.domain.hash(Tag::Notary,&self.hasher.clone().finish());
+        let cert = Notary { sig: signer.sign(&hash) };
+        self.append_cert(Cert::Notary(cert))?;
+        Ok(cert)
+    }
+
+    /// seal & append a verify cert.
+    pub fn seal_verify<S>(&mut self, signer: S, val: Code) -> Result<Verify,StepError> where S: Signer<Msg=[u8;32],Sig=Signature> {
+        self.check_step(STEP::VERIFY)?;
+        let cert = self.sign_verify(signer,Tag::Verify,val);
+        self.append_cert(Cert::Verify(cert))?;
+        Ok(cert)
+    }
+
+    /// seal & append a route cert.
+    ///
+    /// Returns `Ok(None)` if message does not contain a completed
+    /// circuit with which to seed the traversal.
+    ///
+    pub fn seal_route<S>(&mut self, signer: S, tree: &RouteTree<Address>) -> Result<Option<Route>,StepError>
+            where S: Signer<Msg=[u8;32],Sig=Signature> {
+        self.check_step(STEP::ROUTE)?;
+        let dests = self.spec.dests();
+        let (mut val,mut path) = (Vec::with_capacity(dests),Vec::with_capacity(dests));
+        for dest in 0..dests {
+            let key = match self.seed.get_key(dest) {
+                Some(key) => key,
+                None => return Ok(None),
+            };
+            let (leaf,proof) = tree.route(&key);
+            val.push(leaf.item);
+            path.push(proof);
+        }
+        let root = H256(*tree.root());
+        let mut cert = Route { sig: Default::default(), root, val, path };
+        let mut hasher = self.hasher.clone();
+        absorb_route(&mut hasher,&cert);
+        cert.sig = signer.sign(&self.domain.hash(Tag::Route,&hasher.finish()));
+        self.append_cert(Cert::Route(cert.clone()))?;
+        Ok(Some(cert))
+    }
+
+    fn visit_checkpoint(&mut self, checkpoint: Checkpoint) {
+        let start = self.bytes.as_bytes().len();
+        self.bytes.visit_checkpoint(&checkpoint);
+        self.seed.visit_checkpoint(&checkpoint);
+        self.hasher.absorb(&self.bytes.as_bytes()[start..]);
+        self.message.checkpoint = Some(checkpoint);
+    }
+
+    fn sign_verify<S>(&self, signer: S, tag: Tag, val: Code) -> Verify where S: Signer<Msg=[u8;32],Sig=Signature> {
+        let mut hasher = self.hasher.clone();
+        hasher.absorb(&[val.as_u8()]);
+        let sig = signer.sign(&self.domain.hash(tag,&hasher.finish()));
+        Verify { sig, val }
+    }
+
+    fn check_step(&self, found: STEP) -> Result<(),StepError> {
+        let expect = self.next_step();
+        if expect == found { Ok(()) } else { Err(StepError { expect, found }) }
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use mimir_crypto::secp256k1::Signer;
+    use mimir_crypto::{Keccak256,Address};
+    use message::{Message,Payload,CircuitSpec,Code,STEP,StepError};
+    use message::cert::Cert;
+    use seal::{self,MessageAccumulator,Domain};
+    use visit::{self,ByteVisitor};
+    use route::RouteTree;
+    use rand;
+
+    /// build a message w/ `circuits` verify circuits, both incrementally
+    /// & via the standalone sealing helpers.
+    fn build(circuits: usize) -> (MessageAccumulator,Message) {
+        let spec = CircuitSpec::default();
+        let domain = Domain::new(1u64.into(),rand::random());
+        let signer: Signer = rand::random();
+        let validators: Vec<Address> = (0..4).map(|_| rand::random()).collect();
+        let tree = RouteTree::new(validators).unwrap();
+        let mut acc = MessageAccumulator::new(spec.clone(),domain,Payload::default());
+        let mut message = Message::new(Payload::default());
+        let mut seed = None;
+        for _ in 0..(4 + circuits * 6) {
+            let cert = match acc.next_step() {
+                STEP::BLIND => {
+                    let clear: [u8;32] = rand::random();
+                    seed = Some(clear.into());
+                    let cert = Cert::Blind(Keccak256::hash(&clear).into());
+                    acc.append_cert(cert.clone()).unwrap();
+                    cert
+                },
+                STEP::CLEAR => {
+                    let cert = Cert::Clear(seed.take().unwrap());
+                    acc.append_cert(cert.clone()).unwrap();
+                    cert
+                },
+                STEP::ORACLE => {
+                    let cert = acc.seal_oracle(&signer).unwrap();
+                    assert_eq!(cert,seal::oracle(&signer,&spec,&domain,&message));
+                    Cert::Oracle(cert)
+                },
+                STEP::NOTARY => {
+                    let cert = acc.seal_notary(&signer).unwrap();
+                    assert_eq!(cert,seal::notary(&signer,&spec,&domain,&message));
+                    Cert::Notary(cert)
+                },
+                STEP::ROUTE => {
+                    let cert = acc.seal_route(&signer,&tree).unwrap().unwrap();
+                    assert_eq!(Some(cert.clone()),seal::route(&signer,&spec,&domain,&message,&tree));
+                    Cert::Route(cert)
+                },
+                STEP::VERIFY => {
+                    let cert = acc.seal_verify(&signer,Code::Mismatch).unwrap();
+                    assert_eq!(cert,seal::verify(&signer,&spec,&domain,&message,Code::Mismatch));
+                    Cert::Verify(cert)
+                },
+            };
+            message.append_cert(&spec,cert).unwrap();
+        }
+        (acc,message)
+    }
+
+    #[test]
+    fn matches_full_walk() {
+        let spec = CircuitSpec::default();
+        let (acc,message) = build(3);
+        assert_eq!(acc.message(),&message);
+        let mut visitor = ByteVisitor::default();
+        visit::apply(&spec,&mut visitor,&message);
+        assert_eq!(acc.as_bytes(),&visitor.finish()[..]);
+        let resumed = MessageAccumulator::from_message(spec,*acc.domain(),message.clone()).unwrap();
+        assert_eq!(resumed.as_bytes(),acc.as_bytes());
+        assert_eq!(resumed.into_inner(),message);
+    }
+
+    #[test]
+    fn resume_from_checkpoint() {
+        let spec = CircuitSpec::default();
+        let signer: Signer = rand::random();
+        let tree = RouteTree::new(vec![signer.address()]).unwrap();
+        let (acc,mut message) = build(1);
+        let domain = *acc.domain();
+        let checkpoint = seal::checkpoint(&signer,&spec,&domain,&message).unwrap();
+        message.collapse(&spec,checkpoint).unwrap();
+        let mut acc = MessageAccumulator::from_message(spec.clone(),domain,message.clone()).unwrap();
+        let mut visitor = ByteVisitor::default();
+        visit::apply(&spec,&mut visitor,&message);
+        assert_eq!(acc.as_bytes(),&visitor.finish()[..]);
+        let blind = rand::random();
+        acc.append_cert(Cert::Blind(blind)).unwrap();
+        message.append_cert(&spec,Cert::Blind(blind)).unwrap();
+        let cert = acc.seal_route(&signer,&tree).unwrap().unwrap();
+        assert_eq!(Some(cert),seal::route(&signer,&spec,&domain,&message,&tree));
+    }
+
+    #[test]
+    fn out_of_order() {
+        let signer: Signer = rand::random();
+        let mut acc = MessageAccumulator::new(CircuitSpec::default(),Domain::legacy(),Payload::default());
+        let expect = StepError { expect: STEP::BLIND, found: STEP::NOTARY };
+        assert_eq!(acc.seal_notary(&signer),Err(expect));
+        assert_eq!(acc.message(),&Message::new(Payload::default()));
+        let mut message = Message::new(Payload::default());
+        message.notary.push(Default::default());
+        assert!(MessageAccumulator::from_message(CircuitSpec::default(),Domain::legacy(),message).is_none());
+    }
+}
@@ -0,0 +1,354 @@
+//! domain separation of signed hashes.
+//!
+//! a signing domain binds each signature to the kind of value being
+//! signed, the chain on which the bridge is deployed, and the address
+//! of the deployment's `WorkerSet` contract.  this prevents signatures
+//! from being replayed across deployments, or confused between kinds
+//! of certs.
+//!
+//! domains are versioned.  the `Legacy` version signs the plain hashes
+//! which preceeded signing domains (ignoring tag, chain & contract).
+//!
+//! each message declares the version under which its certs are signed (see
+//! `Payload::version`), and is verified under that version only if it is
+//! accepted by the verifying deployment (see `Domain::accept`).  the declared
+//! version is covered by every seal, since each version hashes differently
+//! (all versions other than `Legacy` absorb the version byte itself).  a
+//! deployment migrating from `Legacy` to `V1` may therefore continue to
+//! accept `Legacy` messages until all workers have switched, e.g.:
+//!
+//! ```toml
+//! version = 1
+//! chain = "0x1"
+//! contract = "0x000000000000000000000000000000000000beef"
+//! accept = [0]
+//! ```
+//!
+//! under `V1`, the signed hash is the keccak-256 hash of:
+//!
+//! ```text
+//! "mimir-bridge" ‖ version ‖ len(tag) ‖ tag ‖ chain ‖ contract ‖ hash
+//! ```
+//!
+//! where `version` is a single byte, `tag` is the utf-8 tag string,
+//! `chain` is the 32 byte big-endian chain id, `contract` is the 20
+//! byte contract address, and `hash` is the legacy hash.
+//!
+//! signatures produced by wallets via `eth_signTypedData` are instead bound
+//! to the `eip-712` domain returned by `Domain::typed`, which is named
+//! `"mimir-bridge"` and carries the numeric version, chain & contract.
+//!
+use serde::de::{self,Deserialize,Deserializer};
+use serde::ser::{Serialize,Serializer,SerializeSeq};
+use mimir_util::toml::{self,TomlError};
+use mimir_types::{Address,U256};
+use mimir_crypto::eip712::TypedDomain;
+use mimir_crypto::Keccak256;
+use std::path::Path;
+use std::fmt;
+
+
+/// prefix of all domain-separated hashes.
+const PREFIX: &[u8] = b"mimir-bridge";
+
+
+simple_unit!(
+    Tag, "kind of value covered by a signature",
+    Oracle  => "oracle",
+    Notary  => "notary",
+    Route   => "route",
+    Verify  => "verify",
+    Accuse  => "accuse",
+    Auth    => "auth",
+    Command => "command",
+    Checkpoint => "checkpoint",
+);
+
+
+/// version of the signing protocol.
+///
+/// numeric values are part of the protocol and must never be reassigned.
+///
+#[derive(Debug,Copy,Clone,Hash,PartialEq,Eq)]
+pub enum Version {
+    /// plain hashes, with no domain separation.
+    Legacy = 0x00,
+
+    /// hashes bound to tag, chain id & contract address.
+    V1 = 0x01,
+}
+
+
+impl Version {
+
+    /// get version from numeric value (if known).
+    pub fn from_u8(val: u8) -> Option<Self> {
+        match val {
+            0x00 => Some(Version::Legacy),
+            0x01 => Some(Version::V1),
+            _ => None,
+        }
+    }
+
+    /// get numeric value of version.
+    pub fn as_u8(&self) -> u8 { *self as u8 }
+
+    /// check if this is the legacy version.
+    pub fn is_legacy(&self) -> bool { *self == Version::Legacy }
+}
+
+
+impl Default for Version {
+
+    fn default() -> Self { Version::Legacy }
+}
+
+
+impl fmt::Display for Version {
+
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f,"v{}",self.as_u8())
+    }
+}
+
+
+impl Serialize for Version {
+
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok,S::Error> {
+        serializer.serialize_u8(self.as_u8())
+    }
+}
+
+
+impl<'de> Deserialize<'de> for Version {
+
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self,D::Error> {
+        let val = u8::deserialize(deserializer)?;
+        Version::from_u8(val).ok_or_else(|| {
+            de::Error::custom(format!("unknown signing version `{}`",val))
+        })
+    }
+}
+
+
+/// set of signing versions.
+#[derive(Default,Debug,Copy,Clone,Hash,PartialEq,Eq)]
+pub struct Versions(u8);
+
+
+impl Versions {
+
+    /// check if set contains no versions.
+    pub fn is_empty(&self) -> bool { self.0 == 0 }
+
+    /// check if set contains the specified version.
+    pub fn contains(&self, version: Version) -> bool {
+        self.0 & (1 << version.as_u8()) != 0
+    }
+
+    /// add a version to the set.
+    pub fn insert(&mut self, version: Version) {
+        self.0 |= 1 << version.as_u8();
+    }
+
+    /// iterate over the versions of the set in ascending order.
+    pub fn iter(&self) -> impl Iterator<Item=Version> {
+        let bits = self.0;
+        (0..8).filter(move |val| bits & (1 << val) != 0)
+            .filter_map(Version::from_u8)
+    }
+}
+
+
+impl Serialize for Versions {
+
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok,S::Error> {
+        let mut seq = serializer.serialize_seq(None)?;
+        for version in self.iter() {
+            seq.serialize_element(&version)?;
+        }
+        seq.end()
+    }
+}
+
+
+impl<'de> Deserialize<'de> for Versions {
+
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self,D::Error> {
+        let mut versions = Versions::default();
+        for version in Vec::<Version>::deserialize(deserializer)? {
+            versions.insert(version);
+        }
+        Ok(versions)
+    }
+}
+
+
+/// signing domain of a bridge deployment.
+///
+/// the default domain is the `Legacy` domain.
+///
+#[derive(Default,Debug,Copy,Clone,Hash,PartialEq,Eq,Serialize,Deserialize)]
+pub struct Domain {
+    /// version of the signing protocol
+    #[serde(default)]
+    pub version: Version,
+
+    /// id of the chain on which the bridge is deployed
+    #[serde(default)]
+    pub chain: U256,
+
+    /// address of the `WorkerSet` contract
+    #[serde(default)]
+    pub contract: Address,
+
+    /// versions (other than `version`) under which messages
+    /// are also accepted, e.g. during a migration.
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Versions::is_empty")]
+    pub accept: Versions,
+}
+
+
+impl Domain {
+
+    /// get the legacy (non-separated) domain.
+    pub fn legacy() -> Self { Default::default() }
+
+    /// get the current version of the domain for a deployment.
+    pub fn new(chain: U256, contract: Address) -> Self {
+        Domain { version: Version::V1, chain, contract, accept: Default::default() }
+    }
+
+    /// also accept messages signed under the specified version.
+    pub fn accepting(mut self, version: Version) -> Self {
+        self.accept.insert(version);
+        self
+    }
+
+    /// check if this is the legacy domain.
+    pub fn is_legacy(&self) -> bool { self.version.is_legacy() }
+
+    /// check if messages signed under the specified version are accepted.
+    pub fn accepts(&self, version: Version) -> bool {
+        self.version == version || self.accept.contains(version)
+    }
+
+    /// get this domain at the version declared by a message,
+    /// or `None` if the version is not accepted.
+    pub fn at(&self, version: Version) -> Option<Self> {
+        if self.accepts(version) {
+            Some(Domain { version, ..*self })
+        } else {
+            None
+        }
+    }
+
+    /// get the domain under which to seal a message declaring the specified
+    /// version.  unaccepted versions are sealed under this domain as-is, s.t.
+    /// the resulting certs never verify.
+    pub fn sealing(&self, version: Version) -> Self {
+        self.at(version).unwrap_or(*self)
+    }
+
+    /// attempt to load from target if exists, else return the legacy domain.
+    pub fn init<P: AsRef<Path>>(path: P) -> Result<Self,TomlError> {
+        if path.as_ref().exists() {
+            toml::load(path)
+        } else {
+            Ok(Default::default())
+        }
+    }
+
+    /// attempt to load from target file.
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self,TomlError> { toml::load(path) }
+
+    /// save to target file.
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> Result<(),TomlError> { toml::save(self,path) }
+
+    /// get the `eip-712` domain corresponding to this domain.
+    pub fn typed(&self) -> TypedDomain {
+        TypedDomain {
+            name: "mimir-bridge".to_owned(),
+            version: self.version.as_u8().to_string(),
+            chain_id: self.chain.0,
+            verifying_contract: self.contract,
+        }
+    }
+
+    /// separate a legacy hash into this domain under the supplied tag.
+    pub fn hash(&self, tag: Tag, hash: &[u8;32]) -> [u8;32] {
+        match self.version {
+            Version::Legacy => *hash,
+            Version::V1 => {
+                let tag = tag.as_ref().as_bytes();
+                let mut hasher = Keccak256::default();
+                hasher.absorb(PREFIX);
+                hasher.absorb(&[self.version.as_u8(),tag.len() as u8]);
+                hasher.absorb(tag);
+                hasher.absorb(self.chain.as_ref());
+                hasher.absorb(self.contract.as_ref());
+                hasher.absorb(hash);
+                hasher.finish()
+            },
+        }
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use seal::{Domain,Version,Tag};
+    use mimir_crypto::eip712::TypedDomain;
+    use serde_json;
+    use rand;
+
+    #[test]
+    fn separation() {
+        let hash: [u8;32] = rand::random();
+        let (chain,contract) = (1u64.into(),rand::random());
+        let domain = Domain::new(chain,contract);
+        assert_eq!(Domain::legacy().hash(Tag::Notary,&hash),hash);
+        assert!(domain.hash(Tag::Notary,&hash) != hash);
+        assert!(domain.hash(Tag::Notary,&hash) != domain.hash(Tag::Verify,&hash));
+        let other = Domain::new(3u64.into(),contract);
+        assert!(domain.hash(Tag::Notary,&hash) != other.hash(Tag::Notary,&hash));
+        let other = Domain::new(chain,rand::random());
+        assert!(domain.hash(Tag::Notary,&hash) != other.hash(Tag::Notary,&hash));
+    }
+
+    #[test]
+    fn typed_domain() {
+        let contract = rand::random();
+        let typed = Domain::new(1u64.into(),contract).typed();
+        assert_eq!((typed.name.as_ref(),typed.version.as_ref()),("mimir-bridge","1"));
+        assert_eq!(typed.separator(),TypedDomain::new("mimir-bridge","1",1,contract).separator());
+        assert!(typed.separator() != Domain::legacy().typed().separator());
+    }
+
+    #[test]
+    fn stable_versions() {
+        assert_eq!(Version::from_u8(0x00),Some(Version::Legacy));
+        assert_eq!(Version::from_u8(0x01),Some(Version::V1));
+        assert_eq!(Version::from_u8(0x02),None);
+        let domain = Domain::new(1u64.into(),rand::random());
+        let json = serde_json::to_string(&domain).unwrap();
+        assert_eq!(serde_json::from_str::<Domain>(&json).unwrap(),domain);
+        assert_eq!(serde_json::from_str::<Domain>("{}").unwrap(),Domain::legacy());
+    }
+
+    #[test]
+    fn accepted_versions() {
+        let domain = Domain::new(1u64.into(),rand::random());
+        assert!(domain.accepts(Version::V1) && !domain.accepts(Version::Legacy));
+        assert_eq!(domain.at(Version::Legacy),None);
+        let migrating = domain.accepting(Version::Legacy);
+        let legacy = migrating.at(Version::Legacy).unwrap();
+        assert!(legacy.is_legacy() && legacy.contract == domain.contract);
+        assert_eq!(migrating.at(Version::V1).map(|domain| domain.version),Some(Version::V1));
+        let json = serde_json::to_string(&migrating).unwrap();
+        assert!(json.contains(r#""accept":[0]"#));
+        assert_eq!(serde_json::from_str::<Domain>(&json).unwrap(),migrating);
+        assert!(!serde_json::to_string(&domain).unwrap().contains("accept"));
+    }
+}
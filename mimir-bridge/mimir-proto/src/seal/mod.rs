@@ -3,12 +3,21 @@
 
 pub mod sealer;
 pub mod util;
+pub mod accumulator;
+pub mod domain;
 
 pub use self::sealer::Sealer;
+pub use self::accumulator::MessageAccumulator;
+pub use self::domain::{
+    Domain,
+    Version,
+    Tag,
+};
 pub use self::util::{
     oracle,
     notary,
     verify,
     route,
+    checkpoint,
 };
 
@@ -1,7 +1,8 @@
 //! abstraction representing a type capable of seal generation.
 //!
 use mimir_types::{Signature,Address};
-use message::{Message,DESTS};
+use message::{Message,CircuitSpec,Checkpoint,Code};
+use route::RouteTree;
 use mimir_crypto::Signer;
 use message::cert::{
     Oracle,
@@ -9,7 +10,7 @@ use message::cert::{
     Route,
     Verify,
 };
-use seal;
+use seal::{self,Domain};
 
 
 /// signed cert generator
@@ -32,16 +33,21 @@ pub trait Sealer {
     fn address(&self) -> Address;
 
     /// seal message with an oracle cert
-    fn seal_oracle(&self, message: &Message) -> Oracle;
+    fn seal_oracle(&self, spec: &CircuitSpec, domain: &Domain, message: &Message) -> Oracle;
 
     /// seal message with a notary cert
-    fn seal_notary(&self, message: &Message) -> Notary;
+    fn seal_notary(&self, spec: &CircuitSpec, domain: &Domain, message: &Message) -> Notary;
 
-    /// seal message with a route cert
-    fn seal_route(&self, message: &Message, route: [Address;DESTS]) -> Route;
+    /// seal message with a route cert (`None` if message
+    /// contains no completed circuit to seed routing).
+    fn seal_route(&self, spec: &CircuitSpec, domain: &Domain, message: &Message, tree: &RouteTree<Address>) -> Option<Route>;
 
     /// seal message with a verify cert
-    fn seal_verify(&self, message: &Message, flag: u8) -> Verify;
+    fn seal_verify(&self, spec: &CircuitSpec, domain: &Domain, message: &Message, flag: Code) -> Verify;
+
+    /// seal completed circuits of message into a checkpoint (`None` if
+    /// message contains no completed circuit to collapse).
+    fn seal_checkpoint(&self, spec: &CircuitSpec, domain: &Domain, message: &Message) -> Option<Checkpoint>;
 }
 
 
@@ -54,15 +60,18 @@ impl<T> Sealer for T where T: Signer<Sig=Signature,Msg=[u8;32],Pub=Address> {
     fn address(&self) -> Address { <Self as Signer>::identify(self) }
 
     /// seal message with an oracle cert
-    fn seal_oracle(&self, message: &Message) -> Oracle { seal::oracle(self,message) }
+    fn seal_oracle(&self, spec: &CircuitSpec, domain: &Domain, message: &Message) -> Oracle { seal::oracle(self,spec,domain,message) }
 
     /// seal message with a notary cert
-    fn seal_notary(&self, message: &Message) -> Notary { seal::notary(self,message) }
+    fn seal_notary(&self, spec: &CircuitSpec, domain: &Domain, message: &Message) -> Notary { seal::notary(self,spec,domain,message) }
 
     /// seal message with a route cert
-    fn seal_route(&self, message: &Message, route: [Address;DESTS]) -> Route { seal::route(self,message,route) }
+    fn seal_route(&self, spec: &CircuitSpec, domain: &Domain, message: &Message, tree: &RouteTree<Address>) -> Option<Route> { seal::route(self,spec,domain,message,tree) }
 
     /// seal message with a verify cert
-    fn seal_verify(&self, message: &Message, flag: u8) -> Verify { seal::verify(self,message,flag) }
+    fn seal_verify(&self, spec: &CircuitSpec, domain: &Domain, message: &Message, flag: Code) -> Verify { seal::verify(self,spec,domain,message,flag) }
+
+    /// seal completed circuits of message into a checkpoint
+    fn seal_checkpoint(&self, spec: &CircuitSpec, domain: &Domain, message: &Message) -> Option<Checkpoint> { seal::checkpoint(self,spec,domain,message) }
 }
 
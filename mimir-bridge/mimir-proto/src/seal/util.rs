@@ -1,77 +1,129 @@
 //! utilities related to the signed subset of certs
 //!
-use mimir_types::{Signature,Address};
+//! messages are sealed under the signing version declared by their
+//! payload (see `Domain::sealing`), whereas the `raw` utilities seal
+//! under the supplied domain as-is.
+//!
+use mimir_types::{Signature,Address,H256};
 use mimir_crypto::Signer;
-use visit::{self,ByteVisitor};
+use visit::{self,ByteVisitor,SeedVisitor,MessageVisitor,absorb_checkpoint};
 use message::cert::{Oracle,Notary,Verify,Route};
-use message::{Message,DESTS};
+use message::{Message,CircuitSpec,Checkpoint,Code};
+use mimir_crypto::Keccak256;
+use route::RouteTree;
+use seal::{Domain,Tag};
 
 
 /// generate oracle cert for message
 #[inline]
-pub fn oracle<S>(signer: S, message: &Message) -> Oracle where S: Signer<Msg=[u8;32],Sig=Signature> {
+pub fn oracle<S>(signer: S, spec: &CircuitSpec, domain: &Domain, message: &Message) -> Oracle where S: Signer<Msg=[u8;32],Sig=Signature> {
     let mut visitor = ByteVisitor::default();
-    visit::apply(&mut visitor,message);
+    visit::apply(spec,&mut visitor,message);
     let bytes = visitor.finish();
-    raw::oracle(signer,&bytes)
+    raw::oracle(signer,&domain.sealing(message.payload.version),&bytes)
 }
 
 
 /// generate notary cert for message
 #[inline]
-pub fn notary<S>(signer: S, message: &Message) -> Notary where S: Signer<Msg=[u8;32],Sig=Signature> {
+pub fn notary<S>(signer: S, spec: &CircuitSpec, domain: &Domain, message: &Message) -> Notary where S: Signer<Msg=[u8;32],Sig=Signature> {
     let mut visitor = ByteVisitor::default();
-    visit::apply(&mut visitor,message);
+    visit::apply(spec,&mut visitor,message);
     let bytes = visitor.finish();
-    raw::notary(signer,&bytes)
+    raw::notary(signer,&domain.sealing(message.payload.version),&bytes)
 }
 
 
 /// generate verify cert for message
 #[inline]
-pub fn verify<S>(signer: S, message: &Message, val: u8) -> Verify where S: Signer<Msg=[u8;32],Sig=Signature> {
+pub fn verify<S>(signer: S, spec: &CircuitSpec, domain: &Domain, message: &Message, val: Code) -> Verify where S: Signer<Msg=[u8;32],Sig=Signature> {
     let mut visitor = ByteVisitor::default();
-    visit::apply(&mut visitor,message);
+    visit::apply(spec,&mut visitor,message);
     let bytes = visitor.finish();
-    raw::verify(signer,&bytes,val)
+    raw::verify(signer,&domain.sealing(message.payload.version),&bytes,val)
 }
 
 
 /// generate route cert for message
+///
+/// destinations are selected by traversing the supplied validator
+/// routing tree.  returns `None` if message does not contain a
+/// completed circuit with which to seed the traversal.
 #[inline]
-pub fn route<S>(signer: S, message: &Message, val: [Address;DESTS]) -> Route where S: Signer<Msg=[u8;32],Sig=Signature> {
-    let mut visitor = ByteVisitor::default();
-    visit::apply(&mut visitor,message);
+pub fn route<S>(signer: S, spec: &CircuitSpec, domain: &Domain, message: &Message, tree: &RouteTree<Address>) -> Option<Route> where S: Signer<Msg=[u8;32],Sig=Signature> {
+    let (mut visitor,mut seed) = (ByteVisitor::default(),SeedVisitor::default());
+    visit::apply(spec,&mut visitor,message);
+    visit::apply(spec,&mut seed,message);
+    let (mut val,mut path) = (Vec::with_capacity(spec.dests()),Vec::with_capacity(spec.dests()));
+    for dest in 0..spec.dests() {
+        let key = seed.get_key(dest)?;
+        let (leaf,proof) = tree.route(&key);
+        val.push(leaf.item);
+        path.push(proof);
+    }
+    let root = H256(*tree.root());
     let bytes = visitor.finish();
-    raw::route(signer,&bytes,val)
+    Some(raw::route(signer,&domain.sealing(message.payload.version),&bytes,root,val,path))
 }
 
 
 
+/// generate checkpoint for message
+///
+/// collapses all completed circuits which follow the current
+/// checkpoint (if any).  returns `None` if message does not contain
+/// a completed circuit which has not already been collapsed.  the
+/// resulting checkpoint must be applied via `Message::collapse`.
+pub fn checkpoint<S>(signer: S, spec: &CircuitSpec, domain: &Domain, message: &Message) -> Option<Checkpoint> where S: Signer<Msg=[u8;32],Sig=Signature> {
+    let offset = message.collapsed();
+    let steps = ((offset + 1)..(message.step_index() + 1)).rev()
+        .find(|index| spec.is_boundary(*index))?;
+    let history = message.prefix(spec,steps)?;
+    let (mut visitor,mut seed) = (ByteVisitor::default(),SeedVisitor::default());
+    visit::apply(spec,&mut visitor,&history);
+    visit::apply(spec,&mut seed,&history);
+    let (clear,seal) = seed.get_seed()?;
+    let root = H256(Keccak256::hash(visitor.as_bytes()));
+    let mut checkpoint = Checkpoint { steps: steps as u32, root, clear: *clear, seal: *seal, sig: Default::default() };
+    // checkpoint is signed following the payload, in place of the collapsed certs.
+    let mut visitor = ByteVisitor::default();
+    visitor.visit_payload(&message.payload);
+    let mut hasher = Keccak256::default();
+    hasher.absorb(visitor.as_bytes());
+    absorb_checkpoint(&mut hasher,&checkpoint);
+    let domain = domain.sealing(message.payload.version);
+    checkpoint.sig = signer.sign(&domain.hash(Tag::Checkpoint,&hasher.finish()));
+    Some(checkpoint)
+}
+
+
 /// utilities for generating certs of raw bytes
 ///
 pub mod raw {
-    use mimir_types::{Signature,Address};
+    use mimir_types::{Signature,Address,H256};
     use mimir_crypto::{Keccak256,Signer};
     use message::cert::{Verify,Oracle,Notary,Route};
-    use message::DESTS;
+    use message::Code;
+    use visit::absorb_route;
+    use route::NodeHash;
+    use seal::{Domain,Tag};
     
     
     /// required flag for oracle cert
-    pub(crate) const ORACLE_FLAG: u8 = 0x00;
+    pub(crate) const ORACLE_FLAG: Code = Code::Ok;
 
 
     /// build oracle cert of raw message bytes
     #[inline]
-    pub fn oracle<S>(signer: S, bytes: &[u8]) -> Oracle where S: Signer<Msg=[u8;32],Sig=Signature> {
-        verify(signer,bytes,ORACLE_FLAG)
+    pub fn oracle<S>(signer: S, domain: &Domain, bytes: &[u8]) -> Oracle where S: Signer<Msg=[u8;32],Sig=Signature> {
+        sign_verify(signer,domain,Tag::Oracle,bytes,ORACLE_FLAG)
     }
 
 
     /// build notary cert of raw message bytes
     #[inline]
-    pub fn notary<S>(signer: S, bytes: &[u8]) -> Notary where S: Signer<Msg=[u8;32],Sig=Signature> {
-        let hash = Keccak256::hash(bytes);
+    pub fn notary<S>(signer: S, domain: &Domain, bytes: &[u8]) -> Notary where S: Signer<Msg=[u8;32],Sig=Signature> {
+        let hash = domain.hash(Tag::Notary,&Keccak256::hash(bytes));
         let sig = signer.sign(&hash);
         Notary { sig }
     }
@@ -79,27 +131,33 @@ pub mod raw {
 
     /// build verify cert of raw message bytes
     #[inline]
-    pub fn verify<S>(signer: S, bytes: &[u8], val: u8) -> Verify where S: Signer<Msg=[u8;32],Sig=Signature> {
-        let mut hasher = Keccak256::default();
-        hasher.absorb(bytes);
-        hasher.absorb(&[val]);
-        let hash = hasher.finish();
-        let sig = signer.sign(&hash);
-        Verify { sig, val }
+    pub fn verify<S>(signer: S, domain: &Domain, bytes: &[u8], val: Code) -> Verify where S: Signer<Msg=[u8;32],Sig=Signature> {
+        sign_verify(signer,domain,Tag::Verify,bytes,val)
     }
 
 
     /// build route cert
     #[inline]
-    pub fn route<S>(signer: S, bytes: &[u8], val: [Address;DESTS]) -> Route where S: Signer<Msg=[u8;32],Sig=Signature> {
+    pub fn route<S>(signer: S, domain: &Domain, bytes: &[u8], root: H256, val: Vec<Address>, path: Vec<Vec<NodeHash>>) -> Route
+            where S: Signer<Msg=[u8;32],Sig=Signature> {
+        let mut cert = Route { sig: Default::default(), root, val, path };
         let mut hasher = Keccak256::default();
         hasher.absorb(bytes);
-        for address in val.iter() {
-            hasher.absorb(address.as_ref())
-        }
-        let hash = hasher.finish();
+        absorb_route(&mut hasher,&cert);
+        let hash = domain.hash(Tag::Route,&hasher.finish());
+        cert.sig = signer.sign(&hash);
+        cert
+    }
+
+
+    /// sign raw message bytes & flag (shared by oracle & verify certs).
+    fn sign_verify<S>(signer: S, domain: &Domain, tag: Tag, bytes: &[u8], val: Code) -> Verify where S: Signer<Msg=[u8;32],Sig=Signature> {
+        let mut hasher = Keccak256::default();
+        hasher.absorb(bytes);
+        hasher.absorb(&[val.as_u8()]);
+        let hash = domain.hash(tag,&hasher.finish());
         let sig = signer.sign(&hash);
-        Route { sig, val }
+        Verify { sig, val }
     }
 }
 
@@ -0,0 +1,288 @@
+//! generators of arbitrary messages for use in tests.
+//!
+//! available under the `testkit` feature.  a `Fixture` holds a random
+//! set of entities (along with a `BlockState` which knows of them), and
+//! builds well-formed messages of any length which are signed by those
+//! entities.  corrupted variants of the same messages may be built with
+//! `Fixture::corrupted`; every corrupted message is expected to be
+//! rejected (see `Corruption` for the rejection case of each variant).
+//!
+//! the `vectors` module builds the golden vector corpus, which pins down
+//! the wire format for implementations in other languages.
+//!
+use mimir_crypto::secp256k1::Signer;
+use mimir_crypto::Keccak256;
+use mimir_types::Address;
+use message::{Message,Payload,CircuitSpec,Circuit,Code,STEP};
+use message::cert::Cert;
+use judge::{JudgeVisitor,Verdict};
+use visit::{self,BlockState};
+use seal::{Sealer,Domain};
+use route::RouteTree;
+use rand::{self,Rng};
+
+pub mod vectors;
+
+
+/// targeted corruption of an otherwise well-formed message.
+///
+#[derive(Debug,Copy,Clone,PartialEq,Eq)]
+pub enum Corruption {
+    /// signature of a signed cert is altered (rejected by the judge).
+    Signature,
+
+    /// flag of an `oracle` or `verify` cert is altered after
+    /// signing (rejected by the judge).
+    Flag,
+
+    /// a `clear` cert does not match its `blind` (rejected by the judge).
+    Clear,
+
+    /// an `oracle` or `verify` cert is signed by a validator unknown
+    /// to the block (rejected by the judge).
+    Foreign,
+
+    /// a cert vector is truncated, leaving unpaired certs
+    /// (rejected by `visit::validate`).
+    Truncate,
+}
+
+
+impl Corruption {
+
+    /// get all corruption variants.
+    pub fn all() -> &'static [Corruption] {
+        &[
+            Corruption::Signature,
+            Corruption::Flag,
+            Corruption::Clear,
+            Corruption::Foreign,
+            Corruption::Truncate,
+        ]
+    }
+}
+
+
+/// random set of entities & a payload for messages to carry.
+///
+#[derive(Debug,Clone)]
+pub struct Fixture {
+    /// circuit spec of generated messages
+    pub spec: CircuitSpec,
+
+    /// signing domain of generated messages
+    pub domain: Domain,
+
+    /// payload of generated messages
+    pub payload: Payload,
+
+    /// validators (acting as both oracles & verifiers)
+    pub validators: Vec<Signer>,
+
+    /// known notaries
+    pub notaries: Vec<Signer>,
+
+    /// known routers
+    pub routers: Vec<Signer>,
+
+    /// routing tree of the validator set
+    pub tree: RouteTree<Address>,
+}
+
+
+impl Default for Fixture {
+
+    fn default() -> Self { Self::new(Default::default(),Domain::legacy()) }
+}
+
+
+impl Fixture {
+
+    /// generate random entities for messages of the supplied spec &
+    /// domain (enough validators are generated to fill all destinations).
+    pub fn new(spec: CircuitSpec, domain: Domain) -> Self {
+        let validators: Vec<Signer> = (0..(spec.dests() + 4)).map(|_| rand::random()).collect();
+        let notaries = (0..2).map(|_| rand::random()).collect();
+        let routers = (0..2).map(|_| rand::random()).collect();
+        let tree = RouteTree::new(validators.iter().map(|v| v.address()))
+            .expect("validator set is non-empty");
+        let record = r#"{"method":"eth_gasPrice","result":"0xdeadbeef"}"#.into();
+        let (address,number,hash) = rand::random();
+        let payload = Payload { record, address, number, hash, version: domain.version };
+        Fixture { spec, domain, payload, validators, notaries, routers, tree }
+    }
+
+    /// get the state of the block which generated messages are pinned to.
+    pub fn block(&self) -> BlockState {
+        let addrs = |signers: &[Signer]| signers.iter().map(|s| s.address()).collect();
+        BlockState::new()
+            .validators(addrs(&self.validators))
+            .notaries(addrs(&self.notaries))
+            .routers(addrs(&self.routers))
+            .number(self.payload.number)
+            .hash(self.payload.hash)
+    }
+
+    /// number of steps in a message w/ the supplied number of
+    /// verify circuits (following the oracle circuit).
+    pub fn len(&self, circuits: usize) -> usize {
+        self.spec.oracle().len() + circuits * self.spec.verify().len()
+    }
+
+    /// build a well-formed message of `steps` steps (circuits
+    /// are left incomplete if `steps` is not a boundary).
+    pub fn steps(&self, steps: usize) -> Message {
+        self.build(steps,None)
+    }
+
+    /// build a well-formed message w/ a completed oracle circuit,
+    /// followed by `circuits` completed verify circuits.
+    pub fn message(&self, circuits: usize) -> Message {
+        self.steps(self.len(circuits))
+    }
+
+    /// build a message as per `Fixture::message`, w/ a single
+    /// randomly placed corruption of the specified kind.
+    pub fn corrupted(&self, circuits: usize, corruption: Corruption) -> Message {
+        let mut rng = rand::thread_rng();
+        if corruption == Corruption::Foreign {
+            let signed = (0..self.len(circuits))
+                .filter(|index| [STEP::ORACLE,STEP::VERIFY].contains(&self.spec.step(*index)))
+                .collect::<Vec<_>>();
+            let target = *rng.choose(&signed).expect("oracle step always exists");
+            return self.build(self.len(circuits),Some(target));
+        }
+        let mut message = self.message(circuits);
+        match corruption {
+            Corruption::Signature => {
+                let sig = {
+                    let mut sigs = Vec::new();
+                    sigs.extend(message.verify.iter_mut().map(|cert| &mut cert.sig));
+                    sigs.extend(message.notary.iter_mut().map(|cert| &mut cert.sig));
+                    sigs.extend(message.route.iter_mut().map(|cert| &mut cert.sig));
+                    let index = rng.gen_range(0,sigs.len());
+                    sigs.swap_remove(index)
+                };
+                sig.0[0] ^= 0x01;
+            },
+            Corruption::Flag => {
+                let index = rng.gen_range(0,message.verify.len());
+                let cert = &mut message.verify[index];
+                cert.val = if cert.val.is_ok() { Code::Mismatch } else { Code::Ok };
+            },
+            Corruption::Clear => {
+                let clears = message.blind.len() / 2;
+                let index = 2 * rng.gen_range(0,clears) + 1;
+                message.blind[index] = rand::random();
+            },
+            Corruption::Truncate => {
+                // the final cert is always a `clear`, so truncating any
+                // other vector leaves the certs following it unpaired.
+                let vectors = if message.route.is_empty() { 2 } else { 3 };
+                match rng.gen_range(0,vectors) {
+                    0 => { message.verify.pop(); },
+                    1 => { message.notary.pop(); },
+                    _ => { message.route.pop(); },
+                }
+            },
+            Corruption::Foreign => unreachable!(),
+        }
+        message
+    }
+
+    /// judge a message against the fixture's block.
+    pub fn judge(&self, message: &Message) -> Verdict {
+        let mut judge = JudgeVisitor::with_spec(self.block(),&self.spec,self.domain);
+        visit::apply(&self.spec,&mut judge,message);
+        judge.finish()
+    }
+
+    /// build a message of `steps` steps, w/ the cert at step
+    /// `foreign` (if any) signed by an unknown validator.
+    fn build(&self, steps: usize, foreign: Option<usize>) -> Message {
+        let mut rng = rand::thread_rng();
+        let (spec,domain) = (&self.spec,&self.domain);
+        let mut message = Message::new(self.payload.clone());
+        let mut clear = None;
+        for index in 0..steps {
+            let validator = match foreign {
+                Some(target) if target == index => rand::random(),
+                _ => rng.choose(&self.validators).expect("validators non-empty").clone(),
+            };
+            let cert = match spec.step(index) {
+                STEP::ORACLE => Cert::Oracle(validator.seal_oracle(spec,domain,&message)),
+                STEP::NOTARY => {
+                    let notary = rng.choose(&self.notaries).expect("notaries non-empty");
+                    Cert::Notary(notary.seal_notary(spec,domain,&message))
+                },
+                STEP::BLIND => {
+                    let seed: [u8;32] = rand::random();
+                    clear = Some(seed);
+                    Cert::Blind(Keccak256::hash(&seed).into())
+                },
+                STEP::CLEAR => {
+                    Cert::Clear(clear.take().expect("blind preceeds clear").into())
+                },
+                STEP::ROUTE => {
+                    let router = rng.choose(&self.routers).expect("routers non-empty");
+                    let cert = router.seal_route(spec,domain,&message,&self.tree)
+                        .expect("route follows a completed circuit");
+                    Cert::Route(cert)
+                },
+                STEP::VERIFY => {
+                    let dest = next_dest(spec,&message);
+                    let verifier = match foreign {
+                        Some(target) if target == index => validator,
+                        _ => self.validators.iter().find(|v| v.address() == dest)
+                            .expect("dests are validators").clone(),
+                    };
+                    Cert::Verify(verifier.seal_verify(spec,domain,&message,Code::Ok))
+                },
+            };
+            message.append_cert(spec,cert).expect("cert matches next step");
+        }
+        message
+    }
+}
+
+
+/// get the destination expected to sign the next verify step
+/// of a message (destinations sign in route order).
+fn next_dest(spec: &CircuitSpec, message: &Message) -> Address {
+    match message.circuits(spec).pop() {
+        Some(Circuit::Verify(ref circuit)) => circuit.next_dest(),
+        _ => None,
+    }.expect("route preceeds verify")
+}
+
+
+#[cfg(test)]
+mod tests {
+    use testkit::{Fixture,Corruption};
+    use visit;
+
+    #[test]
+    fn well_formed() {
+        let fixture = Fixture::default();
+        for circuits in 0..4 {
+            let message = fixture.message(circuits);
+            assert_eq!(message.step_index(),fixture.len(circuits));
+            assert!(visit::validate(&fixture.spec,&message).is_ok());
+            assert!(fixture.judge(&message).is_ok());
+        }
+    }
+
+    #[test]
+    fn corruptions_rejected() {
+        let fixture = Fixture::default();
+        for circuits in 0..3 {
+            for corruption in Corruption::all() {
+                let message = fixture.corrupted(circuits,*corruption);
+                match *corruption {
+                    Corruption::Truncate => assert!(visit::validate(&fixture.spec,&message).is_err()),
+                    _ => assert!(!fixture.judge(&message).is_ok(),"{:?} not rejected",corruption),
+                }
+            }
+        }
+    }
+}
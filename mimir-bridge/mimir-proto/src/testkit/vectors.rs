@@ -0,0 +1,359 @@
+//! deterministic golden vectors.
+//!
+//! implementations in other languages (e.g. the on-chain arbiter) must
+//! hash & sign messages exactly as this crate does.  the corpus built
+//! here records, for a fixed set of messages, the message itself, its
+//! binary encoding, its signed byte stream, the signing hash & signer
+//! of each cert, and signed accusations against each accusable cert.
+//!
+//! all keys & blinding values are fixed, and signatures are generated
+//! deterministically (RFC 6979), so the corpus is reproducible byte for
+//! byte.  the corpus is checked in at `vectors/corpus.json`, & should be
+//! regenerated w/ the `mimir-vectors` binary after any intentional change
+//! to the wire format.  all vectors use the default circuit spec.
+//!
+use mimir_crypto::secp256k1::{Signer,Secret,Verifier};
+use mimir_crypto::Keccak256;
+use mimir_types::{Address,Signature,Bytes,H256};
+use message::cert::{Cert,Oracle,Notary,Blind,Clear,Route,Verify};
+use message::{Message,Payload,Checkpoint,Batch,CircuitSpec,Code,STEP};
+use testkit::next_dest;
+use visit::{self,MessageVisitor,ByteVisitor,CertHasher,Encode};
+use judge::accuse::{self,Accusation};
+use seal::{Sealer,Domain,Tag};
+use route::RouteTree;
+
+
+/// corpus of golden vectors.
+///
+#[derive(Debug,Clone,PartialEq,Eq,Serialize,Deserialize)]
+pub struct Corpus {
+    /// address of the accuser of all accusations
+    pub accuser: Address,
+
+    /// individual vectors
+    pub vectors: Vec<Vector>,
+}
+
+
+/// golden vector of a single message.
+///
+#[derive(Debug,Clone,PartialEq,Eq,Serialize,Deserialize)]
+pub struct Vector {
+    /// name of the vector
+    pub name: String,
+
+    /// signing domain of all signatures
+    pub domain: Domain,
+
+    /// the message itself
+    pub message: Message,
+
+    /// binary encoding of the message (see `visit::Encode`)
+    pub encoded: Bytes,
+
+    /// signed byte stream of the message (see `visit::ByteVisitor`)
+    pub bytes: Bytes,
+
+    /// `keccak-256` hash of the signed byte stream
+    pub hash: H256,
+
+    /// signing hash & signer of each signed cert, in visitation order
+    pub certs: Vec<Signed>,
+
+    /// accusations against each accusable cert, in order of generation
+    pub accusations: Vec<Accusation>,
+}
+
+
+/// signing hash & recovered signer of a cert.
+///
+#[derive(Debug,Clone,PartialEq,Eq,Serialize,Deserialize)]
+pub struct Signed {
+    /// kind of signed value
+    pub tag: Tag,
+
+    /// domain-separated hash which was signed
+    pub hash: H256,
+
+    /// address of the signer
+    pub signer: Address,
+}
+
+
+/// build the golden vector corpus.
+pub fn corpus() -> Corpus {
+    let domain = Domain::new(1u64.into(),Address([0xaa;20]));
+    let mut vectors = Vec::new();
+    let circuits = [("oracle-circuit",0),("verify-circuit",1),("verify-circuits",2)];
+    for &(name,circuits) in circuits.iter() {
+        vectors.push(Builder::new(domain).build(name,payload(),circuits));
+    }
+    vectors.push(Builder::new(Domain::legacy()).build("legacy-domain",payload(),1));
+    vectors.push(Builder::new(domain).code(Code::Mismatch).build("disputed",payload(),1));
+    let records = (0..3).map(|id| format!(r#"{{"id":{}}}"#,id));
+    let record = Batch::new(records).expect("batch is non-empty").into();
+    let batched = Payload { record, ..payload() };
+    vectors.push(Builder::new(domain).build("batched-record",batched,1));
+    vectors.push(Builder::new(domain).checkpointed("checkpoint",payload()));
+    let accuser = key(0xac).address();
+    Corpus { accuser, vectors }
+}
+
+
+/// fixed payload of all vectors.
+fn payload() -> Payload {
+    let record = r#"{"method":"eth_gasPrice","result":"0xdeadbeef"}"#.into();
+    let (address,number,hash) = (Address([0x11;20]),0xdeadu64.into(),H256([0x22;32]));
+    Payload { record, address, number, hash, version: Default::default() }
+}
+
+
+/// fixed signing key.
+fn key(seed: u8) -> Signer {
+    Signer::new(Secret([seed;32])).expect("seed is a valid secret")
+}
+
+
+/// fixed clear value of the circuit beginning at `index`.
+fn clear(index: usize) -> [u8;32] {
+    Keccak256::hash(&[b'c',index as u8])
+}
+
+
+/// deterministic message builder.
+struct Builder {
+    spec: CircuitSpec,
+    domain: Domain,
+    code: Code,
+    oracle: Signer,
+    notary: Signer,
+    router: Signer,
+    accuser: Signer,
+    validators: Vec<Signer>,
+    tree: RouteTree<Address>,
+    accusations: Vec<Accusation>,
+}
+
+
+impl Builder {
+
+    fn new(domain: Domain) -> Self {
+        let validators: Vec<Signer> = (0..4).map(|seed| key(0x10 + seed)).collect();
+        let tree = RouteTree::new(validators.iter().map(|v| v.address()))
+            .expect("validator set is non-empty");
+        Builder {
+            spec: Default::default(),
+            domain,
+            code: Code::Ok,
+            oracle: key(0x01),
+            notary: key(0x02),
+            router: key(0x03),
+            accuser: key(0xac),
+            validators,
+            tree,
+            accusations: Vec::new(),
+        }
+    }
+
+    /// configure w/ the code raised by verifiers.
+    fn code(mut self, code: Code) -> Self { self.code = code; self }
+
+    /// build a vector w/ an oracle circuit & `circuits` verify circuits.
+    fn build(mut self, name: &str, payload: Payload, circuits: usize) -> Vector {
+        let mut message = Message::new(self.declare(payload));
+        let steps = self.spec.oracle().len() + circuits * self.spec.verify().len();
+        self.extend(&mut message,steps);
+        self.finish(name,message)
+    }
+
+    /// build a vector w/ a single verify circuit following a checkpoint.
+    fn checkpointed(mut self, name: &str, payload: Payload) -> Vector {
+        let mut message = Message::new(self.declare(payload));
+        let steps = self.spec.oracle().len() + self.spec.verify().len();
+        self.extend(&mut message,steps);
+        let checkpoint = self.notary.seal_checkpoint(&self.spec,&self.domain,&message)
+            .expect("message ends on a boundary");
+        message.collapse(&self.spec,checkpoint).expect("checkpoint matches history");
+        let steps = self.spec.verify().len();
+        self.extend(&mut message,steps);
+        self.finish(name,message)
+    }
+
+    /// declare the signing version of the builder's domain in payload.
+    fn declare(&self, payload: Payload) -> Payload {
+        Payload { version: self.domain.version, ..payload }
+    }
+
+    /// append `steps` certs to message, accusing each accusable cert.
+    fn extend(&mut self, message: &mut Message, steps: usize) {
+        let (spec,domain) = (&self.spec,&self.domain);
+        let mut oracle = None;
+        let mut start = message.step_index();
+        for _ in 0..steps {
+            let index = message.step_index();
+            let cert = match message.next_step(spec) {
+                STEP::BLIND => {
+                    start = index;
+                    Cert::Blind(Keccak256::hash(&clear(start)).into())
+                },
+                STEP::CLEAR => Cert::Clear(clear(start).into()),
+                STEP::ORACLE => {
+                    let cert = self.oracle.seal_oracle(spec,domain,message);
+                    oracle = Some((message.clone(),cert));
+                    Cert::Oracle(cert)
+                },
+                STEP::NOTARY => {
+                    let cert = self.notary.seal_notary(spec,domain,message);
+                    if let Some((inner,accused)) = oracle.take() {
+                        let acc = accuse::oracle(&self.accuser,spec,domain,&inner,&accused,&cert);
+                        self.accusations.push(acc);
+                    }
+                    Cert::Notary(cert)
+                },
+                STEP::ROUTE => {
+                    let cert = self.router.seal_route(spec,domain,message,&self.tree)
+                        .expect("route follows a completed circuit");
+                    self.accusations.push(accuse::route(&self.accuser,spec,domain,message,&cert));
+                    Cert::Route(cert)
+                },
+                STEP::VERIFY => {
+                    let dest = next_dest(spec,message);
+                    let signer = self.validators.iter().find(|v| v.address() == dest)
+                        .expect("dests are validators");
+                    let cert = signer.seal_verify(spec,domain,message,self.code);
+                    self.accusations.push(accuse::verify(&self.accuser,spec,domain,message,&cert));
+                    Cert::Verify(cert)
+                },
+            };
+            message.append_cert(spec,cert).expect("cert matches next step");
+        }
+    }
+
+    /// collect the vector of a completed message.
+    fn finish(self, name: &str, message: Message) -> Vector {
+        let Builder { spec, domain, accusations, .. } = self;
+        let mut bytes = ByteVisitor::default();
+        visit::apply(&spec,&mut bytes,&message);
+        let bytes = bytes.finish();
+        let hash = H256(Keccak256::hash(&bytes));
+        let mut signing = Signing::new(domain);
+        visit::apply(&spec,&mut signing,&message);
+        let encoded = message.to_bytes().into();
+        let certs = signing.certs;
+        let name = name.to_owned();
+        Vector { name, domain, message, encoded, bytes: bytes.into(), hash, certs, accusations }
+    }
+}
+
+
+/// visitor which collects the signing hash & signer of each cert.
+#[derive(Debug)]
+struct Signing {
+    domain: Domain,
+    hasher: CertHasher,
+    verifier: Verifier,
+    certs: Vec<Signed>,
+}
+
+
+impl Signing {
+
+    fn new(domain: Domain) -> Self {
+        let (hasher,verifier,certs) = Default::default();
+        Signing { domain, hasher, verifier, certs }
+    }
+
+    fn push(&mut self, tag: Tag, hash: Option<[u8;32]>, sig: &Signature) {
+        let hash = self.domain.hash(tag,&hash.expect("signed certs produce hashes"));
+        let signer = self.verifier.ecrecover(&hash,sig).expect("vectors are well signed");
+        self.certs.push(Signed { tag, hash: H256(hash), signer });
+    }
+}
+
+
+impl<'v> MessageVisitor<'v> for Signing {
+
+    type Out = ();
+
+    fn visit_payload(&mut self, payload: &Payload) { let _ = self.hasher.visit_payload(payload); }
+
+    fn visit_oracle(&mut self, cert: &Oracle) {
+        let hash = self.hasher.visit_oracle(cert);
+        self.push(Tag::Oracle,hash,&cert.sig);
+    }
+
+    fn visit_notary(&mut self, cert: &Notary) {
+        let hash = self.hasher.visit_notary(cert);
+        self.push(Tag::Notary,hash,&cert.sig);
+    }
+
+    fn visit_blind(&mut self, cert: &Blind) { let _ = self.hasher.visit_blind(cert); }
+
+    fn visit_clear(&mut self, cert: &Clear) { let _ = self.hasher.visit_clear(cert); }
+
+    fn visit_route(&mut self, cert: &Route) {
+        let hash = self.hasher.visit_route(cert);
+        self.push(Tag::Route,hash,&cert.sig);
+    }
+
+    fn visit_verify(&mut self, cert: &Verify) {
+        let hash = self.hasher.visit_verify(cert);
+        self.push(Tag::Verify,hash,&cert.sig);
+    }
+
+    fn visit_checkpoint(&mut self, checkpoint: &Checkpoint) {
+        let hash = self.hasher.visit_checkpoint(checkpoint);
+        self.push(Tag::Checkpoint,hash,&checkpoint.sig);
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use testkit::vectors::{self,Corpus};
+    use judge::JudgeVisitor;
+    use message::{Message,CircuitSpec};
+    use visit::{self,Decode};
+    use serde_json;
+
+    const CORPUS: &str = include_str!("../../vectors/corpus.json");
+
+    #[test]
+    fn corpus_unchanged() {
+        let expect: Corpus = serde_json::from_str(CORPUS).unwrap();
+        let corpus = vectors::corpus();
+        assert_eq!(corpus.accuser,expect.accuser);
+        assert_eq!(corpus.vectors.len(),expect.vectors.len());
+        for (vector,expect) in corpus.vectors.iter().zip(expect.vectors.iter()) {
+            assert_eq!(vector,expect,"vector `{}` does not match corpus",expect.name);
+        }
+    }
+
+    #[test]
+    fn corpus_consistent() {
+        let corpus = vectors::corpus();
+        let spec = CircuitSpec::default();
+        // oracle, router & validators may be accused.
+        let accusable: Vec<_> = [0x01,0x03,0x10,0x11,0x12,0x13].iter()
+            .map(|seed| vectors::key(*seed).address())
+            .collect();
+        for vector in corpus.vectors.iter() {
+            assert_eq!(Message::from_bytes(&vector.encoded).unwrap(),vector.message);
+            let mut judge = JudgeVisitor::with_spec(visit::EmptyVisitor,&spec,vector.domain);
+            visit::apply(&spec,&mut judge,&vector.message);
+            let verdict = judge.finish();
+            assert!(verdict.is_ok(),"vector `{}` not ok",vector.name);
+            assert_eq!(verdict.bytes,vector.bytes.as_ref() as &[u8]);
+            let signers: Vec<_> = verdict.steps.iter().filter_map(|step| step.signer).collect();
+            let certs: Vec<_> = vector.certs.iter().map(|cert| cert.signer).collect();
+            assert_eq!(signers,certs);
+            assert!(!vector.accusations.is_empty());
+            for accusation in vector.accusations.iter() {
+                let verified = accusation.verify(&vector.domain).unwrap();
+                assert_eq!(verified.accuser,corpus.accuser);
+                assert!(accusable.contains(&verified.accused));
+            }
+        }
+    }
+}
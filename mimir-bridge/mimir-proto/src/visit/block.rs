@@ -1,8 +1,10 @@
 //! basic block visitors
 //!
 use mimir_types::{U256,H256,Address};
-use std::collections::HashSet;
+use std::collections::{HashMap,HashSet};
 use visit::BlockVisitor;
+use message::Role;
+use route::RouteTree;
 
 
 /// context object with knowledge about a specific block.
@@ -15,6 +17,9 @@ use visit::BlockVisitor;
 /// serve as a visitor which rejects information that it knows to be bad, allowing all
 /// else to pass.
 ///
+/// Block numbers are accepted if they fall within `window` blocks preceeding the
+/// configured `number` (a window of zero only accepts the configured block).
+///
 /// ```
 /// # 
 /// extern crate mimir_proto;
@@ -34,20 +39,21 @@ use visit::BlockVisitor;
 ///
 #[derive(Debug,Clone,Default,PartialEq,Eq)]
 pub struct BlockState {
-    /// allowable validator set for this block.
-    pub validators: Option<HashSet<Address>>,
+    /// known entities of this block, by role.  roles with no
+    /// entry are unrestricted.
+    pub entities: HashMap<Role,HashMap<Address,Entity>>,
 
-    /// allowable notary set for this block.
-    pub notaries: Option<HashSet<Address>>,
+    /// root of the validator routing tree.
+    pub root: Option<H256>,
 
-    /// alowable router set for this block.
-    pub routers: Option<HashSet<Address>>,
-    
     /// block number
     pub number: Option<U256>,
 
     /// block hash
     pub hash: Option<H256>,
+
+    /// number of preceeding blocks which are also acceptable
+    pub window: u64,
 }
 
 
@@ -56,20 +62,61 @@ impl BlockState {
     /// get new (blank) instance
     pub fn new() -> Self { Default::default() }
 
-    /// configure with a validator set
-    pub fn validators(mut self, set: HashSet<Address>) -> Self { self.validators = Some(set); self }
+    /// configure with a validator set (also configures
+    /// the routing tree root of the set).
+    ///
+    /// validators act as both oracles & verifiers, and
+    /// are registered as active under both roles.
+    pub fn validators(mut self, set: HashSet<Address>) -> Self {
+        self.root = RouteTree::new(set.iter().cloned())
+            .map(|tree| H256(*tree.root()));
+        self.role_set(Role::Oracle,&set).role_set(Role::Verifier,&set)
+    }
+
+    /// configure with a validator routing tree root
+    pub fn root(mut self, val: H256) -> Self { self.root = Some(val); self }
 
     /// configure with a notary set
-    pub fn notaries(mut self, set: HashSet<Address>) -> Self { self.notaries = Some(set); self }
+    pub fn notaries(self, set: HashSet<Address>) -> Self { self.role_set(Role::Notary,&set) }
 
     /// configure with a router set
-    pub fn routers(mut self, set: HashSet<Address>) -> Self { self.routers = Some(set); self }
+    pub fn routers(self, set: HashSet<Address>) -> Self { self.role_set(Role::Router,&set) }
+
+    /// configure with an entity (restricts the entity's role to
+    /// the explicitly configured entities).
+    pub fn entity(mut self, entity: Entity) -> Self {
+        self.entities.entry(entity.role).or_default()
+            .insert(entity.address,entity);
+        self
+    }
 
     /// configure with block number
     pub fn number(mut self, val: U256) -> Self { self.number = Some(val); self }
 
     /// configure with block hash
     pub fn hash(mut self, val: H256) -> Self { self.hash = Some(val); self }
+
+    /// configure with a window of acceptable recent blocks
+    pub fn window(mut self, val: u64) -> Self { self.window = val; self }
+
+    /// check if an address is an active entity of the
+    /// specified role (`true` if the role is unrestricted).
+    pub fn is_active(&self, role: Role, ident: &Address) -> bool {
+        match self.entities.get(&role) {
+            Some(known) => known.get(ident).map(|entity| entity.active).unwrap_or(false),
+            None => true,
+        }
+    }
+
+    /// register a set of active, unstaked entities under `role`
+    /// (replacing any entities previously registered under it).
+    fn role_set(mut self, role: Role, set: &HashSet<Address>) -> Self {
+        let known = set.iter()
+            .map(|address| (*address,Entity::new(*address,role)))
+            .collect();
+        self.entities.insert(role,known);
+        self
+    }
 }
 
 
@@ -78,34 +125,39 @@ impl BlockVisitor for BlockState {
     /// output generated upon visitation
     type Out = bool;
 
-    /// visit a validator address
+    /// visit a validator address (accepted if active
+    /// as either an oracle or a verifier).
     fn visit_validator(&self, ident: &Address) -> Self::Out {
-        match self.validators {
-            Some(ref set) => set.contains(ident),
-            None => true,
+        let roles = [Role::Oracle,Role::Verifier];
+        if roles.iter().all(|role| !self.entities.contains_key(role)) {
+            return true;
         }
+        roles.iter().any(|role| {
+            self.get_entity(*role,ident).map(|entity| entity.active).unwrap_or(false)
+        })
     }
 
     /// visit a router address
     fn visit_router(&self, ident: &Address) -> Self::Out {
-        match self.routers {
-            Some(ref set) => set.contains(ident),
-            None => true,
-        }
+        self.is_active(Role::Router,ident)
     }
 
     /// visit a notary address
     fn visit_notary(&self, ident: &Address) -> Self::Out {
-        match self.routers {
-            Some(ref set) => set.contains(ident),
-            None => true,
-        }
+        self.is_active(Role::Notary,ident)
+    }
+
+    /// visit the address of an entity in a specific role
+    fn visit_entity(&self, role: Role, ident: &Address) -> Self::Out {
+        self.is_active(role,ident)
     }
 
     /// visit a block number
     fn visit_number(&self, number: &U256) -> Self::Out {
         match self.number {
-            Some(ref val) => val == number,
+            Some(ref val) => {
+                number <= val && *number >= val.saturating_sub(&self.window.into())
+            },
             None => true,
         }
     }
@@ -118,6 +170,14 @@ impl BlockVisitor for BlockState {
         }
     }
 
+    /// visit a validator routing tree root
+    fn visit_root(&self, root: &H256) -> Self::Out {
+        match self.root {
+            Some(ref val) => val == root,
+            None => true,
+        }
+    }
+
     /// get reference to target block number
     fn get_number(&self) -> Option<&U256> {
         self.number.as_ref()
@@ -127,4 +187,142 @@ impl BlockVisitor for BlockState {
     fn get_hash(&self) -> Option<&H256> {
         self.hash.as_ref()
     }
+
+    /// get info about a known entity
+    fn get_entity(&self, role: Role, ident: &Address) -> Option<&Entity> {
+        self.entities.get(&role).and_then(|known| known.get(ident))
+    }
+
+    /// no-op (a block state describes a single block)
+    fn select(&mut self, _number: &U256) { }
+}
+
+
+/// entity known to a block (e.g. a staked validator).
+///
+#[derive(Debug,Copy,Clone,PartialEq,Eq,Serialize,Deserialize)]
+pub struct Entity {
+    /// address of the entity
+    pub address: Address,
+
+    /// role in which the entity acts
+    pub role: Role,
+
+    /// stake held by the entity
+    pub stake: U256,
+
+    /// whether or not the entity may currently act
+    pub active: bool,
+}
+
+
+impl Entity {
+
+    /// get new active & unstaked entity
+    pub fn new(address: Address, role: Role) -> Self {
+        Entity { address, role, stake: Default::default(), active: true }
+    }
+
+    /// configure with a stake
+    pub fn stake(mut self, val: U256) -> Self { self.stake = val; self }
+
+    /// configure as active or inactive
+    pub fn active(mut self, val: bool) -> Self { self.active = val; self }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use visit::{BlockVisitor,BlockState,Entity};
+    use message::Role;
+    use mimir_types::{Address,U256};
+    use std::collections::HashSet;
+
+    fn set(addrs: &[Address]) -> HashSet<Address> {
+        addrs.iter().cloned().collect()
+    }
+
+    #[test]
+    fn unrestricted_roles() {
+        let block = BlockState::new();
+        let addr = Address([1;20]);
+        assert!(block.visit_validator(&addr));
+        assert!(block.visit_notary(&addr));
+        assert!(block.visit_router(&addr));
+        assert!(block.get_entity(Role::Notary,&addr).is_none());
+    }
+
+    #[test]
+    fn notary_lookup() {
+        let (notary,router) = (Address([1;20]),Address([2;20]));
+        // notaries are checked against the notary set, not the router set.
+        let block = BlockState::new().notaries(set(&[notary])).routers(set(&[router]));
+        assert!(block.visit_notary(&notary));
+        assert!(!block.visit_notary(&router));
+        let block = BlockState::new().routers(set(&[router]));
+        assert!(block.visit_notary(&router) && block.visit_notary(&notary));
+        assert!(!BlockState::new().notaries(set(&[])).visit_notary(&notary));
+    }
+
+    #[test]
+    fn router_lookup() {
+        let (notary,router) = (Address([1;20]),Address([2;20]));
+        let block = BlockState::new().notaries(set(&[notary])).routers(set(&[router]));
+        assert!(block.visit_router(&router));
+        assert!(!block.visit_router(&notary));
+        let block = BlockState::new().notaries(set(&[notary]));
+        assert!(block.visit_router(&router));
+    }
+
+    #[test]
+    fn validator_lookup() {
+        let (oracle,verifier,other) = (Address([1;20]),Address([2;20]),Address([3;20]));
+        let block = BlockState::new().validators(set(&[oracle,verifier]));
+        assert!(block.visit_validator(&oracle) && block.visit_validator(&verifier));
+        assert!(!block.visit_validator(&other));
+        assert!(block.root.is_some());
+        // validators may be restricted to a single role.
+        let block = BlockState::new()
+            .entity(Entity::new(oracle,Role::Oracle))
+            .entity(Entity::new(verifier,Role::Verifier));
+        assert!(block.visit_validator(&oracle) && block.visit_validator(&verifier));
+        assert!(!block.visit_validator(&other));
+        assert!(block.is_active(Role::Oracle,&oracle) && !block.is_active(Role::Oracle,&verifier));
+        assert!(block.is_active(Role::Verifier,&verifier) && !block.is_active(Role::Verifier,&oracle));
+        let block = BlockState::new().entity(Entity::new(oracle,Role::Oracle));
+        assert!(block.visit_validator(&oracle) && !block.visit_validator(&verifier));
+    }
+
+    #[test]
+    fn role_replacement() {
+        let (old,new) = (Address([1;20]),Address([2;20]));
+        // reconfiguring a role replaces its entities, rather than extending them.
+        let block = BlockState::new().validators(set(&[old])).validators(set(&[new]));
+        assert!(!block.visit_validator(&old) && block.visit_validator(&new));
+        let block = BlockState::new().notaries(set(&[old])).notaries(set(&[new]));
+        assert!(!block.visit_notary(&old) && block.visit_notary(&new));
+        // entities are checked against their own role only.
+        let block = BlockState::new()
+            .entity(Entity::new(old,Role::Oracle))
+            .entity(Entity::new(new,Role::Verifier));
+        assert!(block.visit_entity(Role::Oracle,&old) && !block.visit_entity(Role::Verifier,&old));
+        assert!(block.visit_entity(Role::Verifier,&new) && !block.visit_entity(Role::Oracle,&new));
+        assert!(block.visit_validator(&old) && block.visit_validator(&new));
+        assert!(block.visit_entity(Role::Notary,&old));
+    }
+
+    #[test]
+    fn entity_info() {
+        let (addr,stake) = (Address([1;20]),U256::from(1000u64));
+        let block = BlockState::new()
+            .entity(Entity::new(addr,Role::Notary).stake(stake))
+            .entity(Entity::new(addr,Role::Router).active(false));
+        let notary = block.get_entity(Role::Notary,&addr).unwrap();
+        assert_eq!((notary.role,notary.stake,notary.active),(Role::Notary,stake,true));
+        assert!(block.get_entity(Role::Oracle,&addr).is_none());
+        // inactive entities are known, but not accepted.
+        assert!(block.get_entity(Role::Router,&addr).is_some());
+        assert!(block.visit_notary(&addr) && !block.visit_router(&addr));
+        assert!(block.visit_validator(&addr));
+    }
 }
@@ -1,4 +1,6 @@
 use mimir_types::{U256,H256,Address};
+use visit::Entity;
+use message::Role;
 use std::sync::Arc;
 use std::rc::Rc;
 
@@ -20,20 +22,45 @@ pub trait BlockVisitor {
     /// visit a notary address
     fn visit_notary(&self, ident: &Address) -> Self::Out;
 
+    /// visit the address of an entity acting in the specified role.
+    /// defaults to the general method of the role (`visit_validator`
+    /// for oracles, verifiers, & roles without a dedicated method).
+    fn visit_entity(&self, role: Role, ident: &Address) -> Self::Out {
+        match role {
+            Role::Notary => self.visit_notary(ident),
+            Role::Router => self.visit_router(ident),
+            _ => self.visit_validator(ident),
+        }
+    }
+
     /// visit a block number
     fn visit_number(&self, number: &U256) -> Self::Out;
 
     /// visit a block hash
     fn visit_hash(&self, hash: &H256) -> Self::Out;
 
+    /// visit a validator routing tree root
+    fn visit_root(&self, root: &H256) -> Self::Out;
+
     /// get reference to target block number if exists
     fn get_number(&self) -> Option<&U256>;
 
     /// get reference to target block hash if exists
     fn get_hash(&self) -> Option<&H256>;
+
+    /// select the block against which subsequent visitations are
+    /// checked (a no-op for visitors with knowledge of a single block).
+    /// deliberately not defaulted, s.t. wrappers cannot drop it.
+    fn select(&mut self, number: &U256);
+
+    /// get info about an entity acting in the specified role, if
+    /// known (e.g. for weighting by stake).
+    fn get_entity(&self, _role: Role, _ident: &Address) -> Option<&Entity> { None }
 }
 
 
+/// shared references cannot select; checks are made against whichever
+/// block `T` already has selected (forward selection via `&mut T`).
 impl<'a,T> BlockVisitor for &'a T where T: BlockVisitor + ?Sized {
 
     type Out = <T as BlockVisitor>::Out;
@@ -44,13 +71,49 @@ impl<'a,T> BlockVisitor for &'a T where T: BlockVisitor + ?Sized {
 
     fn visit_notary(&self, ident: &Address) -> Self::Out { <T as BlockVisitor>::visit_notary(self,ident) }
 
+    fn visit_entity(&self, role: Role, ident: &Address) -> Self::Out { <T as BlockVisitor>::visit_entity(self,role,ident) }
+
     fn visit_number(&self, number: &U256) -> Self::Out { <T as BlockVisitor>::visit_number(self,number) }
 
     fn visit_hash(&self, hash: &H256) -> Self::Out { <T as BlockVisitor>::visit_hash(self,hash) }
 
+    fn visit_root(&self, root: &H256) -> Self::Out { <T as BlockVisitor>::visit_root(self,root) }
+
     fn get_number(&self) -> Option<&U256> { <T as BlockVisitor>::get_number(self) }
 
     fn get_hash(&self) -> Option<&H256> { <T as BlockVisitor>::get_hash(self) }
+
+    fn get_entity(&self, role: Role, ident: &Address) -> Option<&Entity> { <T as BlockVisitor>::get_entity(self,role,ident) }
+
+    fn select(&mut self, _number: &U256) { }
+}
+
+
+impl<'a,T> BlockVisitor for &'a mut T where T: BlockVisitor + ?Sized {
+
+    type Out = <T as BlockVisitor>::Out;
+
+    fn visit_validator(&self, ident: &Address) -> Self::Out { <T as BlockVisitor>::visit_validator(self,ident) }
+
+    fn visit_router(&self, ident: &Address) -> Self::Out { <T as BlockVisitor>::visit_router(self,ident) }
+
+    fn visit_notary(&self, ident: &Address) -> Self::Out { <T as BlockVisitor>::visit_notary(self,ident) }
+
+    fn visit_entity(&self, role: Role, ident: &Address) -> Self::Out { <T as BlockVisitor>::visit_entity(self,role,ident) }
+
+    fn visit_number(&self, number: &U256) -> Self::Out { <T as BlockVisitor>::visit_number(self,number) }
+
+    fn visit_hash(&self, hash: &H256) -> Self::Out { <T as BlockVisitor>::visit_hash(self,hash) }
+
+    fn visit_root(&self, root: &H256) -> Self::Out { <T as BlockVisitor>::visit_root(self,root) }
+
+    fn get_number(&self) -> Option<&U256> { <T as BlockVisitor>::get_number(self) }
+
+    fn get_hash(&self) -> Option<&H256> { <T as BlockVisitor>::get_hash(self) }
+
+    fn get_entity(&self, role: Role, ident: &Address) -> Option<&Entity> { <T as BlockVisitor>::get_entity(self,role,ident) }
+
+    fn select(&mut self, number: &U256) { <T as BlockVisitor>::select(self,number) }
 }
 
 
@@ -64,17 +127,26 @@ impl<T> BlockVisitor for Box<T> where T: BlockVisitor + ?Sized {
 
     fn visit_notary(&self, ident: &Address) -> Self::Out { <T as BlockVisitor>::visit_notary(self,ident) }
 
+    fn visit_entity(&self, role: Role, ident: &Address) -> Self::Out { <T as BlockVisitor>::visit_entity(self,role,ident) }
+
     fn visit_number(&self, number: &U256) -> Self::Out { <T as BlockVisitor>::visit_number(self,number) }
 
     fn visit_hash(&self, hash: &H256) -> Self::Out { <T as BlockVisitor>::visit_hash(self,hash) }
 
+    fn visit_root(&self, root: &H256) -> Self::Out { <T as BlockVisitor>::visit_root(self,root) }
+
     fn get_number(&self) -> Option<&U256> { <T as BlockVisitor>::get_number(self) }
 
     fn get_hash(&self) -> Option<&H256> { <T as BlockVisitor>::get_hash(self) }
+
+    fn get_entity(&self, role: Role, ident: &Address) -> Option<&Entity> { <T as BlockVisitor>::get_entity(self,role,ident) }
+
+    fn select(&mut self, number: &U256) { <T as BlockVisitor>::select(self,number) }
 }
 
 
-impl<T> BlockVisitor for Arc<T> where T: BlockVisitor + ?Sized {
+/// selection is copy-on-write; other holders of a shared `Arc` are unaffected.
+impl<T> BlockVisitor for Arc<T> where T: BlockVisitor + Clone {
 
     type Out = <T as BlockVisitor>::Out;
 
@@ -84,17 +156,26 @@ impl<T> BlockVisitor for Arc<T> where T: BlockVisitor + ?Sized {
 
     fn visit_notary(&self, ident: &Address) -> Self::Out { <T as BlockVisitor>::visit_notary(self,ident) }
 
+    fn visit_entity(&self, role: Role, ident: &Address) -> Self::Out { <T as BlockVisitor>::visit_entity(self,role,ident) }
+
     fn visit_number(&self, number: &U256) -> Self::Out { <T as BlockVisitor>::visit_number(self,number) }
 
     fn visit_hash(&self, hash: &H256) -> Self::Out { <T as BlockVisitor>::visit_hash(self,hash) }
 
+    fn visit_root(&self, root: &H256) -> Self::Out { <T as BlockVisitor>::visit_root(self,root) }
+
     fn get_number(&self) -> Option<&U256> { <T as BlockVisitor>::get_number(self) }
 
     fn get_hash(&self) -> Option<&H256> { <T as BlockVisitor>::get_hash(self) }
+
+    fn get_entity(&self, role: Role, ident: &Address) -> Option<&Entity> { <T as BlockVisitor>::get_entity(self,role,ident) }
+
+    fn select(&mut self, number: &U256) { <T as BlockVisitor>::select(Arc::make_mut(self),number) }
 }
 
 
-impl<T> BlockVisitor for Rc<T> where T: BlockVisitor + ?Sized {
+/// selection is copy-on-write; other holders of a shared `Rc` are unaffected.
+impl<T> BlockVisitor for Rc<T> where T: BlockVisitor + Clone {
 
     type Out = <T as BlockVisitor>::Out;
 
@@ -104,12 +185,20 @@ impl<T> BlockVisitor for Rc<T> where T: BlockVisitor + ?Sized {
 
     fn visit_notary(&self, ident: &Address) -> Self::Out { <T as BlockVisitor>::visit_notary(self,ident) }
 
+    fn visit_entity(&self, role: Role, ident: &Address) -> Self::Out { <T as BlockVisitor>::visit_entity(self,role,ident) }
+
     fn visit_number(&self, number: &U256) -> Self::Out { <T as BlockVisitor>::visit_number(self,number) }
 
     fn visit_hash(&self, hash: &H256) -> Self::Out { <T as BlockVisitor>::visit_hash(self,hash) }
 
+    fn visit_root(&self, root: &H256) -> Self::Out { <T as BlockVisitor>::visit_root(self,root) }
+
     fn get_number(&self) -> Option<&U256> { <T as BlockVisitor>::get_number(self) }
 
     fn get_hash(&self) -> Option<&H256> { <T as BlockVisitor>::get_hash(self) }
+
+    fn get_entity(&self, role: Role, ident: &Address) -> Option<&Entity> { <T as BlockVisitor>::get_entity(self,role,ident) }
+
+    fn select(&mut self, number: &U256) { <T as BlockVisitor>::select(Rc::make_mut(self),number) }
 }
 
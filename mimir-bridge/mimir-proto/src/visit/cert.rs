@@ -1,13 +1,15 @@
 //! message visitors for cert verification
 //!
 use message::cert::{Oracle,Notary,Blind,Clear,Route,Verify};
-use message::Payload;
+use message::{Payload,Checkpoint,Code};
 use visit::MessageVisitor;
-use visit::message::ByteVisitor;
+use visit::message::{ByteVisitor,SeedVisitor,absorb_route,absorb_checkpoint};
+use route::{self,ProofError};
 use mimir_crypto::secp256k1::Error as SigError;
 use mimir_crypto::secp256k1::Verifier;
 use mimir_crypto::Keccak256;
-use mimir_types::Address;
+use mimir_types::{Signature,Address};
+use seal::{Domain,Version,Tag};
 use std::{fmt,error};
 
 
@@ -18,21 +20,44 @@ use std::{fmt,error};
 /// if cert fails one or more checks, an error describing
 /// the issue is returned.
 ///
-#[derive(Default,Debug)]
+/// signatures are verified under the version declared by the message
+/// payload, which must be accepted by the supplied domain (see
+/// `Domain::accept`).  only canonical signatures are accepted (see
+/// `Verifier::strict`), s.t. each cert has exactly one valid encoding.
+///
+#[derive(Debug)]
 pub struct CertVisitor {
+    domain: Domain,
+    signing: Result<Domain,Version>,
     verifier: Verifier,
     hasher: CertHasher,
+    seed: SeedVisitor,
     blind: Option<Blind>
 }
 
 
 impl CertVisitor {
 
+    /// instantiate new visitor which verifies
+    /// signatures under the supplied domain.
+    pub fn new(domain: Domain) -> Self {
+        let (hasher,seed,blind) = Default::default();
+        let (signing,verifier) = (Ok(domain),Verifier::strict());
+        CertVisitor { domain, signing, verifier, hasher, seed, blind }
+    }
+
     /// consume visitor, returning vector of
     /// visited bytes
     pub fn finish(self) -> Vec<u8> {
         self.hasher.inner.finish()
     }
+
+    /// recover the signer of a hash under the declared signing domain.
+    fn recover(&self, tag: Tag, hash: &[u8;32], sig: &Signature) -> Result<Address,CertError> {
+        let domain = self.signing.map_err(|version| CertError::Version { version })?;
+        let addr = self.verifier.ecrecover(&domain.hash(tag,hash),sig)?;
+        Ok(addr)
+    }
 }
 
 
@@ -44,18 +69,23 @@ impl<'v> MessageVisitor<'v> for CertVisitor {
     
     fn visit_payload(&mut self, payload: &Payload) -> Self::Out {
         let _ = self.hasher.visit_payload(payload);
-        Ok(None)
+        let version = payload.version;
+        self.signing = self.domain.at(version).ok_or(version);
+        match self.signing {
+            Ok(_) => Ok(None),
+            Err(version) => Err(CertError::Version { version }),
+        }
     }
 
 
     fn visit_oracle(&mut self, cert: &Oracle) -> Self::Out {
         let hash = self.hasher.visit_oracle(cert)
             .expect("always returns a value");
-        let addr = self.verifier.ecrecover(&hash,&cert.sig)?;
-        if cert.val == 0 {
+        let addr = self.recover(Tag::Oracle,&hash,&cert.sig)?;
+        if cert.val.is_ok() {
             Ok(Some(addr))
         } else {
-            Err(CertError::Flag { addr })
+            Err(CertError::Flag { addr, code: cert.val })
         }
     }
 
@@ -63,7 +93,8 @@ impl<'v> MessageVisitor<'v> for CertVisitor {
     fn visit_notary(&mut self, cert: &Notary) -> Self::Out {
         let hash = self.hasher.visit_notary(cert)
             .expect("always returns a value");
-        let addr = self.verifier.ecrecover(&hash,&cert.sig)?;
+        let addr = self.recover(Tag::Notary,&hash,&cert.sig)?;
+        self.seed.visit_notary(cert);
         Ok(Some(addr))
     }
  
@@ -80,6 +111,7 @@ impl<'v> MessageVisitor<'v> for CertVisitor {
         if let Some(blind) = self.blind.take() {
             let hash = Keccak256::hash(cert);
             if blind.as_ref() == hash.as_ref() {
+                self.seed.visit_clear(cert);
                 Ok(None)
             } else {
                 Err(CertError::InvalidBlind)
@@ -93,7 +125,23 @@ impl<'v> MessageVisitor<'v> for CertVisitor {
     fn visit_route(&mut self, cert: &Route) -> Self::Out {
         let hash = self.hasher.visit_route(cert)
             .expect("always returns a value");
-        let addr = self.verifier.ecrecover(&hash,&cert.sig)?;
+        let addr = self.recover(Tag::Route,&hash,&cert.sig)?;
+        let (clear,notary) = self.seed.get_seed()
+            .ok_or(CertError::MissingSeed)?;
+        if cert.val.len() != cert.path.len() {
+            let error = ProofError::Path;
+            return Err(CertError::Route { addr, error });
+        }
+        for (dest,(address,path)) in cert.val.iter().zip(cert.path.iter()).enumerate() {
+            let key = route::traversal_key(clear,notary,dest);
+            let leaf = Keccak256::hash(address.as_ref());
+            let root = route::recover_root(&key,&leaf,path)
+                .map_err(|error| CertError::Route { addr, error })?;
+            if root != cert.root.0 {
+                let error = ProofError::Root;
+                return Err(CertError::Route { addr, error });
+            }
+        }
         Ok(Some(addr))
     }
  
@@ -101,7 +149,17 @@ impl<'v> MessageVisitor<'v> for CertVisitor {
     fn visit_verify(&mut self, cert: &Verify) -> Self::Out {
         let hash = self.hasher.visit_verify(cert)
             .expect("always returns a value");
-        let addr = self.verifier.ecrecover(&hash,&cert.sig)?;
+        let addr = self.recover(Tag::Verify,&hash,&cert.sig)?;
+        Ok(Some(addr))
+    }
+
+
+    fn visit_checkpoint(&mut self, checkpoint: &Checkpoint) -> Self::Out {
+        let hash = self.hasher.visit_checkpoint(checkpoint)
+            .expect("always returns a value");
+        let addr = self.recover(Tag::Checkpoint,&hash,&checkpoint.sig)?;
+        self.seed.visit_checkpoint(checkpoint);
+        self.blind = None;
         Ok(Some(addr))
     }
 }
@@ -113,7 +171,7 @@ impl<'v> MessageVisitor<'v> for CertVisitor {
 /// returns the hash necessary for signature recovery on certs
 /// which contain signatures, and `None` for all others.
 #[derive(Default,Debug)]
-struct CertHasher {
+pub(crate) struct CertHasher {
     inner: ByteVisitor
 }
 
@@ -157,9 +215,7 @@ impl<'v> MessageVisitor<'v> for CertHasher {
     fn visit_route(&mut self, cert: &Route) -> Self::Out {
         let mut hasher = Keccak256::default();
         hasher.absorb(self.inner.as_bytes());
-        for address in cert.val.iter() {
-            hasher.absorb(address.as_ref()); 
-        }
+        absorb_route(&mut hasher,cert);
         let hash = hasher.finish();
         self.inner.visit_route(cert);
         Some(hash)
@@ -169,11 +225,21 @@ impl<'v> MessageVisitor<'v> for CertHasher {
     fn visit_verify(&mut self, cert: &Verify) -> Self::Out {
         let mut hasher = Keccak256::default();
         hasher.absorb(self.inner.as_bytes());
-        hasher.absorb(&[cert.val]);
+        hasher.absorb(&[cert.val.as_u8()]);
         let hash = hasher.finish();
         self.inner.visit_oracle(cert);
         Some(hash)
     }
+
+
+    fn visit_checkpoint(&mut self, checkpoint: &Checkpoint) -> Self::Out {
+        let mut hasher = Keccak256::default();
+        hasher.absorb(self.inner.as_bytes());
+        absorb_checkpoint(&mut hasher,checkpoint);
+        let hash = hasher.finish();
+        self.inner.visit_checkpoint(checkpoint);
+        Some(hash)
+    }
 }
 
 
@@ -188,6 +254,10 @@ pub enum CertError {
     /// got a `clear` cert with no
     /// preceeding `blind` cert.
     MissingBlind,
+
+    /// got a `route` cert with no
+    /// preceeding circuit to seed it.
+    MissingSeed,
     
     /// signature was invalid
     Sig {
@@ -195,10 +265,28 @@ pub enum CertError {
         error: SigError
     }, 
     
-    /// cert contained invalid flag 
+    /// cert raised a dispute (illegal for oracle certs)
     Flag {
         /// identity of cert generator
-        addr: Address
+        addr: Address,
+
+        /// dispute code raised by cert
+        code: Code
+    },
+
+    /// cert contained invalid routing proof
+    Route {
+        /// identity of cert generator
+        addr: Address,
+
+        /// error raised during proof recovery
+        error: ProofError
+    },
+
+    /// message declared a signing version which is not accepted
+    Version {
+        /// version declared by the message
+        version: Version
     }
 }
 
@@ -210,8 +298,11 @@ impl CertError {
         match *self {
             CertError::InvalidBlind => "invalid hash-blind",
             CertError::MissingBlind => "missing hash-blind",
+            CertError::MissingSeed => "missing route seed",
             CertError::Sig { .. } => "bad signature",
-            CertError::Flag { .. } => "illegal flag"
+            CertError::Flag { .. } => "cert raised dispute",
+            CertError::Route { .. } => "bad routing proof",
+            CertError::Version { .. } => "unaccepted signing version"
         }
     }
 }
@@ -230,8 +321,15 @@ impl fmt::Display for CertError {
         match *self {
             CertError::InvalidBlind => f.write_str(self.as_str()),
             CertError::MissingBlind => f.write_str(self.as_str()),
+            CertError::MissingSeed => f.write_str(self.as_str()),
             CertError::Sig { ref error } => error.fmt(f),
-            CertError::Flag { .. } => f.write_str(self.as_str())
+            CertError::Flag { ref code, .. } => {
+                write!(f,"cert raised dispute ({})",code)
+            },
+            CertError::Route { ref error, .. } => error.fmt(f),
+            CertError::Version { ref version } => {
+                write!(f,"unaccepted signing version ({})",version)
+            }
         }
     }
 }
@@ -244,3 +342,115 @@ impl error::Error for CertError {
 }
 
 
+#[cfg(test)]
+mod tests {
+    use message::cert::{Oracle,Notary,Blind,Clear,Route,Verify};
+    use message::{Message,Payload,Checkpoint};
+    use visit::{self,MessageVisitor,CertVisitor,CertError};
+    use judge::{accuse,AccusationError};
+    use seal::Sealer;
+    use testkit::{Fixture,Corruption};
+    use mimir_crypto::secp256k1::Error as SigError;
+    use mimir_types::Address;
+
+    /// collects the result of each visited cert.
+    struct Collect {
+        inner: CertVisitor,
+        results: Vec<Result<Option<Address>,CertError>>,
+    }
+
+    impl Collect {
+
+        fn new(fixture: &Fixture) -> Self {
+            Collect { inner: CertVisitor::new(fixture.domain), results: Vec::new() }
+        }
+    }
+
+    impl<'v> MessageVisitor<'v> for Collect {
+
+        type Out = ();
+
+        fn visit_payload(&mut self, payload: &Payload) { let _ = self.inner.visit_payload(payload); }
+
+        fn visit_oracle(&mut self, cert: &Oracle) { self.results.push(self.inner.visit_oracle(cert)); }
+
+        fn visit_notary(&mut self, cert: &Notary) { self.results.push(self.inner.visit_notary(cert)); }
+
+        fn visit_blind(&mut self, cert: &Blind) { self.results.push(self.inner.visit_blind(cert)); }
+
+        fn visit_clear(&mut self, cert: &Clear) { self.results.push(self.inner.visit_clear(cert)); }
+
+        fn visit_route(&mut self, cert: &Route) { self.results.push(self.inner.visit_route(cert)); }
+
+        fn visit_verify(&mut self, cert: &Verify) { self.results.push(self.inner.visit_verify(cert)); }
+
+        fn visit_checkpoint(&mut self, cert: &Checkpoint) { self.results.push(self.inner.visit_checkpoint(cert)); }
+    }
+
+    /// check that all certs pass, & are signed by fixture entities.
+    fn passes(fixture: &Fixture, results: &[Result<Option<Address>,CertError>]) -> bool {
+        let known: Vec<Address> = fixture.validators.iter()
+            .chain(fixture.notaries.iter())
+            .chain(fixture.routers.iter())
+            .map(|signer| signer.address())
+            .collect();
+        results.iter().all(|result| match *result {
+            Ok(Some(ref addr)) => known.contains(addr),
+            Ok(None) => true,
+            Err(_) => false,
+        })
+    }
+
+    #[test]
+    fn generated_certs() {
+        let fixture = Fixture::default();
+        for circuits in 0..4 {
+            let mut collect = Collect::new(&fixture);
+            let message = fixture.message(circuits);
+            visit::apply(&fixture.spec,&mut collect,&message);
+            assert_eq!(collect.results.len(),message.step_index());
+            assert!(passes(&fixture,&collect.results));
+        }
+    }
+
+    #[test]
+    fn corrupted_certs() {
+        let fixture = Fixture::default();
+        let corruptions = Corruption::all().iter()
+            .filter(|corruption| **corruption != Corruption::Truncate);
+        for corruption in corruptions {
+            for circuits in 0..3 {
+                let mut collect = Collect::new(&fixture);
+                let message = fixture.corrupted(circuits,*corruption);
+                visit::apply(&fixture.spec,&mut collect,&message);
+                assert!(!passes(&fixture,&collect.results),"{:?} passed",corruption);
+            }
+        }
+    }
+
+    #[test]
+    fn malleated_certs() {
+        let fixture = Fixture::default();
+        let mut message = fixture.message(1);
+        message.notary[0].sig.0[64] -= 27;
+        let mut collect = Collect::new(&fixture);
+        visit::apply(&fixture.spec,&mut collect,&message);
+        assert!(collect.results.contains(&Err(CertError::Sig { error: SigError::BadRecoveryByte })));
+    }
+
+    #[test]
+    fn malleated_accusations() {
+        let fixture = Fixture::default();
+        let (oracle,accuser) = (&fixture.validators[0],&fixture.validators[1]);
+        let inner = Message::new(fixture.payload.clone());
+        let cert = oracle.seal_oracle(&fixture.spec,&fixture.domain,&inner);
+        let accusation = accuse::verify(accuser,&fixture.spec,&fixture.domain,&inner,&cert);
+        assert!(accusation.verify(&fixture.domain).is_ok());
+        // neither the accused nor the accuser signature may be malleated.
+        for &index in [accusation.accused + 65,accusation.bytes.len() - 1].iter() {
+            let mut bad = accusation.clone();
+            bad.bytes.0[index] -= 27;
+            assert_eq!(bad.verify(&fixture.domain),Err(AccusationError::Sig));
+        }
+    }
+}
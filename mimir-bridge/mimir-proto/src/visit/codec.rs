@@ -0,0 +1,611 @@
+//! canonical binary encoding of messages & certs.
+//!
+//! unlike the byte stream produced by `ByteVisitor` (which exists
+//! only to be hashed), this encoding is fully reversible.  all values
+//! are encoded in struct field order; fixed-size values are written
+//! as-is, integers are big-endian, and variable-length values (strings
+//! & sequences) are prefixed with their length as a `u32`.  payload
+//! records are encoded as strings; batched records are encoded as a
+//! length-prefixed body beginning with `0xff` (which never occurs in
+//! utf-8), followed by `0x00` & the records, or `0x01` & the root.
+//! optional values are prefixed with `0x00` if absent, or `0x01` if
+//! present.  the signing version of a payload is a single byte.  since
+//! every value has exactly one valid encoding, decoding fails if the
+//! input is truncated, has trailing bytes, or contains illegal values.
+//!
+//! ```
+//! # extern crate mimir_proto;
+//! # fn main() {
+//! use mimir_proto::visit::{Encode,Decode};
+//! use mimir_proto::message::Message;
+//!
+//! let message = Message::default();
+//!
+//! let bytes = message.to_bytes();
+//!
+//! assert_eq!(Message::from_bytes(&bytes),Ok(message));
+//! # }
+//! ```
+//!
+use message::cert::{Notary,Route,Verify};
+use message::record::BATCH_FLAG;
+use message::{Message,Payload,Record,Batch,Checkpoint,Code};
+use seal::Version;
+use mimir_types::{Address,Signature,U256,H256};
+use route::{NodeHash,MAX_PROOF_LEN};
+use std::cmp;
+
+
+simple_error!(
+    DecodeError, "error indicating invalid or non-canonical encoding",
+    Truncated => "unexpected end of input",
+    Trailing => "unexpected trailing bytes",
+    Utf8 => "string is not valid utf-8",
+    Flag => "illegal flag value",
+    Empty => "empty record batch",
+    Proof => "routing proof exceeds maximum length",
+);
+
+
+/// type which may be canonically encoded
+pub trait Encode {
+
+    /// append encoding of self to buffer
+    fn encode(&self, buff: &mut Vec<u8>);
+
+    /// get encoding of self as new byte vector
+    fn to_bytes(&self) -> Vec<u8> {
+        let mut buff = Vec::new();
+        self.encode(&mut buff);
+        buff
+    }
+}
+
+
+/// type which may be decoded from its canonical encoding
+pub trait Decode: Sized {
+
+    /// decode next value from reader
+    fn decode(reader: &mut Reader) -> Result<Self,DecodeError>;
+
+    /// decode value which must make up the entirety of `bytes`
+    fn from_bytes(bytes: &[u8]) -> Result<Self,DecodeError> {
+        let mut reader = Reader::new(bytes);
+        let value = Self::decode(&mut reader)?;
+        reader.finish()?;
+        Ok(value)
+    }
+}
+
+
+/// cursor over a byte slice being decoded.
+#[derive(Debug,Clone)]
+pub struct Reader<'a> {
+    bytes: &'a [u8]
+}
+
+
+impl<'a> Reader<'a> {
+
+    /// wrap byte slice in new reader
+    pub fn new(bytes: &'a [u8]) -> Self { Reader { bytes } }
+
+    /// number of bytes not yet consumed
+    pub fn remaining(&self) -> usize { self.bytes.len() }
+
+    /// take the next `len` bytes
+    pub fn take(&mut self, len: usize) -> Result<&'a [u8],DecodeError> {
+        if len <= self.bytes.len() {
+            let (head,tail) = self.bytes.split_at(len);
+            self.bytes = tail;
+            Ok(head)
+        } else {
+            Err(DecodeError::Truncated)
+        }
+    }
+
+    /// take next byte
+    pub fn take_u8(&mut self) -> Result<u8,DecodeError> {
+        self.take(1).map(|bytes| bytes[0])
+    }
+
+    /// take next big-endian `u32`
+    pub fn take_u32(&mut self) -> Result<u32,DecodeError> {
+        let bytes = self.take(4)?;
+        let value = bytes.iter().fold(0u32,|acc,byte| (acc << 8) | *byte as u32);
+        Ok(value)
+    }
+
+    /// consume reader, failing if any bytes remain
+    pub fn finish(self) -> Result<(),DecodeError> {
+        if self.bytes.is_empty() {
+            Ok(())
+        } else {
+            Err(DecodeError::Trailing)
+        }
+    }
+}
+
+
+/// append a length prefix
+fn encode_len(len: usize, buff: &mut Vec<u8>) {
+    debug_assert!(len <= u32::max_value() as usize);
+    let len = len as u32;
+    buff.extend_from_slice(&[(len >> 24) as u8,(len >> 16) as u8,(len >> 8) as u8,len as u8]);
+}
+
+
+/// append a length-prefixed sequence
+fn encode_seq<T>(items: &[T], buff: &mut Vec<u8>) where T: Encode {
+    encode_len(items.len(),buff);
+    for item in items.iter() {
+        item.encode(buff);
+    }
+}
+
+
+/// decode a length-prefixed sequence
+fn decode_seq<T>(reader: &mut Reader) -> Result<Vec<T>,DecodeError> where T: Decode {
+    let len = reader.take_u32()? as usize;
+    // every element occupies at least one byte, so a length which
+    // exceeds the remaining input is known to be truncated.
+    if len > reader.remaining() {
+        return Err(DecodeError::Truncated);
+    }
+    let mut items = Vec::with_capacity(cmp::min(len,reader.remaining()));
+    for _ in 0..len {
+        items.push(T::decode(reader)?);
+    }
+    Ok(items)
+}
+
+
+macro_rules! fixed_codec {
+    ($($name:ident => $size:expr),+) => {
+        $(
+            impl Encode for $name {
+
+                fn encode(&self, buff: &mut Vec<u8>) { buff.extend_from_slice(&self.0) }
+            }
+
+            impl Decode for $name {
+
+                fn decode(reader: &mut Reader) -> Result<Self,DecodeError> {
+                    let bytes = reader.take($size)?;
+                    let mut inner = [0u8;$size];
+                    inner.copy_from_slice(bytes);
+                    Ok($name(inner))
+                }
+            }
+        )+
+    }
+}
+
+fixed_codec!(Address => 20, U256 => 32, H256 => 32, Signature => 65);
+
+
+impl Encode for String {
+
+    fn encode(&self, buff: &mut Vec<u8>) {
+        encode_len(self.len(),buff);
+        buff.extend_from_slice(self.as_bytes());
+    }
+}
+
+
+impl Decode for String {
+
+    fn decode(reader: &mut Reader) -> Result<Self,DecodeError> {
+        let len = reader.take_u32()? as usize;
+        let bytes = reader.take(len)?;
+        String::from_utf8(bytes.to_owned())
+            .map_err(|_| DecodeError::Utf8)
+    }
+}
+
+
+impl Encode for Record {
+
+    fn encode(&self, buff: &mut Vec<u8>) {
+        let body = match *self {
+            Record::Single(ref record) => return record.encode(buff),
+            Record::Batch(ref batch) => {
+                let mut body = vec![BATCH_FLAG,0x00];
+                encode_seq(batch.records(),&mut body);
+                body
+            },
+            Record::Root(ref root) => {
+                let mut body = vec![BATCH_FLAG,0x01];
+                root.encode(&mut body);
+                body
+            },
+        };
+        encode_len(body.len(),buff);
+        buff.extend_from_slice(&body);
+    }
+}
+
+
+impl Decode for Record {
+
+    fn decode(reader: &mut Reader) -> Result<Self,DecodeError> {
+        let len = reader.take_u32()? as usize;
+        let bytes = reader.take(len)?;
+        if bytes.first() != Some(&BATCH_FLAG) {
+            return String::from_utf8(bytes.to_owned())
+                .map(Record::Single)
+                .map_err(|_| DecodeError::Utf8);
+        }
+        let mut body = Reader::new(&bytes[1..]);
+        let record = match body.take_u8()? {
+            0x00 => {
+                let records = decode_seq(&mut body)?;
+                Batch::new(records).map(Record::Batch)
+                    .ok_or(DecodeError::Empty)?
+            },
+            0x01 => Record::Root(H256::decode(&mut body)?),
+            _ => return Err(DecodeError::Flag),
+        };
+        body.finish()?;
+        Ok(record)
+    }
+}
+
+
+impl Encode for NodeHash {
+
+    fn encode(&self, buff: &mut Vec<u8>) {
+        buff.push(self.side());
+        self.hash().encode(buff);
+    }
+}
+
+
+impl Decode for NodeHash {
+
+    fn decode(reader: &mut Reader) -> Result<Self,DecodeError> {
+        let side = reader.take_u8()?;
+        let hash = H256::decode(reader)?;
+        match side {
+            0x00 => Ok(NodeHash::Left(hash)),
+            0x01 => Ok(NodeHash::Right(hash)),
+            _ => Err(DecodeError::Flag),
+        }
+    }
+}
+
+
+impl Encode for Vec<NodeHash> {
+
+    fn encode(&self, buff: &mut Vec<u8>) { encode_seq(self,buff) }
+}
+
+
+impl Decode for Vec<NodeHash> {
+
+    fn decode(reader: &mut Reader) -> Result<Self,DecodeError> { decode_seq(reader) }
+}
+
+
+impl Encode for Payload {
+
+    fn encode(&self, buff: &mut Vec<u8>) {
+        let &Payload { ref record, ref address, ref number, ref hash, ref version } = self;
+        record.encode(buff);
+        address.encode(buff);
+        number.encode(buff);
+        hash.encode(buff);
+        buff.push(version.as_u8());
+    }
+}
+
+
+impl Decode for Payload {
+
+    fn decode(reader: &mut Reader) -> Result<Self,DecodeError> {
+        let record = Record::decode(reader)?;
+        let address = Address::decode(reader)?;
+        let number = U256::decode(reader)?;
+        let hash = H256::decode(reader)?;
+        let version = Version::from_u8(reader.take_u8()?)
+            .ok_or(DecodeError::Flag)?;
+        Ok(Payload { record, address, number, hash, version })
+    }
+}
+
+
+impl Encode for Notary {
+
+    fn encode(&self, buff: &mut Vec<u8>) { self.sig.encode(buff) }
+}
+
+
+impl Decode for Notary {
+
+    fn decode(reader: &mut Reader) -> Result<Self,DecodeError> {
+        let sig = Signature::decode(reader)?;
+        Ok(Notary { sig })
+    }
+}
+
+
+impl Encode for Verify {
+
+    fn encode(&self, buff: &mut Vec<u8>) {
+        self.sig.encode(buff);
+        buff.push(self.val.as_u8());
+    }
+}
+
+
+impl Decode for Verify {
+
+    fn decode(reader: &mut Reader) -> Result<Self,DecodeError> {
+        let sig = Signature::decode(reader)?;
+        let val = Code::from_u8(reader.take_u8()?)
+            .ok_or(DecodeError::Flag)?;
+        Ok(Verify { sig, val })
+    }
+}
+
+
+impl Encode for Route {
+
+    fn encode(&self, buff: &mut Vec<u8>) {
+        let &Route { ref sig, ref root, ref val, ref path } = self;
+        sig.encode(buff);
+        root.encode(buff);
+        encode_seq(val,buff);
+        encode_seq(path,buff);
+    }
+}
+
+
+impl Decode for Route {
+
+    fn decode(reader: &mut Reader) -> Result<Self,DecodeError> {
+        let sig = Signature::decode(reader)?;
+        let root = H256::decode(reader)?;
+        let val = decode_seq(reader)?;
+        let path: Vec<Vec<NodeHash>> = decode_seq(reader)?;
+        if path.iter().any(|nodes| nodes.len() > MAX_PROOF_LEN) {
+            return Err(DecodeError::Proof);
+        }
+        Ok(Route { sig, root, val, path })
+    }
+}
+
+
+impl Encode for Checkpoint {
+
+    fn encode(&self, buff: &mut Vec<u8>) {
+        let &Checkpoint { ref steps, ref root, ref clear, ref seal, ref sig } = self;
+        encode_len(*steps as usize,buff);
+        root.encode(buff);
+        clear.encode(buff);
+        seal.encode(buff);
+        sig.encode(buff);
+    }
+}
+
+
+impl Decode for Checkpoint {
+
+    fn decode(reader: &mut Reader) -> Result<Self,DecodeError> {
+        let steps = reader.take_u32()?;
+        let root = H256::decode(reader)?;
+        let clear = H256::decode(reader)?;
+        let seal = Notary::decode(reader)?;
+        let sig = Signature::decode(reader)?;
+        Ok(Checkpoint { steps, root, clear, seal, sig })
+    }
+}
+
+
+impl Encode for Option<Checkpoint> {
+
+    fn encode(&self, buff: &mut Vec<u8>) {
+        match *self {
+            Some(ref checkpoint) => { buff.push(0x01); checkpoint.encode(buff); },
+            None => buff.push(0x00),
+        }
+    }
+}
+
+
+impl Decode for Option<Checkpoint> {
+
+    fn decode(reader: &mut Reader) -> Result<Self,DecodeError> {
+        match reader.take_u8()? {
+            0x00 => Ok(None),
+            0x01 => Checkpoint::decode(reader).map(Some),
+            _ => Err(DecodeError::Flag),
+        }
+    }
+}
+
+
+impl Encode for Message {
+
+    fn encode(&self, buff: &mut Vec<u8>) {
+        let &Message { ref payload, ref verify, ref notary, ref route, ref blind, ref checkpoint } = self;
+        payload.encode(buff);
+        encode_seq(verify,buff);
+        encode_seq(notary,buff);
+        encode_seq(route,buff);
+        encode_seq(blind,buff);
+        checkpoint.encode(buff);
+    }
+}
+
+
+impl Decode for Message {
+
+    fn decode(reader: &mut Reader) -> Result<Self,DecodeError> {
+        let payload = Payload::decode(reader)?;
+        let verify = decode_seq(reader)?;
+        let notary = decode_seq(reader)?;
+        let route = decode_seq(reader)?;
+        let blind = decode_seq(reader)?;
+        let checkpoint = Decode::decode(reader)?;
+        Ok(Message { payload, verify, notary, route, blind, checkpoint })
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use visit::{Encode,Decode,DecodeError};
+    use message::cert::{Notary,Route,Verify};
+    use message::{Message,Payload,Record,Batch,Checkpoint,Code};
+    use mimir_types::{Address,Signature,Bytes,H256};
+    use route::NodeHash;
+    use seal::Version;
+
+    fn payload() -> Payload {
+        Payload {
+            record: "{}".into(),
+            address: Address([0x11;20]),
+            number: 0x2au64.into(),
+            hash: H256([0x22;32]),
+            version: Version::V1,
+        }
+    }
+
+    fn route() -> Route {
+        Route {
+            sig: Signature([0x33;65]),
+            root: H256([0x44;32]),
+            val: vec![Address([0x55;20])],
+            path: vec![vec![NodeHash::Left(H256([0x66;32])),NodeHash::Right(H256([0x77;32]))]],
+        }
+    }
+
+    fn message() -> Message {
+        let mut message = Message::new(payload());
+        message.blind.push(H256([0x88;32]));
+        message.verify.push(Verify { sig: Signature([0x99;65]), val: Code::Ok });
+        message.notary.push(Notary { sig: Signature([0xaa;65]) });
+        message.route.push(route());
+        message
+    }
+
+    fn hex(bytes: &[u8]) -> String { format!("{:?}",Bytes::from(bytes.to_vec())) }
+
+    // golden vectors (line breaks added for readability only).
+
+    const PAYLOAD: &str = "0x\
+        000000027b7d1111111111111111111111111111111111111111000000000000\
+        000000000000000000000000000000000000000000000000002a222222222222\
+        222222222222222222222222222222222222222222222222222201";
+
+    const VERIFY: &str = "0x\
+        9999999999999999999999999999999999999999999999999999999999999999\
+        9999999999999999999999999999999999999999999999999999999999999999\
+        9903";
+
+    const NOTARY: &str = "0x\
+        aaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaa\
+        aaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaa\
+        aa";
+
+    const ROUTE: &str = "0x\
+        3333333333333333333333333333333333333333333333333333333333333333\
+        3333333333333333333333333333333333333333333333333333333333333333\
+        3344444444444444444444444444444444444444444444444444444444444444\
+        4400000001555555555555555555555555555555555555555500000001000000\
+        0200666666666666666666666666666666666666666666666666666666666666\
+        6666017777777777777777777777777777777777777777777777777777777777\
+        777777";
+
+    const MESSAGE: &str = "0x\
+        000000027b7d1111111111111111111111111111111111111111000000000000\
+        000000000000000000000000000000000000000000000000002a222222222222\
+        2222222222222222222222222222222222222222222222222222010000000199\
+        9999999999999999999999999999999999999999999999999999999999999999\
+        9999999999999999999999999999999999999999999999999999999999999999\
+        0000000001aaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaa\
+        aaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaa\
+        aaaaaaaaaaaa0000000133333333333333333333333333333333333333333333\
+        3333333333333333333333333333333333333333333333333333333333333333\
+        3333333333333333333333444444444444444444444444444444444444444444\
+        4444444444444444444444000000015555555555555555555555555555555555\
+        5555550000000100000002006666666666666666666666666666666666666666\
+        6666666666666666666666660177777777777777777777777777777777777777\
+        7777777777777777777777777700000001888888888888888888888888888888\
+        888888888888888888888888888888888800";
+
+
+    #[test]
+    fn golden_vectors() {
+        let verify = Verify { sig: Signature([0x99;65]), val: Code::Malformed };
+        let notary = Notary { sig: Signature([0xaa;65]) };
+        assert_eq!(hex(&payload().to_bytes()),PAYLOAD);
+        assert_eq!(hex(&verify.to_bytes()),VERIFY);
+        assert_eq!(hex(&notary.to_bytes()),NOTARY);
+        assert_eq!(hex(&route().to_bytes()),ROUTE);
+        assert_eq!(hex(&message().to_bytes()),MESSAGE);
+        let golden: Bytes = MESSAGE.parse().unwrap();
+        assert_eq!(Message::from_bytes(&golden),Ok(message()));
+    }
+
+    #[test]
+    fn round_trip() {
+        let message = message();
+        let bytes = message.to_bytes();
+        assert_eq!(Message::from_bytes(&bytes),Ok(message));
+        assert_eq!(Route::from_bytes(&route().to_bytes()),Ok(route()));
+        assert_eq!(Payload::from_bytes(&payload().to_bytes()),Ok(payload()));
+        let records = vec!["{}".to_owned(),"[]".to_owned()];
+        let batch = Record::from(Batch::new(records).unwrap());
+        assert_eq!(Record::from_bytes(&batch.to_bytes()),Ok(batch.clone()));
+        let root = Record::Root(batch.root().unwrap());
+        assert_eq!(Record::from_bytes(&root.to_bytes()),Ok(root));
+        let mut message = self::message();
+        message.checkpoint = Some(Checkpoint { steps: 10, root: H256([0xbb;32]), ..Default::default() });
+        assert_eq!(Message::from_bytes(&message.to_bytes()),Ok(message));
+    }
+
+    #[test]
+    fn non_canonical() {
+        let bytes = message().to_bytes();
+        // truncated input is rejected at every possible length.
+        for len in 0..bytes.len() {
+            assert_eq!(Message::from_bytes(&bytes[..len]),Err(DecodeError::Truncated));
+        }
+        // trailing input is rejected.
+        let mut trailing = bytes.clone();
+        trailing.push(0x00);
+        assert_eq!(Message::from_bytes(&trailing),Err(DecodeError::Trailing));
+        // node flags other than left/right are rejected.
+        let mut flagged = route().to_bytes();
+        let offset = 65 + 32 + 4 + 20 + 4 + 4;
+        assert_eq!(flagged[offset],0x00);
+        flagged[offset] = 0x02;
+        assert_eq!(Route::from_bytes(&flagged),Err(DecodeError::Flag));
+        // proofs too long for their signed length byte are rejected.
+        let mut deep = route();
+        deep.path[0] = vec![NodeHash::Left(H256([0x66;32]));256];
+        assert_eq!(Route::from_bytes(&deep.to_bytes()),Err(DecodeError::Proof));
+        // records must be valid utf-8.
+        let mut record = payload().to_bytes();
+        record[5] = 0xff;
+        assert_eq!(Payload::from_bytes(&record),Err(DecodeError::Utf8));
+        // unknown signing versions are rejected.
+        let mut versioned = payload().to_bytes();
+        let len = versioned.len();
+        versioned[len - 1] = 0xff;
+        assert_eq!(Payload::from_bytes(&versioned),Err(DecodeError::Flag));
+        // batched records must have a known kind & at least one record.
+        let mut batched = Record::Root(H256([0x22;32])).to_bytes();
+        batched[5] = 0x02;
+        assert_eq!(Record::from_bytes(&batched),Err(DecodeError::Flag));
+        let empty = [0x00,0x00,0x00,0x06,0xff,0x00,0x00,0x00,0x00,0x00];
+        assert_eq!(Record::from_bytes(&empty),Err(DecodeError::Empty));
+        // oversized length prefixes are rejected before allocation.
+        let mut oversized = Message::default().to_bytes();
+        let len = oversized.len();
+        oversized[len - 5..len - 1].copy_from_slice(&[0xff;4]);
+        assert_eq!(Message::from_bytes(&oversized),Err(DecodeError::Truncated));
+    }
+}
@@ -1,10 +1,11 @@
 //! basic message visitors
 //!
 use message::cert::{Oracle,Notary,Blind,Clear,Route,Verify};
-use message::{Message,Payload};
+use message::{Message,Payload,Checkpoint};
 use visit::MessageVisitor;
 use mimir_crypto::{Hasher,HashVoyeur};
 use mimir_types::H256;
+use route;
 
 /// visitor which retains references to visited elements
 /// for later examination.
@@ -14,7 +15,8 @@ pub struct MessageVoyeur<'v> {
     verify: Vec<&'v Verify>,
     notary: Vec<&'v Notary>,
     route: Vec<&'v Route>,
-    blind: Vec<&'v H256>
+    blind: Vec<&'v H256>,
+    checkpoint: Option<&'v Checkpoint>
 }
 
 impl<'v> MessageVoyeur<'v> {
@@ -33,7 +35,8 @@ impl<'v> MessageVoyeur<'v> {
                 .map(|cert| (*cert).to_owned()).collect();
             let blind = self.blind.iter()
                 .map(|cert| (*cert).to_owned()).collect();
-            let msg = Message { payload, verify, notary, route, blind };
+            let checkpoint = self.checkpoint.cloned();
+            let msg = Message { payload, verify, notary, route, blind, checkpoint };
             Some(msg)
         } else {
             None
@@ -81,6 +84,67 @@ impl<'v> MessageVisitor<'v> for MessageVoyeur<'v> {
     fn visit_verify(&mut self, cert: &'v Verify) -> Self::Out {
         self.verify.push(cert);
     }
+
+    /// visit a checkpoint of collapsed circuits.
+    fn visit_checkpoint(&mut self, checkpoint: &'v Checkpoint) -> Self::Out {
+        self.checkpoint = Some(checkpoint);
+    }
+}
+
+
+
+/// visitor which tracks the `clear` & `notary` certs of the most
+/// recently completed circuit (used to seed route traversal).
+#[derive(Default,Debug,Clone)]
+pub struct SeedVisitor {
+    notary: Option<Notary>,
+    seed: Option<(Clear,Notary)>
+}
+
+
+impl SeedVisitor {
+
+    /// get reference to current seed values if
+    /// a circuit has been completed.
+    pub fn get_seed(&self) -> Option<(&Clear,&Notary)> {
+        self.seed.as_ref().map(|&(ref clear,ref notary)| (clear,notary))
+    }
+
+    /// derive traversal key for specified destination.
+    pub fn get_key(&self, dest: usize) -> Option<[u8;32]> {
+        self.get_seed().map(|(clear,notary)| route::traversal_key(clear,notary,dest))
+    }
+}
+
+
+impl<'v> MessageVisitor<'v> for SeedVisitor {
+
+    type Out = ();
+
+    fn visit_payload(&mut self, _: &Payload) -> Self::Out { }
+
+    fn visit_oracle(&mut self, _: &Oracle) -> Self::Out { }
+
+    fn visit_notary(&mut self, cert: &Notary) -> Self::Out {
+        self.notary = Some(*cert);
+    }
+
+    fn visit_blind(&mut self, _: &Blind) -> Self::Out { }
+
+    fn visit_clear(&mut self, cert: &Clear) -> Self::Out {
+        if let Some(notary) = self.notary.take() {
+            self.seed = Some((*cert,notary));
+        }
+    }
+
+    fn visit_route(&mut self, _: &Route) -> Self::Out { }
+
+    fn visit_verify(&mut self, _: &Verify) -> Self::Out { }
+
+    fn visit_checkpoint(&mut self, checkpoint: &Checkpoint) -> Self::Out {
+        self.notary = None;
+        self.seed = Some((checkpoint.clear,checkpoint.seal));
+    }
 }
 
 
@@ -144,9 +208,11 @@ impl<'v,T> MessageVisitor<'v> for HashVisitor<T> where T: Hasher {
     type Out = ();
 
     
+    /// the signing version is not absorbed, since it is
+    /// bound by the signing domain of each seal.
     fn visit_payload(&mut self, payload: &Payload) -> Self::Out {
-        let &Payload { ref record, ref address, ref number, ref hash } = payload;
-        self.hasher.absorb(record.as_ref());
+        let &Payload { ref record, ref address, ref number, ref hash, .. } = payload;
+        self.hasher.absorb(&record.signed_bytes());
         self.hasher.absorb(address.as_ref());
         self.hasher.absorb(number.as_ref());
         self.hasher.absorb(hash.as_ref());
@@ -175,18 +241,56 @@ impl<'v,T> MessageVisitor<'v> for HashVisitor<T> where T: Hasher {
  
     
     fn visit_route(&mut self, cert: &Route) -> Self::Out {
-        let &Route { ref val, ref sig } = cert;
-        for address in val.iter() {
-            self.hasher.absorb(address.as_ref()); 
-        }
-        self.hasher.absorb(sig.as_ref())
+        absorb_route(&mut self.hasher,cert);
+        self.hasher.absorb(cert.sig.as_ref())
     }
 
 
     fn visit_verify(&mut self, cert: &Verify) -> Self::Out {
         let &Verify { ref val, ref sig } = cert;
-        self.hasher.absorb(&[*val]);
+        self.hasher.absorb(&[val.as_u8()]);
         self.hasher.absorb(sig.as_ref());
     }
+
+
+    fn visit_checkpoint(&mut self, checkpoint: &Checkpoint) -> Self::Out {
+        absorb_checkpoint(&mut self.hasher,checkpoint);
+        self.hasher.absorb(checkpoint.sig.as_ref())
+    }
 }
 
+
+
+/// absorb the signed contents of a route cert (everything
+/// except the signature itself).
+///
+/// the root is followed by each destination address, the length
+/// of its proof, and the side & hash of each node in the proof.
+/// proof lengths are absorbed as a single byte, since proofs longer
+/// than `route::MAX_PROOF_LEN` are rejected.
+pub(crate) fn absorb_route<H>(hasher: &mut H, cert: &Route) where H: Hasher {
+    let &Route { ref root, ref val, ref path, .. } = cert;
+    hasher.absorb(root.as_ref());
+    for (address,nodes) in val.iter().zip(path.iter()) {
+        hasher.absorb(address.as_ref());
+        hasher.absorb(&[nodes.len() as u8]);
+        for node in nodes.iter() {
+            hasher.absorb(&[node.side()]);
+            hasher.absorb(node.hash().as_ref());
+        }
+    }
+}
+
+
+/// absorb the signed contents of a checkpoint (everything
+/// except the signature itself).
+///
+/// the step count is absorbed as a big-endian `u32`, followed by
+/// the root, and the clear & notary signature which seed routing.
+pub(crate) fn absorb_checkpoint<H>(hasher: &mut H, checkpoint: &Checkpoint) where H: Hasher {
+    let &Checkpoint { ref steps, ref root, ref clear, ref seal, .. } = checkpoint;
+    hasher.absorb(&[(steps >> 24) as u8,(steps >> 16) as u8,(steps >> 8) as u8,*steps as u8]);
+    hasher.absorb(root.as_ref());
+    hasher.absorb(clear.as_ref());
+    hasher.absorb(seal.sig.as_ref());
+}
@@ -1,5 +1,5 @@
 use message::cert::{Oracle,Notary,Blind,Clear,Route,Verify};
-use message::Payload;
+use message::{Payload,Checkpoint};
 
 
 /// Trait for processing messages with visitor pattern.
@@ -32,6 +32,9 @@ pub trait MessageVisitor<'v> {
  
     /// visit a verification cert.   
     fn visit_verify(&mut self, cert: &'v Verify) -> Self::Out;
+
+    /// visit a checkpoint of collapsed circuits.
+    fn visit_checkpoint(&mut self, checkpoint: &'v Checkpoint) -> Self::Out;
 }
 
 
@@ -52,6 +55,8 @@ impl<'a,'v: 'a,T> MessageVisitor<'v> for &'a mut T where T: MessageVisitor<'v> +
     fn visit_route(&mut self, cert: &'v Route) -> Self::Out { <T as MessageVisitor>::visit_route(self,cert) }
  
     fn visit_verify(&mut self, cert: &'v Verify) -> Self::Out { <T as MessageVisitor>::visit_verify(self,cert) }
+
+    fn visit_checkpoint(&mut self, checkpoint: &'v Checkpoint) -> Self::Out { <T as MessageVisitor>::visit_checkpoint(self,checkpoint) }
 }
 
 
@@ -72,4 +77,6 @@ impl<'v,T> MessageVisitor<'v> for Box<T> where T: MessageVisitor<'v> + ?Sized {
     fn visit_route(&mut self, cert: &'v Route) -> Self::Out { <T as MessageVisitor>::visit_route(self,cert) }
  
     fn visit_verify(&mut self, cert: &'v Verify) -> Self::Out { <T as MessageVisitor>::visit_verify(self,cert) }
+
+    fn visit_checkpoint(&mut self, checkpoint: &'v Checkpoint) -> Self::Out { <T as MessageVisitor>::visit_checkpoint(self,checkpoint) }
 }
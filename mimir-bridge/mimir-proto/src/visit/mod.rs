@@ -2,27 +2,46 @@
 //!
 use message::cert::{Oracle,Notary,Blind,Clear,Route,Verify};
 use mimir_types::{U256,H256,Address};
-use message::Payload;
+use message::{Payload,Checkpoint};
 
 
 mod message_visitor;
 mod block_visitor;
 mod message;
 mod block;
+mod registry;
 mod cert;
 mod util;
+mod strict;
+mod codec;
 
 
 pub use self::message_visitor::MessageVisitor;
 pub use self::block_visitor::BlockVisitor;
 pub use self::message::{
     MessageVoyeur,
+    SeedVisitor,
     ByteVisitor,
     HashVisitor,
 };
-pub use self::block::BlockState;
+pub use self::block::{BlockState,Entity};
+pub use self::registry::BlockRegistry;
 pub use self::cert::{CertVisitor,CertError};
 pub use self::util::apply;
+pub use self::strict::{
+    validate,
+    apply_strict,
+    StructError,
+};
+pub use self::codec::{
+    Encode,
+    Decode,
+    DecodeError,
+    Reader,
+};
+pub(crate) use self::message::{absorb_route,absorb_checkpoint};
+#[cfg(any(test,feature = "testkit"))]
+pub(crate) use self::cert::CertHasher;
 
 
 
@@ -49,9 +68,13 @@ impl BlockVisitor for EmptyVisitor {
 
     fn visit_hash(&self, _: &H256) -> Self::Out { true }
 
+    fn visit_root(&self, _: &H256) -> Self::Out { true }
+
     fn get_number(&self) -> Option<&U256> { None }
 
     fn get_hash(&self) -> Option<&H256> { None }
+
+    fn select(&mut self, _: &U256) { }
 }
 
 
@@ -72,5 +95,7 @@ impl<'v> MessageVisitor<'v> for EmptyVisitor {
     fn visit_route(&mut self, _: &Route) -> Self::Out { }
  
     fn visit_verify(&mut self, _: &Verify) -> Self::Out { }
+
+    fn visit_checkpoint(&mut self, _: &Checkpoint) -> Self::Out { }
 }
 
@@ -0,0 +1,281 @@
+//! registry of recent block states
+//!
+use mimir_types::{U256,H256,Address};
+use std::collections::VecDeque;
+use std::sync::Arc;
+use visit::{BlockVisitor,BlockState,Entity};
+use message::Role;
+
+
+/// default number of blocks retained by a registry.
+pub const DEFAULT_CAPACITY: usize = 64;
+
+
+/// bounded registry of the states of recent blocks.
+///
+/// messages are judged against the state of the block to which their
+/// payload is pinned, rather than the most recent block.  when acting
+/// as a visitor, the registry checks all values against the state whose
+/// number matches the last selected number (see `BlockVisitor::select`).
+/// if no such state is held, the most recent state is used instead (and
+/// its `window` determines whether or not the number is acceptable, since
+/// the hash of an unheld block cannot be checked).
+/// an empty registry accepts all values, as with a blank `BlockState`.
+///
+/// states are cheaply shared, so a registry may be cloned for each
+/// message which is to be judged.
+///
+/// ```
+/// #
+/// extern crate mimir_proto;
+/// use mimir_proto::visit::{BlockVisitor,BlockState,BlockRegistry};
+/// # fn main() {
+///
+/// let mut registry = BlockRegistry::new(2);
+/// for number in 1..4u64 {
+///     registry.insert(BlockState::new().number(number.into()));
+/// }
+///
+/// assert_eq!(registry.len(),2);
+/// assert!(registry.get(&1u64.into()).is_none());
+///
+/// registry.select(&2u64.into());
+/// assert_eq!(registry.get_number(),Some(&2u64.into()));
+/// # }
+/// ```
+///
+#[derive(Debug,Clone)]
+pub struct BlockRegistry {
+    /// known block states, ordered by block number.
+    blocks: VecDeque<Arc<BlockState>>,
+
+    /// maximum number of states to retain.
+    capacity: usize,
+
+    /// state selected for visitation.
+    selected: Option<Arc<BlockState>>,
+}
+
+
+impl Default for BlockRegistry {
+
+    fn default() -> Self { Self::new(DEFAULT_CAPACITY) }
+}
+
+
+impl BlockRegistry {
+
+    /// get new (empty) registry which retains at
+    /// most `capacity` block states.
+    pub fn new(capacity: usize) -> Self {
+        let capacity = capacity.max(1);
+        let blocks = VecDeque::with_capacity(capacity);
+        BlockRegistry { blocks, capacity, selected: None }
+    }
+
+    /// insert the state of a block, evicting the lowest numbered
+    /// state if the registry is full.  any existing state w/ the same
+    /// block number is replaced (e.g. in the event of a reorg).
+    pub fn insert<B>(&mut self, block: B) where B: Into<Arc<BlockState>> {
+        let block = block.into();
+        if let Some(number) = block.number {
+            self.blocks.retain(|known| known.number != Some(number));
+        }
+        let index = self.blocks.iter()
+            .position(|known| known.number > block.number)
+            .unwrap_or(self.blocks.len());
+        self.blocks.insert(index,block);
+        while self.blocks.len() > self.capacity {
+            let _ = self.blocks.pop_front();
+        }
+    }
+
+    /// get the state of the specified block, if known.
+    pub fn get(&self, number: &U256) -> Option<&Arc<BlockState>> {
+        self.blocks.iter().rev().find(|block| block.number.as_ref() == Some(number))
+    }
+
+    /// get the state of the highest numbered block.
+    pub fn latest(&self) -> Option<&Arc<BlockState>> { self.blocks.back() }
+
+    /// number of block states currently held.
+    pub fn len(&self) -> usize { self.blocks.len() }
+
+    /// check if no block states are currently held.
+    pub fn is_empty(&self) -> bool { self.blocks.is_empty() }
+
+    /// maximum number of block states retained.
+    pub fn capacity(&self) -> usize { self.capacity }
+
+    /// get the state against which values are currently checked.
+    fn current(&self) -> Option<&BlockState> {
+        self.selected.as_ref().or_else(|| self.latest()).map(AsRef::as_ref)
+    }
+}
+
+
+impl BlockVisitor for BlockRegistry {
+
+    /// output generated upon visitation
+    type Out = bool;
+
+    /// visit a validator address
+    fn visit_validator(&self, ident: &Address) -> Self::Out {
+        self.current().map(|block| block.visit_validator(ident)).unwrap_or(true)
+    }
+
+    /// visit a router address
+    fn visit_router(&self, ident: &Address) -> Self::Out {
+        self.current().map(|block| block.visit_router(ident)).unwrap_or(true)
+    }
+
+    /// visit a notary address
+    fn visit_notary(&self, ident: &Address) -> Self::Out {
+        self.current().map(|block| block.visit_notary(ident)).unwrap_or(true)
+    }
+
+    /// visit the address of an entity in a specific role
+    fn visit_entity(&self, role: Role, ident: &Address) -> Self::Out {
+        self.current().map(|block| block.visit_entity(role,ident)).unwrap_or(true)
+    }
+
+    /// visit a block number
+    fn visit_number(&self, number: &U256) -> Self::Out {
+        self.current().map(|block| block.visit_number(number)).unwrap_or(true)
+    }
+
+    /// visit a block hash
+    fn visit_hash(&self, hash: &H256) -> Self::Out {
+        self.current().map(|block| block.visit_hash(hash)).unwrap_or(true)
+    }
+
+    /// visit a validator routing tree root
+    fn visit_root(&self, root: &H256) -> Self::Out {
+        self.current().map(|block| block.visit_root(root)).unwrap_or(true)
+    }
+
+    /// get reference to number of the selected block
+    fn get_number(&self) -> Option<&U256> {
+        self.current().and_then(|block| block.get_number())
+    }
+
+    /// get reference to hash of the selected block
+    fn get_hash(&self) -> Option<&H256> {
+        self.current().and_then(|block| block.get_hash())
+    }
+
+    /// get info about an entity of the selected block
+    fn get_entity(&self, role: Role, ident: &Address) -> Option<&Entity> {
+        self.current().and_then(|block| block.get_entity(role,ident))
+    }
+
+    /// select the state of the specified block (falls
+    /// back to the latest state if none is known).
+    fn select(&mut self, number: &U256) {
+        self.selected = self.get(number).cloned();
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use visit::{BlockVisitor,BlockState,BlockRegistry};
+    use mimir_types::{Address,H256};
+    use std::collections::HashSet;
+    use std::sync::Arc;
+    use std::rc::Rc;
+
+    fn block(number: u64, validator: Address) -> BlockState {
+        let mut validators = HashSet::new();
+        validators.insert(validator);
+        BlockState::new()
+            .number(number.into())
+            .hash(H256([number as u8;32]))
+            .validators(validators)
+    }
+
+    #[test]
+    fn historical_lookup() {
+        let (old,new) = (Address([1;20]),Address([2;20]));
+        let mut registry = BlockRegistry::new(3);
+        assert!(registry.visit_validator(&old));
+        registry.insert(block(10,old));
+        registry.insert(block(11,new));
+        // selection pins checks to the state of the selected block.
+        registry.select(&10u64.into());
+        assert!(registry.visit_validator(&old) && !registry.visit_validator(&new));
+        assert!(registry.visit_hash(&H256([10;32])));
+        registry.select(&11u64.into());
+        assert!(!registry.visit_validator(&old) && registry.visit_validator(&new));
+        // unknown blocks fall back to the latest state.
+        registry.select(&9u64.into());
+        assert_eq!(registry.get_number(),Some(&11u64.into()));
+        assert!(!registry.visit_number(&9u64.into()));
+    }
+
+    fn select_old<B: BlockVisitor<Out=bool>>(mut visitor: B) -> B {
+        visitor.select(&10u64.into());
+        visitor
+    }
+
+    #[test]
+    fn wrapped_selection() {
+        let old = Address([1;20]);
+        let mut registry = BlockRegistry::new(3);
+        registry.insert(block(10,old));
+        registry.insert(block(11,Address([2;20])));
+        // selection must reach the registry through any wrapper.
+        assert!(select_old(&mut registry.clone()).visit_validator(&old));
+        assert!(select_old(Box::new(registry.clone())).visit_validator(&old));
+        assert!(select_old(Rc::new(registry.clone())).visit_validator(&old));
+        let shared = Arc::new(registry);
+        assert!(select_old(shared.clone()).visit_validator(&old));
+        // selecting through a shared handle leaves other handles unaffected.
+        assert!(!shared.visit_validator(&old));
+        // shared references retain any prior selection.
+        let selected = select_old(Arc::clone(&shared));
+        assert!(select_old(&*selected).visit_validator(&old));
+        assert!(!select_old(&*shared).visit_validator(&old));
+    }
+
+    #[test]
+    fn eviction() {
+        let addr = Address([1;20]);
+        let mut registry = BlockRegistry::new(2);
+        for number in 0..4 {
+            registry.insert(block(number,addr));
+        }
+        assert_eq!(registry.len(),2);
+        assert!(registry.get(&1u64.into()).is_none());
+        assert!(registry.get(&2u64.into()).is_some());
+        // re-inserting a known block replaces its state.
+        registry.insert(block(3,Address([2;20])));
+        assert_eq!(registry.len(),2);
+        assert!(registry.get(&2u64.into()).is_some());
+        assert_eq!(registry.latest().and_then(|block| block.number),Some(3u64.into()));
+    }
+
+    #[test]
+    fn reorg() {
+        let (old,new) = (Address([1;20]),Address([2;20]));
+        let mut registry = BlockRegistry::new(3);
+        for number in 10..13 {
+            registry.insert(block(number,old));
+        }
+        // replacing an earlier block leaves the latest state in place.
+        registry.insert(block(11,new));
+        assert_eq!(registry.latest().and_then(|block| block.number),Some(12u64.into()));
+        registry.select(&11u64.into());
+        assert!(registry.visit_validator(&new));
+        // blocks arriving out of order are held by number, evicting the lowest.
+        registry.insert(block(14,old));
+        registry.insert(block(13,new));
+        assert_eq!(registry.len(),3);
+        assert!(registry.get(&11u64.into()).is_none());
+        assert_eq!(registry.latest().and_then(|block| block.number),Some(14u64.into()));
+        // stale blocks are evicted immediately.
+        registry.insert(block(9,new));
+        assert!(registry.get(&9u64.into()).is_none());
+        assert_eq!(registry.len(),3);
+    }
+}
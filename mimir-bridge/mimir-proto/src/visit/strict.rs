@@ -0,0 +1,197 @@
+//! strict structural validation of messages.
+//!
+//! `apply` stops at the first step for which no cert exists, ignoring
+//! any certs which remain.  the helpers in this module additionally
+//! require that every cert of a message is consumed by the circuit.
+//!
+use message::{Message,CircuitSpec,STEP};
+use visit::{self,MessageVisitor};
+use std::{fmt,error};
+
+
+/// error indicating a structurally invalid message.
+#[derive(Debug,Copy,Clone,PartialEq,Eq)]
+pub enum StructError {
+    /// certs which were not consumed by the circuit
+    Unconsumed {
+        /// kind of the leftover certs (`VERIFY` includes oracle certs)
+        step: STEP,
+
+        /// number of leftover certs
+        count: usize
+    },
+
+    /// blind/clear values which do not pair up with the circuit
+    Unbalanced {
+        /// number of blinds consumed
+        blinds: usize,
+
+        /// number of clears consumed
+        clears: usize,
+
+        /// number of leftover values
+        count: usize
+    },
+
+    /// checkpoint which does not end on a circuit boundary
+    Checkpoint {
+        /// number of steps collapsed into the checkpoint
+        steps: usize
+    }
+}
+
+
+impl StructError {
+
+    /// get simple static error message
+    pub fn as_str(&self) -> &'static str {
+        match *self {
+            StructError::Unconsumed { .. } => "unconsumed certs",
+            StructError::Unbalanced { .. } => "unbalanced blind/clear pairing",
+            StructError::Checkpoint { .. } => "misaligned checkpoint",
+        }
+    }
+}
+
+
+impl fmt::Display for StructError {
+
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            StructError::Unconsumed { ref step, ref count } => {
+                write!(f,"{} unconsumed `{:?}` cert(s)",count,step)
+            },
+            StructError::Unbalanced { ref blinds, ref clears, ref count } => {
+                write!(f,"{} stray blind/clear value(s) ({} blind(s), {} clear(s) consumed)",count,blinds,clears)
+            },
+            StructError::Checkpoint { ref steps } => {
+                write!(f,"checkpoint of {} step(s) does not end on a circuit boundary",steps)
+            },
+        }
+    }
+}
+
+
+impl error::Error for StructError {
+
+    fn description(&self) -> &str {
+        self.as_str()
+    }
+}
+
+
+/// check that all certs of a message are consumed by the circuit.
+///
+/// returns the next expected step on success.
+///
+pub fn validate(spec: &CircuitSpec, message: &Message) -> Result<STEP,StructError> {
+    let &Message { ref verify, ref notary, ref route, ref blind, .. } = message;
+    let (mut verify,mut notary,mut route,mut blind) = (verify.len(),notary.len(),route.len(),blind.len());
+    let (mut blinds,mut clears) = (0,0);
+    let mut index = message.collapsed();
+    if index > 0 && !spec.is_boundary(index) {
+        return Err(StructError::Checkpoint { steps: index });
+    }
+    let next = loop {
+        let step = spec.step(index);
+        let remaining = match step {
+            STEP::ORACLE | STEP::VERIFY => &mut verify,
+            STEP::NOTARY => &mut notary,
+            STEP::ROUTE => &mut route,
+            STEP::BLIND | STEP::CLEAR => &mut blind,
+        };
+        if *remaining == 0 { break step; }
+        *remaining -= 1;
+        match step {
+            STEP::BLIND => blinds += 1,
+            STEP::CLEAR => clears += 1,
+            _ => { },
+        }
+        index += 1;
+    };
+    let leftover = [(STEP::VERIFY,verify),(STEP::NOTARY,notary),(STEP::ROUTE,route)];
+    if let Some(&(step,count)) = leftover.iter().find(|&&(_,count)| count > 0) {
+        return Err(StructError::Unconsumed { step, count });
+    }
+    if blind > 0 {
+        return Err(StructError::Unbalanced { blinds, clears, count: blind });
+    }
+    Ok(next)
+}
+
+
+/// apply visitor to a message, after checking that all certs
+/// of the message will be consumed (see `validate`).
+///
+/// the visitor is not invoked if the message is invalid.
+///
+pub fn apply_strict<'v,V>(spec: &CircuitSpec, visitor: V, message: &'v Message) -> Result<STEP,StructError> where V: MessageVisitor<'v,Out=()> {
+    validate(spec,message)?;
+    Ok(visit::apply(spec,visitor,message))
+}
+
+
+#[cfg(test)]
+mod tests {
+    use message::{Message,Payload,CircuitSpec,Checkpoint,STEP};
+    use visit::{self,StructError,ByteVisitor};
+
+    /// message w/ certs filling the oracle circuit & the
+    /// blind of the first verify circuit.
+    fn message() -> Message {
+        let mut message = Message::new(Payload::default());
+        message.blind.extend_from_slice(&[Default::default(),Default::default(),Default::default()]);
+        message.verify.push(Default::default());
+        message.notary.push(Default::default());
+        message
+    }
+
+    #[test]
+    fn valid_structure() {
+        let spec = CircuitSpec::default();
+        let message = message();
+        assert_eq!(visit::validate(&spec,&message),Ok(STEP::ROUTE));
+        let step = visit::apply_strict(&spec,ByteVisitor::default(),&message);
+        assert_eq!(step,Ok(visit::apply(&spec,ByteVisitor::default(),&message)));
+        let empty = Message::new(Payload::default());
+        assert_eq!(visit::validate(&spec,&empty),Ok(STEP::BLIND));
+    }
+
+    #[test]
+    fn unconsumed_certs() {
+        let spec = CircuitSpec::default();
+        let mut message = message();
+        message.route.extend_from_slice(&[Default::default(),Default::default(),Default::default(),Default::default()]);
+        // one route is consumed, then the circuit stops at the first verify step.
+        let expect = StructError::Unconsumed { step: STEP::ROUTE, count: 3 };
+        assert_eq!(visit::validate(&spec,&message),Err(expect));
+        let mut message = self::message();
+        message.verify.push(Default::default());
+        let expect = StructError::Unconsumed { step: STEP::VERIFY, count: 1 };
+        assert_eq!(visit::apply_strict(&spec,ByteVisitor::default(),&message),Err(expect));
+    }
+
+    #[test]
+    fn unbalanced_blinds() {
+        let spec = CircuitSpec::default();
+        let mut message = message();
+        message.blind.push(Default::default());
+        let expect = StructError::Unbalanced { blinds: 2, clears: 1, count: 1 };
+        assert_eq!(visit::validate(&spec,&message),Err(expect));
+        let mut message = Message::new(Payload::default());
+        message.blind.extend_from_slice(&[Default::default(),Default::default()]);
+        let expect = StructError::Unbalanced { blinds: 1, clears: 0, count: 1 };
+        assert_eq!(visit::validate(&spec,&message),Err(expect));
+    }
+
+    #[test]
+    fn misaligned_checkpoint() {
+        let spec = CircuitSpec::default();
+        let mut message = Message::new(Payload::default());
+        message.checkpoint = Some(Checkpoint { steps: 10, ..Default::default() });
+        assert_eq!(visit::validate(&spec,&message),Ok(STEP::BLIND));
+        message.checkpoint = Some(Checkpoint { steps: 6, ..Default::default() });
+        let expect = StructError::Checkpoint { steps: 6 };
+        assert_eq!(visit::validate(&spec,&message),Err(expect));
+    }
+}
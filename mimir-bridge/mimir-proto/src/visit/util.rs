@@ -1,6 +1,6 @@
 //! helpers related to applying visitors
 //!
-use message::{Message,STEP};
+use message::{Message,CircuitSpec,STEP};
 use visit::MessageVisitor;
 
 
@@ -15,22 +15,31 @@ macro_rules! try_next_or {
 }
 
 
-/// apply a visitor to a function.
+/// apply a visitor to a message.
 ///
 /// performs the minimal logic necessary to apply a visitor to a
-/// message.  returns the value of the step at which application could
-/// no longer continue.
-pub fn apply<'v,V>(mut visitor: V, message: &'v Message) -> STEP where V: MessageVisitor<'v,Out=()> {
+/// message, with steps ordered as per the supplied circuit spec.
+/// returns the value of the step at which application could no
+/// longer continue.
+///
+/// if the message holds a checkpoint, it is visited immediately after
+/// the payload, and application resumes at the first step following
+/// the collapsed circuits.
+pub fn apply<'v,V>(spec: &CircuitSpec, mut visitor: V, message: &'v Message) -> STEP where V: MessageVisitor<'v,Out=()> {
     // destructure message, and generate iterators for all certs.
-    let &Message { ref payload, ref verify, ref notary, ref route, ref blind } = message;
+    let &Message { ref payload, ref verify, ref notary, ref route, ref blind, ref checkpoint } = message;
     let mut verify_certs = verify.iter();
     let mut notary_certs = notary.iter();
     let mut route_certs = route.iter();
     let mut blind_certs = blind.iter();
     // pass message payload to visitor.
     visitor.visit_payload(payload);
+    // pass checkpoint (if any) to visitor.
+    if let Some(ref checkpoint) = *checkpoint {
+        visitor.visit_checkpoint(checkpoint);
+    }
     // apply certs until an iterator returns none
-    for step in (0..).into_iter().map(|i| STEP::new(i)) {
+    for step in (message.collapsed()..).into_iter().map(|i| spec.step(i)) {
         match step {
             STEP::ORACLE => visitor.visit_oracle(try_next_or!(verify_certs,step)),
             
@@ -48,3 +57,54 @@ pub fn apply<'v,V>(mut visitor: V, message: &'v Message) -> STEP where V: Messag
     unreachable!()
 }
 
+
+
+#[cfg(test)]
+mod tests {
+    use testkit::{Fixture,Corruption};
+    use visit::{self,ByteVisitor,EmptyVisitor};
+    use seal::util::raw;
+    use seal::Sealer;
+
+    #[test]
+    fn apply_prefixes() {
+        let fixture = Fixture::default();
+        let spec = &fixture.spec;
+        for steps in 0..fixture.len(3) {
+            let message = fixture.steps(steps);
+            // application halts at the step following the last cert.
+            assert_eq!(visit::apply(spec,EmptyVisitor,&message),spec.step(steps));
+            assert_eq!(visit::apply(spec,EmptyVisitor,&message),message.next_step(spec));
+            assert_eq!(visit::validate(spec,&message),Ok(spec.step(steps)));
+        }
+    }
+
+    #[test]
+    fn apply_visits_signed_bytes() {
+        let fixture = Fixture::default();
+        let (spec,domain) = (&fixture.spec,&fixture.domain);
+        let signer = &fixture.notaries[0];
+        for circuits in 0..3 {
+            let message = fixture.message(circuits);
+            let mut bytes = ByteVisitor::default();
+            visit::apply(spec,&mut bytes,&message);
+            // judge & sealer see the same bytes as any other visitor.
+            assert_eq!(fixture.judge(&message).bytes,bytes.as_bytes());
+            let cert = signer.seal_notary(spec,domain,&message);
+            assert_eq!(cert,raw::notary(signer,domain,bytes.as_bytes()));
+        }
+    }
+
+    #[test]
+    fn apply_truncated() {
+        let fixture = Fixture::default();
+        for circuits in 0..3 {
+            let message = fixture.corrupted(circuits,Corruption::Truncate);
+            let certs = message.verify.len() + message.notary.len()
+                + message.route.len() + message.blind.len();
+            // application halts before all certs are consumed.
+            assert!(fixture.judge(&message).steps.len() < certs);
+            assert!(visit::validate(&fixture.spec,&message).is_err());
+        }
+    }
+}
@@ -36,6 +36,23 @@ pub struct U256(pub [u8;32]);
 newtype!(U256,[u8;32],[u8]);
 
 
+impl U256 {
+
+    /// subtract `other` from `self`, saturating at zero.
+    pub fn saturating_sub(&self, other: &U256) -> Self {
+        if self <= other { return Default::default(); }
+        let mut buff = [0u8;32];
+        let mut borrow = 0i16;
+        for idx in (0..32).rev() {
+            let diff = self.0[idx] as i16 - other.0[idx] as i16 - borrow;
+            borrow = if diff < 0 { 1 } else { 0 };
+            buff[idx] = (diff + (borrow << 8)) as u8;
+        }
+        U256(buff)
+    }
+}
+
+
 impl FromStr for U256 {
 
     type Err = ParseHexError;
@@ -174,6 +191,17 @@ mod tests {
         }
     }
 
+    #[test]
+    fn saturating_sub() {
+        for _ in 0..32 {
+            let (a,b): (u64,u64) = rand::random();
+            let expect = U256::from(a.saturating_sub(b));
+            assert_eq!(U256::from(a).saturating_sub(&U256::from(b)),expect);
+        }
+        let big: U256 = "0x10000000000000000".parse().unwrap();
+        assert_eq!(big.saturating_sub(&1u64.into()),U256::from(u64::max_value()));
+    }
+
     fn check_pair(int: U256, hex: &str) {
         let into_hex = format!("{:?}",int);
         let into_int = hex.parse().unwrap();
@@ -18,5 +18,6 @@ pub mod hex;
 
 pub use misc::{
     unix_time,
+    create_private,
 };
 
@@ -1,4 +1,7 @@
 use std::time::{SystemTime, UNIX_EPOCH};
+use std::fs::{File,OpenOptions};
+use std::path::Path;
+use std::io;
 
 
 /// get the current unix timestamp.  
@@ -14,3 +17,49 @@ pub fn unix_time() -> u64 {
 }
 
 
+/// create (or truncate) a file which is only accessible to
+/// its owner (e.g. for storing key material).
+///
+/// on unix, the file is given mode `0600` irrespective of the
+/// process umask, and the permissions of existing files are reset.
+///
+pub fn create_private<P: AsRef<Path>>(path: P) -> Result<File,io::Error> {
+    let mut options = OpenOptions::new();
+    options.write(true).create(true).truncate(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+    let file = options.open(path.as_ref())?;
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        use std::fs::Permissions;
+        file.set_permissions(Permissions::from_mode(0o600))?;
+    }
+    Ok(file)
+}
+
+
+#[cfg(test)]
+mod tests {
+    use misc::create_private;
+    use std::io::Write;
+    use std::env;
+    use std::fs;
+
+    #[test]
+    #[cfg(unix)]
+    fn private_mode() {
+        use std::os::unix::fs::PermissionsExt;
+        let path = env::temp_dir().join(format!("mimir-util-private-{}",::std::process::id()));
+        fs::write(&path,"public").unwrap();
+        fs::set_permissions(&path,fs::Permissions::from_mode(0o644)).unwrap();
+        create_private(&path).unwrap().write_all(b"private").unwrap();
+        let meta = fs::metadata(&path).unwrap();
+        assert_eq!(meta.permissions().mode() & 0o777,0o600);
+        assert_eq!(fs::read_to_string(&path).unwrap(),"private");
+        fs::remove_file(&path).unwrap();
+    }
+}
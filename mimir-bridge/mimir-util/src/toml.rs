@@ -26,6 +26,7 @@ use serde::ser::Serialize;
 use std::io::{Read,Write};
 use std::fs::OpenOptions;
 use std::path::Path;
+use misc::create_private;
 use _toml;
 
 
@@ -82,3 +83,16 @@ pub fn save<T,P>(value: &T, path: P) -> Result<(),TomlError> where T: Serialize,
         .map_err(|_| TomlError::WriteFile)
 }
 
+
+/// attempt to save value to a toml file which is only
+/// accessible to its owner (see `create_private`).
+///
+pub fn save_private<T,P>(value: &T, path: P) -> Result<(),TomlError> where T: Serialize, P: AsRef<Path> {
+    let mut file = create_private(path)
+        .map_err(|_| TomlError::OpenFile)?;
+    let serialized = _toml::to_string(value)
+        .map_err(|_| TomlError::Serialize)?;
+    file.write_all(serialized.as_ref())
+        .map_err(|_| TomlError::WriteFile)
+}
+
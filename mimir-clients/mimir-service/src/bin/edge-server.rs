@@ -18,6 +18,7 @@ use mimir_transport::{
     ws
 };
 use log::LevelFilter;
+use std::sync::Arc;
 
 
 fn main() {
@@ -44,11 +45,15 @@ fn main() {
 
     let auth_server = edge::Policy::new(lease_server,config.policies.clone());
 
+    let circuit_spec = Arc::new(config.circuit_spec.clone());
+
     let work = ws_server.for_each(|(client,address)| {
         info!("incoming connection from {}",address);
         let conn_handle = handle.clone();
         let conn_work = edge::serve_connection(
             auth_server.clone(),
+            config.signing_domain,
+            circuit_spec.clone(),
             client,
             config.redis_address,
             conn_handle
@@ -1,7 +1,7 @@
 use std::path::Path;
 use mimir_util::toml::{self,TomlError};
 use mimir_transport::edge::AuthPolicy;
-use mimir_transport::common::Role;
+use mimir_transport::common::{Role,Domain,CircuitSpec};
 use std::collections::HashMap;
 use std::net::SocketAddr;
 use std::str::FromStr;
@@ -26,6 +26,14 @@ pub struct Config {
     /// socket-address for redis instance
     #[serde(rename = "redis-address", default = "defaults::redis_address")]
     pub redis_address: SocketAddr,
+
+    /// signing domain of the bridge deployment
+    #[serde(rename = "signing-domain", default)]
+    pub signing_domain: Domain,
+
+    /// circuit spec against which message payloads are validated
+    #[serde(rename = "circuit-spec", default)]
+    pub circuit_spec: CircuitSpec,
 }
 
 
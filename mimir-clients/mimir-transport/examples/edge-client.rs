@@ -10,6 +10,7 @@ extern crate log;
 use mimir_transport::{ws,edge};
 use mimir_transport::common::{
     Command,
+    Domain,
     Role,
 };
 use mimir_crypto::secp256k1::{
@@ -31,14 +32,16 @@ fn main() {
 
     let address = "ws://127.0.0.1:8888".parse().unwrap();
 
+    let domain = Domain::legacy();
+
     let mut conn_work = Vec::new();
 
     for i in 0..32 {
         let (_,secret) = keygen();
         let signer = Signer::new(secret).unwrap();
-        let identify = Command::identify(Role::Admin,&signer);
+        let identify = Command::identify(Role::Admin,&domain,&signer);
         let debug = Command::debug(identify.dest,format!("edge-client test {:02}",i));
-        let kick = Command::kick(identify.dest,&signer);
+        let kick = Command::kick(identify.dest,&domain,&signer);
         let connect = ws::client::connect(&handle,&address,identify.to_string());
         let work = connect.map_err(|e|e.into())
             .and_then(move |client| {
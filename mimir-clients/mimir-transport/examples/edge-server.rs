@@ -7,6 +7,7 @@ extern crate log;
 
 use tokio_core::reactor::Core;
 use futures::{Future,Stream};
+use mimir_transport::common::Domain;
 use mimir_transport::edge;
 use mimir_transport::ws;
 use log::LevelFilter;
@@ -32,6 +33,8 @@ fn main() {
         let conn_handle = handle.clone();
         let conn_work = edge::serve_connection(
             auth_server.clone(),
+            Domain::legacy(),
+            Default::default(),
             client,
             redis_addr,
             conn_handle
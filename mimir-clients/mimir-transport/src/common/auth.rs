@@ -1,12 +1,18 @@
 use mimir_types::{Signature,Address,U256};
-use mimir_proto::seal::Sealer;
+use mimir_proto::seal::{Sealer,Domain,Tag};
 use mimir_crypto::secp256k1::{
     Verifier,
     Error,
 };
+use mimir_crypto::eip191::personal_hash;
+use mimir_crypto::eip712::StructHasher;
 use mimir_crypto::Keccak256;
 use mimir_util::unix_time;
-use common::Role;
+use common::{Role,Scheme};
+
+
+/// `eip-712` encoded type of `Auth`.
+pub const AUTH_TYPE: &str = "Auth(address addr,string role,uint256 time)";
 
 
 /// payload of the `AUTH` operation.
@@ -18,6 +24,17 @@ use common::Role;
 /// Because of the included timestamp, this structure should
 /// be lazily initialized immediately prior to broadcast.
 ///
+/// The seal is bound to the signing domain of the deployment,
+/// which must be supplied again when checking the seal.
+///
+/// Wallet users may instead seal the `personal_sign` hash of
+/// the domain-separated hash (`Scheme::Personal`), or sign
+/// the fields as `eip-712` typed data of type `AUTH_TYPE`
+/// under `Domain::typed` (`Scheme::Typed`).
+///
+/// The convenience methods only accept canonical seals
+/// (see `Verifier::strict`).
+///
 #[derive(Debug,Copy,Clone,PartialEq,Eq,Serialize,Deserialize)]
 pub struct Auth {
     pub addr: Address,
@@ -29,28 +46,65 @@ pub struct Auth {
 
 impl Auth {
 
-    pub fn new<S: Sealer>(sealer: S, role: Role) -> Self {
+    pub fn new<S: Sealer>(sealer: S, domain: &Domain, role: Role) -> Self {
+        Self::new_with(sealer,domain,role,Scheme::Raw)
+    }
+
+    pub fn new_with<S: Sealer>(sealer: S, domain: &Domain, role: Role, scheme: Scheme) -> Self {
         let addr = sealer.address();
         let time = unix_time().into();
-        let hash = hash_elems(&addr,role,time);
-        let seal = sealer.sign(&hash);
-        Auth { addr, role, time, seal }
+        let mut auth = Auth { addr, role, time, seal: Default::default() };
+        auth.seal = sealer.sign(&auth.signing_hash(domain,scheme));
+        auth
     }
 
-    pub fn check_seal(&self) -> Result<(),Error> {
-        let verifier = Default::default();
-        self.check_with(&verifier)
+    pub fn check_seal(&self, domain: &Domain) -> Result<(),Error> {
+        let verifier = Verifier::strict();
+        self.check_with(domain,&verifier)
     }
 
-    pub fn check_with(&self,verifier: &Verifier) -> Result<(),Error> {
-        let hash = hash_elems(&self.addr,self.role,self.time);
-        let address = verifier.ecrecover(&hash,&self.seal)?;
+    pub fn check_with(&self, domain: &Domain, verifier: &Verifier) -> Result<(),Error> {
+        let address = self.recover_with(domain,verifier)?;
         if self.addr == address {
             Ok(())
         } else {
             Err(Error::AddressMismatch)
         }
     }
+
+    /// check seal under all accepted schemes, returning the scheme used.
+    pub fn check_any(&self, domain: &Domain, verifier: &Verifier) -> Result<Scheme,Error> {
+        Scheme::detect(&self.addr,&self.seal,verifier,|scheme| self.signing_hash(domain,scheme))
+    }
+
+    pub fn recover(&self, domain: &Domain) -> Result<Address,Error> {
+        let verifier = Verifier::strict();
+        self.recover_with(domain,&verifier)
+    }
+
+    pub fn recover_with(&self, domain: &Domain, verifier: &Verifier) -> Result<Address,Error> {
+        self.recover_scheme(domain,Scheme::Raw,verifier)
+    }
+
+    pub fn recover_scheme(&self, domain: &Domain, scheme: Scheme, verifier: &Verifier) -> Result<Address,Error> {
+        verifier.ecrecover(&self.signing_hash(domain,scheme),&self.seal)
+    }
+
+    /// get the hash which is sealed under the specified scheme.
+    pub fn signing_hash(&self, domain: &Domain, scheme: Scheme) -> [u8;32] {
+        match scheme {
+            Scheme::Raw => domain.hash(Tag::Auth,&hash_elems(&self.addr,self.role,self.time)),
+            Scheme::Personal => personal_hash(&self.signing_hash(domain,Scheme::Raw)),
+            Scheme::Typed => {
+                let hash = StructHasher::new(AUTH_TYPE)
+                    .address(&self.addr)
+                    .string(self.role.as_ref())
+                    .word(&self.time.0)
+                    .finish();
+                domain.typed().hash(&hash)
+            },
+        }
+    }
 }
 
 
@@ -63,3 +117,29 @@ fn hash_elems(addr: &Address, role: Role, time: U256) -> [u8;32] {
     hasher.finish()
 }
 
+
+#[cfg(test)]
+mod tests {
+    use mimir_crypto::secp256k1::{dev,Signer,Verifier,Error};
+    use common::{Auth,Domain,Role,Scheme};
+
+    #[test]
+    fn wallet_schemes() {
+        let signer = Signer::new(dev::SECRET).unwrap();
+        let domain = Domain::new(1u64.into(),"0x000000000000000000000000000000000000beef".parse().unwrap());
+        let verifier = Verifier::strict();
+        for &scheme in Scheme::all().iter() {
+            let auth = Auth::new_with(&signer,&domain,Role::Requester,scheme);
+            assert_eq!(auth.check_any(&domain,&verifier).unwrap(),scheme);
+            assert_eq!(auth.recover_scheme(&domain,scheme,&verifier).unwrap(),signer.address());
+            assert_eq!(auth.check_any(&Domain::legacy(),&verifier),Err(Error::AddressMismatch));
+            if scheme != Scheme::Raw {
+                assert_eq!(auth.check_with(&domain,&verifier),Err(Error::AddressMismatch));
+            }
+            let mut malleated = auth;
+            malleated.seal.0[64] -= 27;
+            assert_eq!(malleated.check_any(&domain,&verifier),Err(Error::BadRecoveryByte));
+            assert_eq!(malleated.check_any(&domain,&Verifier::default()).unwrap(),scheme);
+        }
+    }
+}
@@ -1,15 +1,18 @@
 use mimir_types::{Signature,Address,U256};
-use mimir_proto::seal::Sealer;
+use mimir_proto::seal::{Sealer,Domain,Tag};
 use mimir_crypto::secp256k1::{
     Verifier,
     Error,
 };
+use mimir_crypto::eip191::personal_hash;
+use mimir_crypto::eip712::StructHasher;
 use mimir_crypto::Keccak256;
 use mimir_util::unix_time;
 use common::{
     ParseError,
     Identity,
     Channel,
+    Scheme,
     Role,
     CMD,
 };
@@ -17,6 +20,10 @@ use std::str::FromStr;
 use std::fmt::{self,Write};
 
 
+/// `eip-712` encoded type of `Command` (absent data is encoded as the empty string).
+pub const COMMAND_TYPE: &str = "Command(string flag,string role,address addr,uint256 time,string data)";
+
+
 /// a command sent to be consumed by an edge node
 ///
 /// ```
@@ -40,6 +47,8 @@ use std::fmt::{self,Write};
 /// # }
 /// ```
 ///
+/// signed variants may also be sealed by wallets (see `Scheme`).
+///
 #[derive(Debug,Clone,PartialEq,Eq)]
 pub struct Command {
     /// flag indicating the command variant
@@ -61,12 +70,12 @@ pub struct Command {
 
 impl Command {
 
-    fn new_signed<S: Sealer>(flag: CMD, dest: Identity, data: Option<String>, sealer: S) -> Self {
+    fn new_signed<S: Sealer>(flag: CMD, dest: Identity, data: Option<String>, domain: &Domain, sealer: S, scheme: Scheme) -> Self {
         debug_assert!(flag.signed_variant(),"should only be called on signed variants");
         let time = unix_time().into();
-        let hash = hash_elems(flag,dest.role,dest.address,time,data.as_ref());
-        let seal = Some(sealer.sign(&hash));
-        Self { flag, dest, time, data, seal }
+        let mut cmd = Self { flag, dest, time, data, seal: None };
+        cmd.seal = Some(sealer.sign(&cmd.signing_hash(domain,scheme)));
+        cmd
     }
 
     fn new_unsigned(flag: CMD, dest: Identity, data: Option<String>) -> Self {
@@ -75,13 +84,17 @@ impl Command {
         Self { flag, dest, time, data, seal }
     }
 
-    pub fn identify<S: Sealer>(role: Role, sealer: S) -> Self {
+    pub fn identify<S: Sealer>(role: Role, domain: &Domain, sealer: S) -> Self {
+        Self::identify_with(role,domain,sealer,Scheme::Raw)
+    }
+
+    pub fn identify_with<S: Sealer>(role: Role, domain: &Domain, sealer: S, scheme: Scheme) -> Self {
         let dest = Identity::new(sealer.address(),role);
-        Self::new_signed(CMD::IDENTIFY,dest,None,sealer)
+        Self::new_signed(CMD::IDENTIFY,dest,None,domain,sealer,scheme)
     }
 
-    pub fn kick<S: Sealer>(dest: Identity, sealer: S) -> Self {
-        Self::new_signed(CMD::KICK,dest,None,sealer)
+    pub fn kick<S: Sealer>(dest: Identity, domain: &Domain, sealer: S) -> Self {
+        Self::new_signed(CMD::KICK,dest,None,domain,sealer,Scheme::Raw)
     }
 
     pub fn debug(dest: Identity, data: String) -> Self {
@@ -92,20 +105,56 @@ impl Command {
 
     pub fn dest_channel(&self) -> Channel { self.dest.direct_channel() }
 
-    pub fn recover(&self) -> Result<Option<Address>,Error> {
-        let verifier = Default::default();
-        self.recover_with(&verifier)
+    pub fn recover(&self, domain: &Domain) -> Result<Option<Address>,Error> {
+        let verifier = Verifier::strict();
+        self.recover_with(domain,&verifier)
     }
 
-    pub fn recover_with(&self, verifier: &Verifier) -> Result<Option<Address>,Error> {
+    pub fn recover_with(&self, domain: &Domain, verifier: &Verifier) -> Result<Option<Address>,Error> {
+        self.recover_scheme(domain,Scheme::Raw,verifier)
+    }
+
+    pub fn recover_scheme(&self, domain: &Domain, scheme: Scheme, verifier: &Verifier) -> Result<Option<Address>,Error> {
         if let Some(ref seal) = self.seal {
-            let hash = hash_elems(self.flag,self.dest.role,self.dest.address,self.time,self.data.as_ref());
-            let addr = verifier.ecrecover(&hash,seal)?;
+            let addr = verifier.ecrecover(&self.signing_hash(domain,scheme),seal)?;
             Ok(Some(addr))
         } else {
             Ok(None)
         }
     }
+
+    /// check that the seal was produced by the destination identity (as is
+    /// required of `IDENTIFY`) under any accepted scheme, returning the
+    /// scheme used (or `None` if no seal is present).
+    pub fn check_identity(&self, domain: &Domain, verifier: &Verifier) -> Result<Option<Scheme>,Error> {
+        if let Some(ref seal) = self.seal {
+            let scheme = Scheme::detect(&self.dest.address,seal,verifier,|scheme| self.signing_hash(domain,scheme))?;
+            Ok(Some(scheme))
+        } else {
+            Ok(None)
+        }
+    }
+
+    /// get the hash which is sealed under the specified scheme.
+    pub fn signing_hash(&self, domain: &Domain, scheme: Scheme) -> [u8;32] {
+        match scheme {
+            Scheme::Raw => {
+                let hash = hash_elems(self.flag,self.dest.role,self.dest.address,self.time,self.data.as_ref());
+                domain.hash(Tag::Command,&hash)
+            },
+            Scheme::Personal => personal_hash(&self.signing_hash(domain,Scheme::Raw)),
+            Scheme::Typed => {
+                let hash = StructHasher::new(COMMAND_TYPE)
+                    .string(self.flag.as_ref())
+                    .string(self.dest.role.as_ref())
+                    .address(&self.dest.address)
+                    .word(&self.time.0)
+                    .string(self.data.as_ref().map(|data| data.as_ref()).unwrap_or(""))
+                    .finish();
+                domain.typed().hash(&hash)
+            },
+        }
+    }
 }
 
 
@@ -181,3 +230,26 @@ fn hash_elems(flag: CMD, role: Role, addr: Address, time: U256, data: Option<&St
     hasher.finish()
 }
 
+
+
+#[cfg(test)]
+mod tests {
+    use mimir_crypto::secp256k1::{dev,Signer,Verifier,Error};
+    use common::{Command,Identity,Domain,Role,Scheme};
+
+    #[test]
+    fn wallet_schemes() {
+        let signer = Signer::new(dev::SECRET).unwrap();
+        let domain = Domain::new(1u64.into(),"0x000000000000000000000000000000000000beef".parse().unwrap());
+        let verifier = Verifier::strict();
+        for &scheme in Scheme::all().iter() {
+            let cmd = Command::identify_with(Role::Requester,&domain,&signer,scheme);
+            let parsed: Command = cmd.to_string().parse().unwrap();
+            assert_eq!(parsed.check_identity(&domain,&verifier).unwrap(),Some(scheme));
+            assert_eq!(parsed.recover_scheme(&domain,scheme,&verifier).unwrap(),Some(signer.address()));
+            assert_eq!(parsed.check_identity(&Domain::legacy(),&verifier),Err(Error::AddressMismatch));
+        }
+        let debug = Command::debug(Identity::new(dev::ADDRESS,Role::Requester),"hello".into());
+        assert_eq!(debug.check_identity(&domain,&verifier).unwrap(),None);
+    }
+}
@@ -17,6 +17,11 @@ pub use self::message::Message;
 pub use self::command::Command;
 pub use self::channel::Channel;
 pub use self::auth::Auth;
+pub use mimir_proto::seal::Domain;
+pub use mimir_proto::message::{Role,CircuitSpec};
+
+use mimir_crypto::secp256k1::{Verifier,Error};
+use mimir_types::{Signature,Address};
 
 
 simple_error!(
@@ -89,16 +94,34 @@ impl CMD {
 
 
 simple_unit!(
-    Role, "basic client roles", 
-    Oracle    => "oracle",
-    Notary    => "notary",
-    Requester => "requester",
-    Router    => "router", 
-    Verifier  => "verifier",
-    Admin     => "admin",
+    Scheme, "signature schemes accepted for `Auth` & `Command` seals",
+    Raw      => "raw",
+    Personal => "personal",
+    Typed    => "typed"
 );
 
 
+impl Scheme {
+
+    /// get all schemes (in order of preference)
+    pub fn all() -> [Scheme;3] { [Scheme::Raw,Scheme::Personal,Scheme::Typed] }
+
+    /// find the scheme under which `seal` recovers to the `expected` address,
+    /// given a function producing the signed hash under each scheme.
+    pub(crate) fn detect<F>(expected: &Address, seal: &Signature, verifier: &Verifier, hash: F) -> Result<Self,Error> where F: Fn(Scheme) -> [u8;32] {
+        let mut error = Error::AddressMismatch;
+        for &scheme in Self::all().iter() {
+            match verifier.ecrecover(&hash(scheme),seal) {
+                Ok(ref address) if address == expected => return Ok(scheme),
+                Ok(_) => { },
+                Err(err) => { error = err; },
+            }
+        }
+        Err(error)
+    }
+}
+
+
 /*
 #[derive(Debug,Clone,PartialEq,Eq,PartialOrd,Ord,Serialize,Deserialize)]
 #[serde(tag = "op", content = "msg")]
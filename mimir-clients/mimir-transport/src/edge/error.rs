@@ -1,6 +1,7 @@
 use redis_async::error::Error as RedisError;
 use tokio_timer::Error as TimerError;
 use websocket::WebSocketError;
+use mimir_proto::visit::StructError;
 use common::ParseError;
 use std::{fmt,error};
 
@@ -21,6 +22,9 @@ pub enum Error {
     /// error during message parsing
     Parsing(ParseError),
 
+    /// structurally invalid message payload
+    Structure(StructError),
+
     /// generic error variant
     Other(&'static str)
 }
@@ -34,6 +38,7 @@ impl fmt::Display for Error {
             Error::Redis(ref err) => err.fmt(f),
             Error::WebSocket(ref err) => err.fmt(f),
             Error::Parsing(ref err) => err.fmt(f),
+            Error::Structure(ref err) => err.fmt(f),
             Error::Other(ref msg) => f.write_str(msg),
         }
     }
@@ -48,6 +53,7 @@ impl error::Error for Error {
             Error::Redis(ref err) => err.description(),
             Error::WebSocket(ref err) => err.description(),
             Error::Parsing(ref err) => err.description(),
+            Error::Structure(ref err) => err.description(),
             Error::Other(ref msg) => msg
         }
     }
@@ -58,6 +64,7 @@ impl error::Error for Error {
             Error::Redis(ref err) => Some(err),
             Error::WebSocket(ref err) => Some(err),
             Error::Parsing(ref err) => Some(err),
+            Error::Structure(ref err) => Some(err),
             Error::Other(_) => None
         }
     }
@@ -84,6 +91,11 @@ impl From<ParseError> for Error {
     fn from(err: ParseError) -> Self { Error::Parsing(err) }
 }
 
+impl From<StructError> for Error {
+
+    fn from(err: StructError) -> Self { Error::Structure(err) }
+}
+
 impl From<&'static str> for Error {
 
     fn from(msg: &'static str) -> Self { Error::Other(msg) }
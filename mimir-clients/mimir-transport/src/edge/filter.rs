@@ -1,11 +1,18 @@
+use mimir_proto::message::{self,CircuitSpec};
+use mimir_proto::visit::{self,EmptyVisitor};
+use mimir_proto::judge::JudgeVisitor;
 use edge::Error;
+use serde_json;
+use std::sync::Arc;
 use common::{
     Operation,
     Abilities,
     Identity,
     Message,
     Command, 
+    Domain,
     CMD,
+    MSG,
 };
 
 
@@ -16,6 +23,14 @@ pub struct OperationFilter {
 
     /// identity of connected entity
     identity: Identity,
+
+    /// circuit spec for structural validation of
+    /// message payloads (if enabled).
+    spec: Option<Arc<CircuitSpec>>,
+
+    /// signing domain under which message payloads
+    /// are judged for logging (if enabled).
+    domain: Option<Domain>,
 }
 
 
@@ -24,9 +39,17 @@ impl OperationFilter {
     /// initialize new connection filter
     pub fn new(identity: Identity) -> Self {
         let abilities = Abilities::new(identity.role);
-        Self { abilities, identity }
+        let (spec,domain) = (None,None);
+        Self { abilities, identity, spec, domain }
     }
 
+    /// enable strict structural validation of incoming message payloads.
+    pub fn with_spec(mut self, spec: Arc<CircuitSpec>) -> Self { self.spec = Some(spec); self }
+
+    /// enable logging of failed certs in incoming message payloads
+    /// (requires structural validation, see `with_spec`).
+    pub fn with_domain(mut self, domain: Domain) -> Self { self.domain = Some(domain); self }
+
 
     /// filter incoming (client -> redis) operation. 
     pub fn filter_incoming(&self, op: Operation) -> Result<Option<Operation>,Error> {
@@ -45,6 +68,7 @@ impl OperationFilter {
     fn filter_incoming_msg(&self, msg: Message) -> Result<Option<Message>,Error> {
         if self.abilities.can_produce(msg.msg_variant()) {
             if &self.identity.address == msg.source_address() {
+                self.check_structure(&msg)?;
                 Ok(Some(msg))
             } else {
                 Err("invalid source address".into())
@@ -54,6 +78,37 @@ impl OperationFilter {
         }
     }
 
+    /// check structure of message payload if strict validation is enabled.
+    /// query payloads are requests rather than protocol messages, and are skipped.
+    fn check_structure(&self, msg: &Message) -> Result<(),Error> {
+        if let Some(ref spec) = self.spec {
+            if msg.msg_variant() != MSG::QUERY {
+                let payload: message::Message = serde_json::from_str(msg.msg_payload())
+                    .map_err(|_| "malformed message payload")?;
+                visit::validate(spec,&payload)?;
+                if let Some(domain) = self.domain {
+                    self.log_verdict(spec,domain,&payload);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// judge message payload & log any failed certs.  no block state is
+    /// held at the edge, so only signatures & routing are checked, and
+    /// failures are left to the notary to reject.
+    fn log_verdict(&self, spec: &CircuitSpec, domain: Domain, payload: &message::Message) {
+        let mut judge = JudgeVisitor::with_spec(EmptyVisitor,spec,domain);
+        visit::apply(spec,&mut judge,payload);
+        let verdict = judge.finish();
+        for err in verdict.payload.iter() {
+            warn!("payload from {}: {}",self.identity,err);
+        }
+        for step in verdict.failures() {
+            warn!("message from {}: {}",self.identity,step);
+        }
+    }
+
     fn filter_incoming_cmd(&self, cmd: Command) -> Result<Option<Command>,Error> {
         if self.abilities.can_execute(cmd.cmd_variant()) {
             Ok(Some(cmd))
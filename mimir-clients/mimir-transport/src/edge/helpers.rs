@@ -4,9 +4,12 @@ use redis::{self,NonBlockHandle,BlockingHandle};
 use websocket::stream::async::Stream as WsStream;
 use websocket::client::async::Client;
 use websocket::OwnedMessage;
+use mimir_crypto::secp256k1::{Verifier,Error as CryptoError};
 use common::{
     Operation,
     Identity,
+    CircuitSpec,
+    Domain,
     CMD,
 };
 use edge::{
@@ -16,6 +19,7 @@ use edge::{
     Error,
 };
 use std::net::SocketAddr;
+use std::sync::Arc;
 
 
 // TODO: convert the various trait objects below into concrete types.
@@ -40,14 +44,17 @@ impl<T> Receiver for T where T: Stream<Item=Operation,Error=Error> { }
 
 /// attempt to serve a client connection
 ///
-pub fn serve_connection<A,S,E>(auth_server: Policy<A>, client: Client<S>, redis_addr: SocketAddr, executor: E) -> impl Future<Item=(),Error=Error>
+/// the `IDENTIFY` handshake must be signed under the supplied domain,
+/// and incoming message payloads must be well formed under `spec`.
+///
+pub fn serve_connection<A,S,E>(auth_server: Policy<A>, domain: Domain, spec: Arc<CircuitSpec>, client: Client<S>, redis_addr: SocketAddr, executor: E) -> impl Future<Item=(),Error=Error>
         where A: AuthServer<Error=Error> + 'static, 
               S: WsStream + 'static, 
               E: Executor<Box<Future<Item=(),Error=()> + Send>> + Clone + 'static {
-    let work = init_server_side_client(client).and_then(move |(ident,client_tx,client_rx)| {
+    let work = init_server_side_client(client,domain).and_then(move |(ident,client_tx,client_rx)| {
             let conn_work = init_redis(redis_addr,executor,ident)
                 .and_then(move |(redis_tx,redis_rx)| {
-                    build_connection((client_tx,client_rx),(redis_tx,redis_rx),ident)
+                    build_connection((client_tx,client_rx),(redis_tx,redis_rx),ident,spec,domain)
                 });
             auth_server.while_authorized(ident,conn_work)
         });
@@ -57,10 +64,10 @@ pub fn serve_connection<A,S,E>(auth_server: Policy<A>, client: Client<S>, redis_
 
 /// configure websocket client handles & execute `IDENTIFY` handshake
 ///
-pub fn init_server_side_client<S>(client: Client<S>) -> impl Future<Item=(Identity,impl Sender, impl Receiver),Error=Error>
+pub fn init_server_side_client<S>(client: Client<S>, domain: Domain) -> impl Future<Item=(Identity,impl Sender, impl Receiver),Error=Error>
         where S: WsStream + 'static {
     let (sender,receiver) = split_client(client);
-    let work = server_side_handshake(receiver).map(move |(ident,receiver)| {
+    let work = server_side_handshake(receiver,domain).map(move |(ident,receiver)| {
         (ident,sender,receiver)
     });
     work
@@ -84,8 +91,11 @@ pub fn init_redis<E>(address: SocketAddr, executor: E, ident: Identity) -> impl
 
 /// build top-level connection future
 ///
-pub fn build_connection(client_handles: (impl Sender,impl Receiver), redis_handles: (impl Sender,impl Receiver), ident: Identity) -> impl Future<Item=(),Error=Error> {
-    let filter_a = OperationFilter::new(ident);
+/// incoming message payloads are structurally validated against `spec`,
+/// and any certs which fail judgement under `domain` are logged.
+///
+pub fn build_connection(client_handles: (impl Sender,impl Receiver), redis_handles: (impl Sender,impl Receiver), ident: Identity, spec: Arc<CircuitSpec>, domain: Domain) -> impl Future<Item=(),Error=Error> {
+    let filter_a = OperationFilter::new(ident).with_spec(spec).with_domain(domain);
     let filter_b = filter_a.clone();
     let (client_tx,client_rx) = client_handles;
     let (redis_tx,redis_rx) = redis_handles;
@@ -140,13 +150,13 @@ pub fn split_client<S>(client: Client<S>) -> (impl Sender,impl Receiver)
 
 
 // TODO: add handshake timeout...
-fn server_side_handshake<S>(client_stream: S) -> impl Future<Item=(Identity,S),Error=Error>
+fn server_side_handshake<S>(client_stream: S, domain: Domain) -> impl Future<Item=(Identity,S),Error=Error>
         where S: Stream<Item=Operation,Error=Error> + 'static {
     let work = client_stream.into_future()
         .map_err(|(error,_)|error)
-        .and_then(|(item,stream)| {
+        .and_then(move |(item,stream)| {
             if let Some(op) = item {
-                process_handshake(op)
+                process_handshake(op,&domain)
                     .map(move |ident|(ident,stream))
             } else {
                 Err("stream terminated prior to handshake".into())
@@ -156,17 +166,69 @@ fn server_side_handshake<S>(client_stream: S) -> impl Future<Item=(Identity,S),E
 }
 
 
-fn process_handshake(op: Operation) -> Result<Identity,Error> {
+fn process_handshake(op: Operation, domain: &Domain) -> Result<Identity,Error> {
     let cmd = op.expect_command(CMD::IDENTIFY)
         .map_err(|_|"handshake variant must be `CMD::IDENTIFY`")?;
-    if let Some(address) = cmd.recover().map_err(|_|"unrecoverable signature")? {
-        if cmd.dest.address == address {
+    let verifier = Verifier::strict();
+    match cmd.check_identity(domain,&verifier) {
+        Ok(Some(scheme)) => {
+            debug!("handshake from {} sealed via {} scheme",cmd.dest,scheme);
             Ok(cmd.dest)
-        } else {
-            Err("signer address does not match claim".into())
-        }
-    } else {
-        Err("missing signature in handshake".into())
+        },
+        Ok(None) => Err("missing signature in handshake".into()),
+        Err(CryptoError::AddressMismatch) => Err("signer address does not match claim".into()),
+        Err(_) => Err("unrecoverable signature".into()),
     }
 }
 
+
+
+#[cfg(test)]
+mod tests {
+    use futures::{stream,Future,Stream,Sink};
+    use futures::sync::mpsc;
+    use mimir_proto::message::{self,Payload};
+    use mimir_types::Address;
+    use common::{Operation,Identity,Message,CircuitSpec,Domain,Role};
+    use edge::helpers::build_connection;
+    use edge::Error;
+    use serde_json;
+    use std::sync::Arc;
+
+    /// build a `NOTARIZE` operation from an oracle w/ the supplied payload.
+    fn notarize(ident: &Identity, payload: &message::Message) -> Operation {
+        let text = format!("NOTARIZE {} {}",ident.address,serde_json::to_string(payload).unwrap());
+        Message::from_string(text).unwrap().into()
+    }
+
+    #[test]
+    fn structure_checked() {
+        let ident = Identity::new(Address([1;20]),Role::Oracle);
+        let mut payload = message::Message::new(Payload::default());
+        payload.blind.push(Default::default());
+        payload.verify.push(Default::default());
+        let connect = |op: Operation| {
+            let (client_tx,_client) = mpsc::unbounded();
+            let (redis_tx,redis) = mpsc::unbounded();
+            let client_rx = stream::iter_ok::<_,Error>(vec![op]);
+            let client_tx = client_tx.sink_map_err(|_| Error::Other("client closed"));
+            let redis_tx = redis_tx.sink_map_err(|_| Error::Other("redis closed"));
+            // redis side remains open for the duration of the connection.
+            let (_hold,redis_rx) = mpsc::unbounded::<Operation>();
+            let redis_rx = redis_rx.map_err(|_| Error::Other("redis closed"));
+            let result = build_connection((client_tx,client_rx),(redis_tx,redis_rx),ident,Arc::new(CircuitSpec::default()),Domain::legacy()).wait();
+            (result,redis)
+        };
+        let (result,redis) = connect(notarize(&ident,&payload));
+        assert!(result.is_ok());
+        assert_eq!(redis.wait().count(),1);
+        // connections are terminated upon receipt of leftover certs.
+        payload.verify.push(Default::default());
+        let (result,redis) = connect(notarize(&ident,&payload));
+        match result {
+            Err(Error::Structure(_)) => { },
+            other => panic!("unexpected result: {:?}",other),
+        }
+        assert_eq!(redis.wait().count(),0);
+    }
+}
@@ -13,6 +13,7 @@ extern crate tokio_core;
 #[macro_use]
 extern crate serde_derive;
 extern crate serde;
+extern crate serde_json;
 #[macro_use]
 extern crate log;
 
@@ -12,6 +12,7 @@ extern crate log;
 use tokio_timer::Deadline;
 use std::time::{Duration,Instant};
 use mimir_transport::edge;
+use mimir_transport::common::Domain;
 use mimir_worker::{common,faucet};
 use tokio_core::reactor::Core;
 use futures::future::{self,Either};
@@ -30,11 +31,15 @@ fn main() {
         common::KeyStore::dev_account()
             .sealer().unwrap()
     } else {
-        common::KeyStore::init(&opt.keys)
+        let passphrase = common::Passphrase::select(opt.passphrase_file.as_ref());
+        common::KeyStore::init(&opt.keys,&passphrase)
             .expect("unable to parse keystore")
             .sealer().unwrap()
     };
 
+    let domain = Domain::init(&opt.signing_domain)
+        .expect("unable to parse signing domain");
+
     info!("initializing faucet::{:#}...",sealer.address());
 
     let mut core = Core::new().unwrap();
@@ -58,7 +63,7 @@ fn main() {
         .and_then(|(client,address)| {
             info!("incoming connection from {}",address);
             Deadline::new(
-                edge::init_server_side_client(client),
+                edge::init_server_side_client(client,domain),
                 Instant::now() + max_handshake
                 ).map_err(|e| error!("in handshake {:?}",e))
         })
@@ -0,0 +1,31 @@
+extern crate mimir_worker;
+#[macro_use]
+extern crate serde_json;
+
+use mimir_worker::keygen::{
+    Options,
+    Output,
+};
+use std::process;
+
+
+
+fn main() {
+
+    let opt = Options::from_args();
+
+    match mimir_worker::keygen::run(&opt.cmd) {
+        Ok(output) => {
+            let json = serde_json::to_string_pretty(&output)
+                .expect("always valid json");
+            println!("{}",json);
+            if let Output::Verified(ref verified) = output {
+                if !verified.valid { process::exit(1); }
+            }
+        },
+        Err(error) => {
+            eprintln!("{}",json!({ "error": error.to_string() }));
+            process::exit(1);
+        },
+    }
+}
@@ -1,5 +1,6 @@
 extern crate mimir_transport;
 extern crate mimir_worker;
+extern crate mimir_proto;
 extern crate futures;
 extern crate tokio_core;
 extern crate serde_json;
@@ -11,7 +12,8 @@ use futures::{Future,Stream,Sink};
 use tokio_core::reactor::Core;
 use log::LevelFilter;
 
-use mimir_worker::common::KeyStore;
+use mimir_worker::common::{KeyStore,Passphrase};
+use mimir_proto::message::CircuitSpec;
 use mimir_worker::notary::{
     Options,
     Notary,
@@ -20,6 +22,7 @@ use mimir_transport::{ws,edge};
 use mimir_transport::common::{
     Message,
     Command,
+    Domain,
     Role,
     MSG,
 };
@@ -32,17 +35,20 @@ fn main() {
 
     init_logger(opt.log_level);
 
-    let sealer = KeyStore::init(&opt.keys)
+    let passphrase = Passphrase::select(opt.passphrase_file.as_ref());
+    let sealer = KeyStore::init(&opt.keys,&passphrase)
         .unwrap().sealer().unwrap();
+    let spec = CircuitSpec::init(&opt.circuit_spec).unwrap();
+    let domain = Domain::init(&opt.signing_domain).unwrap();
 
     info!("notary::{:#} starting...",sealer.address());
     debug!("using options {:?}",opt);
 
     let mut core = Core::new().unwrap();
     let handle = core.handle(); 
-    let notary = Notary::new(sealer);
+    let notary = Notary::new(sealer).with_spec(spec).with_domain(domain);
 
-    let identify = Command::identify(Role::Notary,notary.sealer());
+    let identify = Command::identify(Role::Notary,notary.domain(),notary.sealer());
 
     let connect = ws::client::connect(&handle,&opt.bridge_portal,identify.to_string());
 
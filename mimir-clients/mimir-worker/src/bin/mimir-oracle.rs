@@ -15,7 +15,7 @@ use tokio_timer::{Delay,Interval};
 use std::time::{Instant,Duration};
 use log::LevelFilter;
 
-use mimir_worker::common::KeyStore;
+use mimir_worker::common::{KeyStore,Passphrase};
 use mimir_transport::{ws,edge};
 use mimir_transport::common::{
     Message,
@@ -38,7 +38,8 @@ fn main() {
     
     // load configuration & key files
     let conf = Config::init(&opt.config).unwrap();
-    let sealer = KeyStore::init(&opt.keys)
+    let passphrase = Passphrase::select(opt.passphrase_file.as_ref());
+    let sealer = KeyStore::init(&opt.keys,&passphrase)
         .unwrap().sealer().unwrap();
 
     info!("oracle::{:#} starting...",sealer.address());
@@ -51,7 +52,9 @@ fn main() {
    
     // set up basic oracle client handle.
     let node = mimir_node::node::ws(conf.websocket_rpc.as_ref(),&handle).unwrap();
-    let oracle = SimpleOracle::new(sealer,node);
+    let oracle = SimpleOracle::new(sealer,node)
+        .with_spec(conf.circuit_spec.clone())
+        .with_domain(conf.signing_domain);
 
 
     if !opt.skip_all {
@@ -79,7 +82,7 @@ fn main() {
 
         if opt.auto_fund {
 
-            let identify = Command::identify(Role::Oracle,oracle.sealer());
+            let identify = Command::identify(Role::Oracle,oracle.domain(),oracle.sealer());
 
             let fund_work = ws::client::connect(&handle,&conf.fund_portal,identify.to_string())
                 .and_then(|client| client.flush());
@@ -126,7 +129,7 @@ fn main() {
     }
 
 
-    let identify = Command::identify(Role::Oracle,oracle.sealer());
+    let identify = Command::identify(Role::Oracle,oracle.domain(),oracle.sealer());
 
     let connect = ws::client::connect(&handle,&conf.bridge_portal,identify.to_string());
 
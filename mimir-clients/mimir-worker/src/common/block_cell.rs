@@ -0,0 +1,69 @@
+use mimir_proto::visit::{BlockState,BlockRegistry};
+use crossbeam::sync::ArcCell;
+use std::sync::{Arc,Mutex};
+
+
+/// shared registry of recent block states.
+///
+/// reads are lock-free, while writers are serialized so that
+/// concurrent updates cannot overwrite one another.
+#[derive(Debug)]
+pub struct BlockCell {
+    registry: ArcCell<BlockRegistry>,
+    writer: Mutex<()>,
+}
+
+
+impl Default for BlockCell {
+
+    fn default() -> Self { Self::new(Default::default()) }
+}
+
+
+impl BlockCell {
+
+    /// wrap the specified registry.
+    pub fn new(registry: BlockRegistry) -> Self {
+        let (registry,writer) = (ArcCell::new(Arc::new(registry)),Mutex::new(()));
+        BlockCell { registry, writer }
+    }
+
+    /// get handle to the current registry.
+    pub fn get(&self) -> Arc<BlockRegistry> { self.registry.get() }
+
+    /// get handle to the latest block state.
+    pub fn latest(&self) -> Arc<BlockState> {
+        self.registry.get().latest().cloned().unwrap_or_default()
+    }
+
+    /// insert the state of a block into the registry.
+    pub fn insert<B>(&self, block: B) where B: Into<Arc<BlockState>> {
+        let block = block.into();
+        debug!("setting new block state {:?}",block);
+        let _guard = self.writer.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        let mut registry = (*self.registry.get()).clone();
+        registry.insert(block);
+        let _ = self.registry.set(Arc::new(registry));
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use mimir_proto::visit::BlockState;
+    use common::BlockCell;
+    use std::sync::Arc;
+    use std::thread;
+
+    #[test]
+    fn concurrent_insert() {
+        let cell = Arc::new(BlockCell::default());
+        let handles: Vec<_> = (0..8u64).map(|number| {
+            let cell = cell.clone();
+            thread::spawn(move || cell.insert(BlockState::new().number(number.into())))
+        }).collect();
+        for handle in handles { handle.join().unwrap(); }
+        assert_eq!(cell.get().len(),8);
+        assert_eq!(cell.latest().number,Some(7u64.into()));
+    }
+}
@@ -1,16 +1,27 @@
 use mimir_util::toml::{self,TomlError};
 use mimir_types::{Address,Secret};
 use mimir_crypto::secp256k1::{dev,Signer,Error};
+use mimir_crypto::keystore::{Error as KeyFileError,KeyFile,Kdf};
 use common::ArcSealer;
-use std::path::Path;
+use std::path::{Path,PathBuf};
+use std::io::{self,Read};
+use std::fs::File;
 use std::sync::Arc;
+use std::{env,fmt,error};
+use rpassword;
 use rand;
 
 
-/// serialization/deserialization target for worker key storage.
+/// environment variable from which keystore passphrases are read.
+pub const PASSPHRASE_VAR: &str = "MIMIR_KEYSTORE_PASSPHRASE";
+
+
+/// worker keys.
 ///
-/// WARNING: this is an **unencrypted** keystore.  it is intended for
-/// use in alpha clients working on testnets.  ye be warned.
+/// keys are persisted as encrypted ethereum v3 json keyfiles (see
+/// `mimir_crypto::keystore`).  legacy **unencrypted** toml keystores
+/// are still accepted by `load`, and may be converted to/from keyfiles
+/// via `import` & `export`.
 ///
 #[derive(Default,Debug,Clone,Hash,PartialEq,Eq,Serialize,Deserialize)]
 pub struct KeyStore {
@@ -33,19 +44,351 @@ impl KeyStore {
         Ok(Arc::new(inner))
     }
 
-    pub fn init<P>(path: P) -> Result<Self,TomlError> where P: AsRef<Path> {
-        if path.as_ref().is_file() {
-            Self::load(path)
+    /// load the keystore at `path`.  if no such keystore exists, a legacy toml
+    /// keystore of the same name is imported, or a new key is generated.
+    pub fn init<P>(path: P, passphrase: &Passphrase) -> Result<Self,KeyStoreError> where P: AsRef<Path> {
+        let path = path.as_ref();
+        let legacy = path.with_extension("toml");
+        if path.is_file() {
+            Self::load(path,passphrase)
+        } else if legacy != path && legacy.is_file() {
+            warn!("importing unencrypted keystore {:?} (remove it once {:?} is backed up)",legacy,path);
+            Self::import(legacy,path,passphrase,false)
         } else {
             let signer: Signer = rand::random();
             let (address,secret) = (signer.address(),signer.secret());
             let keystore = KeyStore { address, secret };
-            keystore.save(path)?;
+            keystore.save(path,passphrase)?;
             Ok(keystore)
         }
     }
 
-    pub fn load<P>(path: P) -> Result<Self,TomlError> where P: AsRef<Path> { toml::load(path) }
+    /// load a json keyfile (or a legacy toml keystore).
+    pub fn load<P>(path: P, passphrase: &Passphrase) -> Result<Self,KeyStoreError> where P: AsRef<Path> {
+        match KeyFile::load(path.as_ref()) {
+            Ok(keyfile) => {
+                let passphrase = passphrase.read(false)?;
+                let secret = keyfile.decrypt(passphrase.as_ref())?;
+                let address = Signer::new(&secret)?.address();
+                Ok(KeyStore { address, secret })
+            },
+            Err(KeyFileError::Deserialize) => {
+                warn!("loading unencrypted keystore {:?}...",path.as_ref());
+                Ok(toml::load(path)?)
+            },
+            Err(err) => Err(err.into()),
+        }
+    }
+
+    /// save as a json keyfile, encrypted under the supplied passphrase.
+    pub fn save<P>(&self, path: P, passphrase: &Passphrase) -> Result<(),KeyStoreError> where P: AsRef<Path> {
+        let passphrase = passphrase.read(true)?;
+        let keyfile = KeyFile::encrypt(&self.secret,passphrase.as_ref(),kdf())?;
+        keyfile.save(path)?;
+        Ok(())
+    }
+
+    /// convert a legacy toml keystore into a json keyfile (an existing
+    /// `dst` is only overwritten if `force` is set).
+    pub fn import<S,D>(src: S, dst: D, passphrase: &Passphrase, force: bool) -> Result<Self,KeyStoreError> where S: AsRef<Path>, D: AsRef<Path> {
+        check_dst(src.as_ref(),dst.as_ref(),force)?;
+        let keystore: Self = toml::load(src)?;
+        keystore.save(dst,passphrase)?;
+        Ok(keystore)
+    }
+
+    /// convert a json keyfile into an unencrypted toml keystore (only
+    /// accessible to its owner).  an existing `dst` is only overwritten
+    /// if `force` is set.
+    pub fn export<S,D>(src: S, dst: D, passphrase: &Passphrase, force: bool) -> Result<Self,KeyStoreError> where S: AsRef<Path>, D: AsRef<Path> {
+        check_dst(src.as_ref(),dst.as_ref(),force)?;
+        let keystore = Self::load(src,passphrase)?;
+        toml::save_private(&keystore,dst)?;
+        Ok(keystore)
+    }
+}
+
+
+/// check that a conversion from `src` would not clobber `dst`; a
+/// conversion in place is never permitted.
+fn check_dst(src: &Path, dst: &Path, force: bool) -> Result<(),KeyStoreError> {
+    if !dst.exists() {
+        return Ok(());
+    }
+    let same = match (src.canonicalize(),dst.canonicalize()) {
+        (Ok(src),Ok(dst)) => src == dst,
+        _ => src == dst,
+    };
+    if same {
+        Err(KeyStoreError::SameFile)
+    } else if !force {
+        Err(KeyStoreError::Exists)
+    } else {
+        Ok(())
+    }
+}
+
+
+/// key derivation function of newly saved keyfiles.
+#[cfg(not(test))]
+fn kdf() -> Kdf { Kdf::scrypt() }
+
+
+/// cheap key derivation function, s.t. tests needn't run full scrypt.
+#[cfg(test)]
+fn kdf() -> Kdf {
+    match Kdf::scrypt() {
+        Kdf::Scrypt { dklen, r, p, salt, .. } => Kdf::Scrypt { dklen, n: 1 << 10, r, p, salt },
+        other => other,
+    }
+}
+
+
+/// source of a keystore passphrase.
+#[derive(Debug,Clone,PartialEq,Eq)]
+pub enum Passphrase {
+    /// read from the specified environment variable.
+    Env(String),
+    /// read from the first line of the specified file.
+    File(PathBuf),
+    /// prompt for passphrase via the terminal.
+    Prompt,
+}
+
+
+impl Passphrase {
+
+    /// select a passphrase source: the supplied file if any, else
+    /// the `PASSPHRASE_VAR` environment variable if it is set, else
+    /// an interactive prompt.
+    pub fn select(file: Option<&PathBuf>) -> Self {
+        if let Some(path) = file {
+            Passphrase::File(path.to_owned())
+        } else if env::var_os(PASSPHRASE_VAR).is_some() {
+            Passphrase::Env(PASSPHRASE_VAR.to_owned())
+        } else {
+            Passphrase::Prompt
+        }
+    }
+
+    /// read passphrase from source (prompts ask for confirmation
+    /// if `confirm` is set, as when encrypting a new keyfile).
+    pub fn read(&self, confirm: bool) -> Result<String,io::Error> {
+        match *self {
+            Passphrase::Env(ref var) => env::var(var)
+                .map_err(|err| io::Error::new(io::ErrorKind::NotFound,err)),
+            Passphrase::File(ref path) => {
+                let mut buff = String::new();
+                File::open(path)?.read_to_string(&mut buff)?;
+                Ok(buff.lines().next().unwrap_or("").to_owned())
+            },
+            Passphrase::Prompt => {
+                let passphrase = rpassword::prompt_password_stderr("keystore passphrase: ")?;
+                if confirm && passphrase != rpassword::prompt_password_stderr("confirm passphrase: ")? {
+                    Err(io::Error::new(io::ErrorKind::InvalidInput,"passphrases do not match"))
+                } else {
+                    Ok(passphrase)
+                }
+            },
+        }
+    }
+}
+
+
+#[derive(Debug)]
+pub enum KeyStoreError {
+    Toml(TomlError),
+    KeyFile(KeyFileError),
+    Key(Error),
+    Passphrase(io::Error),
+    Exists,
+    SameFile,
+}
+
+
+impl From<TomlError> for KeyStoreError {
+
+    fn from(err: TomlError) -> Self { KeyStoreError::Toml(err) }
+}
+
+
+impl From<KeyFileError> for KeyStoreError {
+
+    fn from(err: KeyFileError) -> Self { KeyStoreError::KeyFile(err) }
+}
+
+
+impl From<Error> for KeyStoreError {
+
+    fn from(err: Error) -> Self { KeyStoreError::Key(err) }
+}
+
+
+impl From<io::Error> for KeyStoreError {
+
+    fn from(err: io::Error) -> Self { KeyStoreError::Passphrase(err) }
+}
+
+
+impl fmt::Display for KeyStoreError {
+
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            KeyStoreError::Toml(ref err) => err.fmt(f),
+            KeyStoreError::KeyFile(ref err) => err.fmt(f),
+            KeyStoreError::Key(ref err) => err.fmt(f),
+            KeyStoreError::Passphrase(ref err) => err.fmt(f),
+            KeyStoreError::Exists | KeyStoreError::SameFile => f.write_str(error::Error::description(self)),
+        }
+    }
+}
+
+
+impl error::Error for KeyStoreError {
 
-    pub fn save<P>(&self, path: P) -> Result<(),TomlError> where P: AsRef<Path> { toml::save(self,path) }
+    fn description(&self) -> &str {
+        match *self {
+            KeyStoreError::Toml(ref err) => err.description(),
+            KeyStoreError::KeyFile(ref err) => err.description(),
+            KeyStoreError::Key(ref err) => err.description(),
+            KeyStoreError::Passphrase(ref err) => err.description(),
+            KeyStoreError::Exists => "destination exists (use `--force` to overwrite)",
+            KeyStoreError::SameFile => "source and destination are the same file",
+        }
+    }
+
+    fn cause(&self) -> Option<&error::Error> {
+        match *self {
+            KeyStoreError::Toml(ref err) => Some(err),
+            KeyStoreError::KeyFile(ref err) => Some(err),
+            KeyStoreError::Key(ref err) => Some(err),
+            KeyStoreError::Passphrase(ref err) => Some(err),
+            KeyStoreError::Exists | KeyStoreError::SameFile => None,
+        }
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use common::{KeyStore,KeyStoreError,Passphrase};
+    use mimir_crypto::keystore::{Error as KeyFileError,KeyFile};
+    use mimir_util::toml;
+    use std::path::PathBuf;
+    use std::{env,fs,process};
+
+    /// get an empty scratch directory which is unique to the test.
+    fn scratch(name: &str) -> PathBuf {
+        let dir = env::temp_dir().join(format!("mimir-keystore-{}-{}",name,process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    /// write a passphrase file, returning its source.
+    fn passphrase(dir: &PathBuf, contents: &str) -> Passphrase {
+        let path = dir.join("passphrase");
+        fs::write(&path,contents).unwrap();
+        Passphrase::File(path)
+    }
+
+    #[cfg(unix)]
+    fn is_private(path: &PathBuf) -> bool {
+        use std::os::unix::fs::PermissionsExt;
+        fs::metadata(path).unwrap().permissions().mode() & 0o777 == 0o600
+    }
+
+    #[cfg(not(unix))]
+    fn is_private(_path: &PathBuf) -> bool { true }
+
+    #[test]
+    fn passphrase_file() {
+        let dir = scratch("passphrase");
+        // only the first line of the file is used.
+        let source = passphrase(&dir,"hunter2\nignored\n");
+        assert_eq!(source.read(true).unwrap(),"hunter2");
+        assert_eq!(passphrase(&dir,"").read(false).unwrap(),"");
+        assert!(Passphrase::File(dir.join("missing")).read(false).is_err());
+        assert_eq!(Passphrase::select(Some(&dir.join("missing"))),Passphrase::File(dir.join("missing")));
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn init_and_load() {
+        let dir = scratch("init");
+        let (path,source) = (dir.join("keys.json"),passphrase(&dir,"hunter2\n"));
+        let keystore = KeyStore::init(&path,&source).unwrap();
+        assert!(KeyFile::load(&path).is_ok() && is_private(&path));
+        assert_eq!(KeyStore::init(&path,&source).unwrap(),keystore);
+        assert_eq!(KeyStore::load(&path,&source).unwrap(),keystore);
+        match KeyStore::load(&path,&passphrase(&dir,"hunter3")) {
+            Err(KeyStoreError::KeyFile(KeyFileError::MacMismatch)) => { },
+            other => panic!("unexpected result: {:?}",other),
+        }
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn legacy_toml() {
+        let dir = scratch("legacy");
+        let source = passphrase(&dir,"hunter2");
+        let legacy = KeyStore::init(dir.join("other.json"),&source).unwrap();
+        // unencrypted keystores are loaded as-is, irrespective of extension.
+        let path = dir.join("keys.json");
+        toml::save(&legacy,&path).unwrap();
+        assert_eq!(KeyStore::load(&path,&source).unwrap(),legacy);
+        // a toml keystore of the same name is imported by `init`.
+        fs::remove_file(&path).unwrap();
+        toml::save(&legacy,dir.join("keys.toml")).unwrap();
+        assert_eq!(KeyStore::init(&path,&source).unwrap(),legacy);
+        assert!(KeyFile::load(&path).is_ok() && is_private(&path));
+        assert_eq!(KeyStore::load(&path,&source).unwrap(),legacy);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn import_export() {
+        let dir = scratch("convert");
+        let source = passphrase(&dir,"hunter2");
+        let keystore = KeyStore::init(dir.join("keys.json"),&source).unwrap();
+        let exported = dir.join("exported.toml");
+        assert_eq!(KeyStore::export(dir.join("keys.json"),&exported,&source,false).unwrap(),keystore);
+        assert!(is_private(&exported));
+        assert_eq!(toml::load::<KeyStore,_>(&exported).unwrap(),keystore);
+        let imported = dir.join("imported.json");
+        assert_eq!(KeyStore::import(&exported,&imported,&source,false).unwrap(),keystore);
+        assert!(is_private(&imported));
+        assert_eq!(KeyStore::load(&imported,&source).unwrap(),keystore);
+        assert!(KeyStore::import(dir.join("missing.toml"),dir.join("missing.json"),&source,false).is_err());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn no_clobber() {
+        let dir = scratch("clobber");
+        let source = passphrase(&dir,"hunter2");
+        let (keys,exported) = (dir.join("keys.json"),dir.join("exported.toml"));
+        let keystore = KeyStore::init(&keys,&source).unwrap();
+        fs::write(&exported,"precious").unwrap();
+        // existing destinations are left untouched unless forced.
+        match KeyStore::export(&keys,&exported,&source,false) {
+            Err(KeyStoreError::Exists) => { },
+            other => panic!("unexpected result: {:?}",other),
+        }
+        assert_eq!(fs::read_to_string(&exported).unwrap(),"precious");
+        assert_eq!(KeyStore::export(&keys,&exported,&source,true).unwrap(),keystore);
+        // conversions in place are rejected, even if forced.
+        for &force in [false,true].iter() {
+            match KeyStore::export(&keys,dir.join(".").join("keys.json"),&source,force) {
+                Err(KeyStoreError::SameFile) => { },
+                other => panic!("unexpected result: {:?}",other),
+            }
+            match KeyStore::import(&exported,&exported,&source,force) {
+                Err(KeyStoreError::SameFile) => { },
+                other => panic!("unexpected result: {:?}",other),
+            }
+        }
+        assert_eq!(KeyStore::load(&keys,&source).unwrap(),keystore);
+        fs::remove_dir_all(&dir).unwrap();
+    }
 }
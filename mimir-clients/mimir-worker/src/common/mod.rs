@@ -1,8 +1,15 @@
 //! common worker utilities.
 //!
 mod arc_sealer;
+mod block_cell;
 mod keystore;
 
 pub use self::arc_sealer::ArcSealer;
-pub use self::keystore::KeyStore;
+pub use self::block_cell::BlockCell;
+pub use self::keystore::{
+    KeyStore,
+    KeyStoreError,
+    Passphrase,
+    PASSPHRASE_VAR,
+};
 
@@ -0,0 +1,119 @@
+use mimir_crypto::secp256k1::Error as KeyError;
+use mimir_crypto::{bip32,bip39};
+use mimir_transport::common::ParseError;
+use mimir_util::toml::TomlError;
+use serde_json::Error as JsonError;
+use common::KeyStoreError;
+use std::{fmt,error,io};
+
+
+#[derive(Debug)]
+pub enum KeygenError {
+    Io(io::Error),
+    Json(JsonError),
+    Toml(TomlError),
+    Key(KeyError),
+    KeyStore(KeyStoreError),
+    Mnemonic(bip39::Error),
+    Derivation(bip32::Error),
+    Parse(ParseError),
+    Usage(&'static str),
+}
+
+
+impl From<io::Error> for KeygenError {
+
+    fn from(err: io::Error) -> Self { KeygenError::Io(err) }
+}
+
+
+impl From<JsonError> for KeygenError {
+
+    fn from(err: JsonError) -> Self { KeygenError::Json(err) }
+}
+
+
+impl From<TomlError> for KeygenError {
+
+    fn from(err: TomlError) -> Self { KeygenError::Toml(err) }
+}
+
+
+impl From<KeyError> for KeygenError {
+
+    fn from(err: KeyError) -> Self { KeygenError::Key(err) }
+}
+
+
+impl From<KeyStoreError> for KeygenError {
+
+    fn from(err: KeyStoreError) -> Self { KeygenError::KeyStore(err) }
+}
+
+
+impl From<bip39::Error> for KeygenError {
+
+    fn from(err: bip39::Error) -> Self { KeygenError::Mnemonic(err) }
+}
+
+
+impl From<bip32::Error> for KeygenError {
+
+    fn from(err: bip32::Error) -> Self { KeygenError::Derivation(err) }
+}
+
+
+impl From<ParseError> for KeygenError {
+
+    fn from(err: ParseError) -> Self { KeygenError::Parse(err) }
+}
+
+
+impl fmt::Display for KeygenError {
+
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            KeygenError::Io(ref err) => err.fmt(f),
+            KeygenError::Json(ref err) => err.fmt(f),
+            KeygenError::Toml(ref err) => err.fmt(f),
+            KeygenError::Key(ref err) => err.fmt(f),
+            KeygenError::KeyStore(ref err) => err.fmt(f),
+            KeygenError::Mnemonic(ref err) => err.fmt(f),
+            KeygenError::Derivation(ref err) => err.fmt(f),
+            KeygenError::Parse(ref err) => err.fmt(f),
+            KeygenError::Usage(msg) => f.write_str(msg),
+        }
+    }
+}
+
+
+impl error::Error for KeygenError {
+
+    fn description(&self) -> &str {
+        match *self {
+            KeygenError::Io(ref err) => err.description(),
+            KeygenError::Json(ref err) => err.description(),
+            KeygenError::Toml(ref err) => err.description(),
+            KeygenError::Key(ref err) => err.description(),
+            KeygenError::KeyStore(ref err) => err.description(),
+            KeygenError::Mnemonic(ref err) => err.description(),
+            KeygenError::Derivation(ref err) => err.description(),
+            KeygenError::Parse(ref err) => err.description(),
+            KeygenError::Usage(msg) => msg,
+        }
+    }
+
+    fn cause(&self) -> Option<&error::Error> {
+        match *self {
+            KeygenError::Io(ref err) => Some(err),
+            KeygenError::Json(ref err) => Some(err),
+            KeygenError::Toml(ref err) => Some(err),
+            KeygenError::Key(ref err) => Some(err),
+            KeygenError::KeyStore(ref err) => Some(err),
+            KeygenError::Mnemonic(ref err) => Some(err),
+            KeygenError::Derivation(ref err) => Some(err),
+            KeygenError::Parse(ref err) => Some(err),
+            KeygenError::Usage(_) => None,
+        }
+    }
+}
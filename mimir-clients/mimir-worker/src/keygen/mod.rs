@@ -0,0 +1,338 @@
+//! key & signature utilities (see the `mimir-keygen` binary).
+//!
+mod options;
+mod error;
+
+pub use self::options::{
+    Options,
+    KeyArgs,
+    Cmd,
+};
+pub use self::error::KeygenError;
+
+use mimir_crypto::secp256k1::{Signer,Verifier,Public,Error};
+use mimir_crypto::bip32::DerivationPath;
+use mimir_crypto::bip39::Mnemonic;
+use mimir_crypto::Keccak256;
+use mimir_types::{Address,Secret,Signature,H256};
+use mimir_transport::common::{Command,Auth,Domain,Role,Scheme,CMD};
+use common::{KeyStore,Passphrase};
+use serde_json;
+use std::io::{self,Read,BufRead};
+use std::path::{Path,PathBuf};
+use std::fs::File;
+use std::env;
+use rand;
+
+
+/// environment variable from which mnemonic passphrases are read.
+pub const MNEMONIC_VAR: &str = "MIMIR_MNEMONIC_PASSPHRASE";
+
+
+/// output of a keygen subcommand.
+#[derive(Debug,Clone,PartialEq,Eq,Serialize)]
+#[serde(untagged)]
+pub enum Output {
+    Account(Account),
+    Key(Key),
+    Signed(Signed),
+    Verified(Verified),
+    Converted(Converted),
+}
+
+
+/// key material of a generated or derived account.
+#[derive(Debug,Clone,PartialEq,Eq,Serialize)]
+pub struct Account {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub mnemonic: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub path: Option<String>,
+    pub address: Address,
+    pub public: Public,
+    pub secret: Secret,
+}
+
+
+impl<'a> From<&'a Signer> for Account {
+
+    fn from(signer: &'a Signer) -> Self {
+        let (address,public,secret) = (signer.address(),signer.public(),signer.secret());
+        Account { mnemonic: None, path: None, address, public, secret }
+    }
+}
+
+
+/// public identity of a key.
+#[derive(Debug,Clone,PartialEq,Eq,Serialize)]
+pub struct Key {
+    pub address: Address,
+    pub public: Public,
+}
+
+
+impl From<Public> for Key {
+
+    fn from(public: Public) -> Self {
+        let address = Address::from(&public);
+        Key { address, public }
+    }
+}
+
+
+/// signature over a hash.
+#[derive(Debug,Clone,PartialEq,Eq,Serialize)]
+pub struct Signed {
+    pub address: Address,
+    pub hash: H256,
+    pub signature: Signature,
+}
+
+
+/// result of verifying a `Command` or `Auth`.
+///
+/// `signer` is the recovered address (if any), and `valid` indicates
+/// that the seal is present where required & that the signer matches
+/// the claimed address for self-identifying variants (under any of the
+/// accepted signature schemes, as indicated by `scheme`).  since any
+/// seal recovers *some* address, other signed variants are only valid
+/// if the signer matches the expected signer.
+///
+#[derive(Debug,Clone,PartialEq,Eq,Serialize)]
+pub struct Verified {
+    pub variant: String,
+    pub role: Role,
+    pub claim: Address,
+    pub signer: Option<Address>,
+    pub scheme: Option<Scheme>,
+    pub valid: bool,
+}
+
+
+/// result of a keystore conversion.
+#[derive(Debug,Clone,PartialEq,Eq,Serialize)]
+pub struct Converted {
+    pub address: Address,
+    pub path: PathBuf,
+}
+
+
+/// execute a keygen subcommand.
+pub fn run(cmd: &Cmd) -> Result<Output,KeygenError> {
+    match *cmd {
+        Cmd::Generate => {
+            let signer: Signer = rand::random();
+            Ok(Output::Account(Account::from(&signer)))
+        },
+        Cmd::Mnemonic { words, ref path } => {
+            let mnemonic = Mnemonic::generate(words)?;
+            let account = derive(&mnemonic,path)?;
+            Ok(Output::Account(Account { mnemonic: Some(mnemonic.to_string()), ..account }))
+        },
+        Cmd::Derive { ref path } => {
+            let mnemonic: Mnemonic = read_line()?.parse()?;
+            Ok(Output::Account(derive(&mnemonic,path)?))
+        },
+        Cmd::Address { ref key } => {
+            let signer = load_signer(key)?;
+            Ok(Output::Key(Key::from(signer.public())))
+        },
+        Cmd::Sign { hash, ref file, ref key } => {
+            let hash = match (hash,file.as_ref()) {
+                (Some(hash),None) => hash,
+                (None,Some(path)) => hash_file(path)?,
+                _ => return Err(KeygenError::Usage("expected exactly one of `--hash` or `--file`")),
+            };
+            let signer = load_signer(key)?;
+            Ok(Output::Signed(sign(&signer,hash)))
+        },
+        Cmd::Ecrecover { hash, ref signature } => {
+            let verifier = Verifier::default();
+            let public = verifier.recover(&hash.0,signature)?;
+            Ok(Output::Key(Key::from(public)))
+        },
+        Cmd::Verify { ref signing_domain, ref expect, ref message } => {
+            let domain = Domain::init(signing_domain)?;
+            let message = match *message {
+                Some(ref message) => message.to_owned(),
+                None => read_line()?,
+            };
+            Ok(Output::Verified(verify(&message,&domain,expect.as_ref())?))
+        },
+        Cmd::Import { ref src, ref dst, ref passphrase_file, force } => {
+            let passphrase = Passphrase::select(passphrase_file.as_ref());
+            let keystore = KeyStore::import(src,dst,&passphrase,force)?;
+            Ok(Output::Converted(Converted { address: keystore.address, path: dst.to_owned() }))
+        },
+        Cmd::Export { ref src, ref dst, ref passphrase_file, force } => {
+            let passphrase = Passphrase::select(passphrase_file.as_ref());
+            let keystore = KeyStore::export(src,dst,&passphrase,force)?;
+            Ok(Output::Converted(Converted { address: keystore.address, path: dst.to_owned() }))
+        },
+    }
+}
+
+
+/// derive an account from a mnemonic (passphrase read from `MNEMONIC_VAR`).
+pub fn derive(mnemonic: &Mnemonic, path: &DerivationPath) -> Result<Account,KeygenError> {
+    let passphrase = env::var(MNEMONIC_VAR).unwrap_or_default();
+    let signer = mnemonic.signer(&passphrase,path)?;
+    Ok(Account { path: Some(path.to_string()), ..Account::from(&signer) })
+}
+
+
+/// sign a hash.
+pub fn sign(signer: &Signer, hash: H256) -> Signed {
+    let signature = signer.sign(&hash.0);
+    Signed { address: signer.address(), hash, signature }
+}
+
+
+/// verify a serialized `Command`, or a json `Auth` (signed commands
+/// other than `IDENTIFY` must be signed by `expect`).
+pub fn verify(message: &str, domain: &Domain, expect: Option<&Address>) -> Result<Verified,KeygenError> {
+    let message = message.trim();
+    let verifier = Verifier::strict();
+    if message.starts_with('{') {
+        let auth: Auth = serde_json::from_str(message)?;
+        let scheme = auth.check_any(domain,&verifier).ok();
+        let signer = if scheme.is_some() { Some(auth.addr) } else { auth.recover(domain).ok() };
+        let valid = scheme.is_some();
+        Ok(Verified { variant: "AUTH".into(), role: auth.role, claim: auth.addr, signer, scheme, valid })
+    } else {
+        let cmd: Command = message.parse()?;
+        let (variant,role,claim) = (cmd.flag.as_ref().to_owned(),cmd.dest.role,cmd.dest.address);
+        let scheme = match cmd.flag {
+            CMD::IDENTIFY => cmd.check_identity(domain,&verifier).unwrap_or(None),
+            _ => None,
+        };
+        if scheme.is_some() {
+            return Ok(Verified { variant, role, claim, signer: Some(claim), scheme, valid: true });
+        }
+        let signer = cmd.recover(domain).unwrap_or(None);
+        let valid = match (cmd.flag,signer) {
+            (CMD::IDENTIFY,_) => false,
+            (_,Some(signer)) => expect == Some(&signer),
+            (flag,None) => cmd.seal.is_none() && !flag.signed_variant(),
+        };
+        let scheme = signer.map(|_| Scheme::Raw);
+        Ok(Verified { variant, role, claim, signer, scheme, valid })
+    }
+}
+
+
+/// get the `Keccak256` hash of a file.
+pub fn hash_file<P: AsRef<Path>>(path: P) -> Result<H256,io::Error> {
+    let mut file = File::open(path)?;
+    let mut hasher = Keccak256::default();
+    let mut buff = [0u8;4096];
+    loop {
+        match file.read(&mut buff)? {
+            0 => break,
+            len => hasher.absorb(&buff[..len]),
+        }
+    }
+    Ok(H256(hasher.finish()))
+}
+
+
+/// load signer from keystore, or from a secret read via stdin.
+fn load_signer(key: &KeyArgs) -> Result<Signer,KeygenError> {
+    let secret: Secret = if let Some(ref path) = key.keys {
+        let passphrase = Passphrase::select(key.passphrase_file.as_ref());
+        KeyStore::load(path,&passphrase)?.secret
+    } else {
+        read_line()?.parse().map_err(|_| Error::InvalidSecret)?
+    };
+    Ok(Signer::new(secret)?)
+}
+
+
+fn read_line() -> Result<String,io::Error> {
+    let stdin = io::stdin();
+    let mut line = String::new();
+    stdin.lock().read_line(&mut line)?;
+    Ok(line.trim().to_owned())
+}
+
+
+#[cfg(test)]
+mod tests {
+    use mimir_crypto::secp256k1::{dev,Signer};
+    use mimir_crypto::bip32::DerivationPath;
+    use mimir_crypto::bip39::Mnemonic;
+    use mimir_types::H256;
+    use mimir_transport::common::{Command,Auth,Domain,Identity,Role,Scheme};
+    use keygen::{sign,verify,derive};
+    use serde_json;
+
+
+    #[test]
+    fn derivation() {
+        let phrase = "abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon about";
+        let mnemonic: Mnemonic = phrase.parse().unwrap();
+        let account = derive(&mnemonic,&DerivationPath::ethereum(0)).unwrap();
+        assert_eq!(account.address,"0x9858effd232b4033e47d90003d41ec34ecaeda94".parse().unwrap());
+        assert_eq!(account.path,Some("m/44'/60'/0'/0/0".to_owned()));
+    }
+
+
+    #[test]
+    fn signing() {
+        let signer = Signer::new(dev::SECRET).unwrap();
+        let hash = H256([0x42;32]);
+        let signed = sign(&signer,hash);
+        assert_eq!(signed.address,dev::ADDRESS);
+        let output = serde_json::to_value(&signed).unwrap();
+        assert_eq!(output["address"],"0x00a329c0648769a73afac7f9381e08fb43dbea72");
+        assert_eq!(output["hash"],format!("0x{}","42".repeat(32)));
+    }
+
+
+    #[test]
+    fn verification() {
+        let signer = Signer::new(dev::SECRET).unwrap();
+        let domain = Domain::new(1u64.into(),"0x000000000000000000000000000000000000beef".parse().unwrap());
+
+        let identify = Command::identify(Role::Oracle,&domain,&signer).to_string();
+        let verified = verify(&identify,&domain,None).unwrap();
+        assert_eq!(verified.signer,Some(dev::ADDRESS));
+        assert!(verified.valid);
+        assert!(!verify(&identify,&Domain::legacy(),None).unwrap().valid);
+
+        let identify = Command::identify_with(Role::Requester,&domain,&signer,Scheme::Typed).to_string();
+        let verified = verify(&identify,&domain,None).unwrap();
+        assert_eq!((verified.signer,verified.scheme),(Some(dev::ADDRESS),Some(Scheme::Typed)));
+        assert!(verified.valid);
+
+        let other: Signer = ::rand::random();
+        let kick = Command::kick(Identity::new(other.address(),Role::Notary),&domain,&signer);
+        let verified = verify(&kick.to_string(),&domain,Some(&dev::ADDRESS)).unwrap();
+        assert_eq!((verified.signer,verified.claim),(Some(dev::ADDRESS),other.address()));
+        assert!(verified.valid);
+        // any seal recovers some signer, so one must be expected.
+        assert!(!verify(&kick.to_string(),&domain,None).unwrap().valid);
+        assert!(!verify(&kick.to_string(),&Domain::legacy(),Some(&dev::ADDRESS)).unwrap().valid);
+        let mut tampered = kick.clone();
+        if let Some(ref mut seal) = tampered.seal {
+            seal.0[0] ^= 0x01;
+        }
+        let verified = verify(&tampered.to_string(),&domain,Some(&dev::ADDRESS)).unwrap();
+        assert!(verified.signer != Some(dev::ADDRESS));
+        assert!(!verified.valid);
+
+        let debug = Command::debug(Identity::new(other.address(),Role::Notary),"hello".into()).to_string();
+        let verified = verify(&debug,&domain,None).unwrap();
+        assert_eq!(verified.signer,None);
+        assert!(verified.valid);
+
+        let auth = serde_json::to_string(&Auth::new_with(&signer,&domain,Role::Notary,Scheme::Personal)).unwrap();
+        let verified = verify(&auth,&domain,None).unwrap();
+        assert_eq!((verified.variant.as_ref(),verified.role),("AUTH",Role::Notary));
+        assert_eq!(verified.scheme,Some(Scheme::Personal));
+        assert!(verified.valid);
+        assert!(!verify(&auth,&Domain::legacy(),None).unwrap().valid);
+
+        assert!(verify("IDENTIFY nonsense",&domain,None).is_err());
+    }
+}
@@ -0,0 +1,164 @@
+use mimir_crypto::bip32::DerivationPath;
+use mimir_types::{Signature,Address,H256};
+use structopt::StructOpt;
+use std::path::PathBuf;
+
+
+/// command line args
+#[derive(Debug,Clone,StructOpt)]
+#[structopt(name = "mimir-keygen", about = "key & signature utilities for mimir-bridge workers (outputs json)")]
+pub struct Options {
+
+    /// subcommand to execute
+    #[structopt(subcommand)]
+    pub cmd: Cmd,
+}
+
+
+impl Options {
+
+    pub fn from_args() -> Self { <Self as StructOpt>::from_args() }
+}
+
+
+/// keygen subcommands
+#[derive(Debug,Clone,StructOpt)]
+pub enum Cmd {
+
+    /// generate a random key
+    #[structopt(name = "generate")]
+    Generate,
+
+    /// generate a mnemonic & derive an account from it (passphrase read from `MIMIR_MNEMONIC_PASSPHRASE`)
+    #[structopt(name = "mnemonic")]
+    Mnemonic {
+        /// number of words in mnemonic
+        #[structopt(long = "words", default_value = "24")]
+        words: usize,
+
+        /// derivation path (or account index)
+        #[structopt(long = "path", default_value = "m/44'/60'/0'/0/0", parse(try_from_str = "parse_path"))]
+        path: DerivationPath,
+    },
+
+    /// derive an account from a mnemonic read via stdin (passphrase read from `MIMIR_MNEMONIC_PASSPHRASE`)
+    #[structopt(name = "derive")]
+    Derive {
+        /// derivation path (or account index)
+        #[structopt(long = "path", default_value = "m/44'/60'/0'/0/0", parse(try_from_str = "parse_path"))]
+        path: DerivationPath,
+    },
+
+    /// get the address & public key of a key
+    #[structopt(name = "address")]
+    Address {
+        #[structopt(flatten)]
+        key: KeyArgs,
+    },
+
+    /// sign a 32-byte hash, or the `Keccak256` hash of a file
+    #[structopt(name = "sign")]
+    Sign {
+        /// hash to be signed
+        #[structopt(long = "hash")]
+        hash: Option<H256>,
+
+        /// file to be hashed & signed
+        #[structopt(long = "file", parse(from_os_str))]
+        file: Option<PathBuf>,
+
+        #[structopt(flatten)]
+        key: KeyArgs,
+    },
+
+    /// recover the signer of a hash
+    #[structopt(name = "ecrecover")]
+    Ecrecover {
+        /// hash which was signed
+        #[structopt(long = "hash")]
+        hash: H256,
+
+        /// signature of hash
+        #[structopt(long = "signature")]
+        signature: Signature,
+    },
+
+    /// verify a serialized `Command` or json `Auth` (read via stdin if not supplied)
+    #[structopt(name = "verify")]
+    Verify {
+        /// path to signing domain file (legacy domain used if file does not exist)
+        #[structopt(long = "signing-domain", default_value = "signing-domain.toml", parse(from_os_str))]
+        signing_domain: PathBuf,
+
+        /// expected signer of a signed command (required for signed variants other than `IDENTIFY`)
+        #[structopt(long = "expect")]
+        expect: Option<Address>,
+
+        /// command or auth to be verified
+        message: Option<String>,
+    },
+
+    /// convert an unencrypted toml keystore into an encrypted json keyfile
+    #[structopt(name = "import")]
+    Import {
+        /// path to toml keystore
+        #[structopt(parse(from_os_str))]
+        src: PathBuf,
+
+        /// path to json keyfile
+        #[structopt(parse(from_os_str))]
+        dst: PathBuf,
+
+        /// path to key file passphrase (else read from `MIMIR_KEYSTORE_PASSPHRASE` or prompt)
+        #[structopt(long = "passphrase-file", parse(from_os_str))]
+        passphrase_file: Option<PathBuf>,
+
+        /// overwrite `dst` if it already exists
+        #[structopt(long = "force")]
+        force: bool,
+    },
+
+    /// convert an encrypted json keyfile into an unencrypted toml keystore
+    #[structopt(name = "export")]
+    Export {
+        /// path to json keyfile
+        #[structopt(parse(from_os_str))]
+        src: PathBuf,
+
+        /// path to toml keystore
+        #[structopt(parse(from_os_str))]
+        dst: PathBuf,
+
+        /// path to key file passphrase (else read from `MIMIR_KEYSTORE_PASSPHRASE` or prompt)
+        #[structopt(long = "passphrase-file", parse(from_os_str))]
+        passphrase_file: Option<PathBuf>,
+
+        /// overwrite `dst` if it already exists
+        #[structopt(long = "force")]
+        force: bool,
+    },
+}
+
+
+/// source of the key used by a subcommand
+#[derive(Debug,Clone,StructOpt)]
+pub struct KeyArgs {
+
+    /// path to key file (else the secret key is read via stdin)
+    #[structopt(long = "key-store", parse(from_os_str))]
+    pub keys: Option<PathBuf>,
+
+    /// path to key file passphrase (else read from `MIMIR_KEYSTORE_PASSPHRASE` or prompt)
+    #[structopt(long = "passphrase-file", parse(from_os_str))]
+    pub passphrase_file: Option<PathBuf>,
+}
+
+
+/// parse a derivation path, or the index of an ethereum account.
+fn parse_path(s: &str) -> Result<DerivationPath,String> {
+    if let Ok(index) = s.parse() {
+        Ok(DerivationPath::ethereum(index))
+    } else {
+        s.parse().map_err(|err: ::mimir_crypto::bip32::Error| err.to_string())
+    }
+}
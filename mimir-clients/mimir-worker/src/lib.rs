@@ -17,6 +17,7 @@ extern crate serde_json;
 extern crate serde;
 extern crate web3;
 extern crate rand;
+extern crate rpassword;
 extern crate url_serde;
 extern crate url;
 #[macro_use]
@@ -27,3 +28,4 @@ pub mod common;
 pub mod oracle;
 pub mod notary;
 pub mod faucet;
+pub mod keygen;
@@ -8,16 +8,18 @@ mod types;
 pub use self::options::Options;
 pub use self::types::NotaryFuture;
 
-use mimir_proto::message::Message;
-use mimir_proto::visit::BlockState;
-use crossbeam::sync::ArcCell;
-use common::ArcSealer;
+use mimir_proto::message::{Message,CircuitSpec};
+use mimir_proto::visit::{BlockState,BlockRegistry};
+use mimir_proto::seal::Domain;
+use common::{ArcSealer,BlockCell};
 use std::sync::Arc;
 
 
 pub struct Notary {
     sealer: ArcSealer,
-    block: ArcCell<BlockState>,
+    spec: Arc<CircuitSpec>,
+    domain: Domain,
+    blocks: BlockCell,
 }
 
 
@@ -25,26 +27,43 @@ impl Notary {
 
     /// instantiate new notary
     pub fn new(sealer: ArcSealer) -> Self {
-        let block = ArcCell::new(Default::default());
-        Self { sealer, block }
+        let blocks = BlockCell::default();
+        let (spec,domain) = Default::default();
+        Self { sealer, spec, domain, blocks }
     }
 
+    /// configure with a non-default circuit spec
+    pub fn with_spec(mut self, spec: CircuitSpec) -> Self { self.spec = Arc::new(spec); self }
+
     /// get reference to inner sealer handle
     pub fn sealer(&self) -> &ArcSealer { &self.sealer }
 
+    /// get reference to circuit spec
+    pub fn spec(&self) -> &Arc<CircuitSpec> { &self.spec }
+
+    /// configure with a non-legacy signing domain
+    pub fn with_domain(mut self, domain: Domain) -> Self { self.domain = domain; self }
+
+    /// get reference to signing domain
+    pub fn domain(&self) -> &Domain { &self.domain }
+
     /// get handle to current block state
-    pub fn get_block(&self) -> Arc<BlockState> { self.block.get() }
+    pub fn get_block(&self) -> Arc<BlockState> {
+        self.blocks.latest()
+    }
+
+    /// get handle to registry of recent block states
+    pub fn get_registry(&self) -> Arc<BlockRegistry> { self.blocks.get() }
    
-    /// set current block state
+    /// set current block state (retaining recent states
+    /// for judgement of messages pinned to them).
     pub fn set_block<B>(&self, block: B) where B: Into<Arc<BlockState>> {
-        let block_state = block.into();
-        debug!("setting new block state {:?}",block_state);
-        let _ = self.block.set(block_state);
+        self.blocks.insert(block);
     }
 
     /// attempt to notarize specified message
     pub fn notarize(&self, message: Message) -> NotaryFuture {
-        NotaryFuture::new(self.sealer.clone(),message,self.get_block())
+        NotaryFuture::new(self.sealer.clone(),self.spec.clone(),self.domain,message,self.get_registry())
     }
 }
 
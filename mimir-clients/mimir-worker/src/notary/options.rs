@@ -18,8 +18,20 @@ pub struct Options {
     pub bridge_portal: Url,
 
     /// path to key file
-    #[structopt(long = "key-store", default_value = "notary-keys.toml", parse(from_os_str))]
+    #[structopt(long = "key-store", default_value = "notary-keys.json", parse(from_os_str))]
     pub keys: PathBuf,
+
+    /// path to key file passphrase (else read from `MIMIR_KEYSTORE_PASSPHRASE` or prompt)
+    #[structopt(long = "passphrase-file", parse(from_os_str))]
+    pub passphrase_file: Option<PathBuf>,
+
+    /// path to circuit spec file (default spec used if file does not exist)
+    #[structopt(long = "circuit-spec", default_value = "circuit-spec.toml", parse(from_os_str))]
+    pub circuit_spec: PathBuf,
+
+    /// path to signing domain file (legacy domain used if file does not exist)
+    #[structopt(long = "signing-domain", default_value = "signing-domain.toml", parse(from_os_str))]
+    pub signing_domain: PathBuf,
 }
 
 
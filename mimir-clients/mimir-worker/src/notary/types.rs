@@ -1,8 +1,10 @@
 use futures::{Future,Async,Poll};
-use mimir_proto::visit::{self,BlockState};
+use mimir_proto::visit::{self,BlockRegistry};
 use mimir_proto::judge::JudgeVisitor;
-use mimir_proto::seal::Sealer;
+use mimir_proto::seal::{self,Domain};
+use mimir_proto::message::cert::Cert;
 use mimir_proto::message::{
+    CircuitSpec,
     Message,
     STEP,
 };
@@ -12,16 +14,18 @@ use std::sync::Arc;
 
 pub struct NotaryFuture {
     sealer: Option<ArcSealer>,
+    spec: Arc<CircuitSpec>,
+    domain: Domain,
     message: Option<Message>,
-    block: Option<Arc<BlockState>>,
+    block: Option<Arc<BlockRegistry>>,
 }
 
 
 impl NotaryFuture {
 
-    pub fn new(sealer: ArcSealer, message: Message, block: Arc<BlockState>) -> Self {
+    pub fn new(sealer: ArcSealer, spec: Arc<CircuitSpec>, domain: Domain, message: Message, block: Arc<BlockRegistry>) -> Self {
         let (sealer,message,block) = (Some(sealer),Some(message),Some(block));
-        Self { sealer, message, block }
+        Self { sealer, spec, domain, message, block }
     }
 
 }
@@ -37,33 +41,49 @@ impl Future for NotaryFuture {
         let sealer = self.sealer.take().expect("no polling past completion");
         let mut message = self.message.take().expect("no polling past completion");
         let block = self.block.take().expect("no polling past complection");
-        let (next_step,visit_okay,accusations) = {
-            let mut visitor = JudgeVisitor::new(block);
-            let next_step = visit::apply(&mut visitor, &message);
+        // reject messages with leftover or unpaired certs outright.
+        if let Err(err) = visit::validate(&self.spec,&message) {
+            warn!("rejecting malformed message: {}",err);
+            return Err(err.as_str());
+        }
+        // judge & seal in a single pass; the judge accumulates
+        // the same message bytes which the notary cert commits to.
+        let (next_step,visit_okay,verdict) = {
+            // selection copies the shared registry on write, s.t. the
+            // state of the message's own block may be selected.
+            let mut visitor = JudgeVisitor::with_spec(block,&self.spec,self.domain);
+            let next_step = visit::apply(&self.spec, &mut visitor, &message);
             let visit_okay = visitor.is_ok();
-            let (_,accusations) = visitor.finish();
-            (next_step,visit_okay,accusations)
+            (next_step,visit_okay,visitor.finish())
         };
+        if !visit_okay {
+            for err in verdict.payload.iter() {
+                warn!("rejecting message payload: {}",err);
+            }
+            for step in verdict.failures() {
+                warn!("rejecting message: {}",step);
+            }
+            let accusations = verdict.accusations();
+            if !accusations.is_empty() {
+                warn!("unhandled accusations {:?}",accusations);
+            }
+        }
         match (next_step == STEP::NOTARY,visit_okay) {
             (true, true) => {
-                let cert = sealer.seal_notary(&message);
-                message.notary.push(cert);
+                let domain = self.domain.sealing(message.payload.version);
+                let cert = seal::raw::notary(&sealer,&domain,&verdict.bytes);
+                message.append_cert(&self.spec,Cert::Notary(cert))
+                    .map_err(|_| "invalid message step")?;
                 Ok(Async::Ready(message))
             },
             (true, false) => {
-                if accusations.len() > 0 {
-                    warn!("unhandled accusations {:?}",accusations);
-                }
                 Err("invalid message value")
             },
             (false, true) => {
                 Err("invalid message step")
             },
             (false, false) => {
-                if accusations.len() > 0 {
-                    warn!("unhandled accusations {:?}",accusations);
-                }
-                Err("literally everything about this message is is wrong")
+                Err("invalid message step and value")
             },
         }
     }
@@ -1,5 +1,7 @@
 use std::path::Path;
 use mimir_util::toml::{self,TomlError};
+use mimir_proto::message::CircuitSpec;
+use mimir_proto::seal::Domain;
 use mimir_types::Address;
 use url::Url;
 use url_serde;
@@ -24,6 +26,14 @@ pub struct Config {
     /// address of the auto-funding api portal
     #[serde(rename = "fund-portal", default = "defaults::fund_portal", with = "url_serde")]
     pub fund_portal: Url,
+
+    /// message circuit specification
+    #[serde(rename = "circuit-spec", default)]
+    pub circuit_spec: CircuitSpec,
+
+    /// signing domain of the bridge deployment
+    #[serde(rename = "signing-domain", default)]
+    pub signing_domain: Domain,
 }
 
 
@@ -53,6 +63,8 @@ impl Default for Config {
             websocket_rpc: defaults::websocket_rpc(),
             bridge_portal: defaults::bridge_portal(),
             fund_portal: defaults::fund_portal(),
+            circuit_spec: Default::default(),
+            signing_domain: Default::default(),
         }
     }
 }
@@ -1,3 +1,4 @@
+use mimir_proto::message::StepError;
 use serde_json::Error as JsonError;
 use web3::Error as Web3Error;
 use std::{fmt,error};
@@ -7,6 +8,7 @@ use std::{fmt,error};
 pub enum OracleError {
     Json(JsonError),
     Web3(Web3Error),
+    Step(StepError),
 }
 
 
@@ -22,12 +24,19 @@ impl From<Web3Error> for OracleError {
 }
 
 
+impl From<StepError> for OracleError {
+
+    fn from(err: StepError) -> Self { OracleError::Step(err) }
+}
+
+
 impl fmt::Display for OracleError {
 
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match *self {
             OracleError::Json(ref err) => err.fmt(f),
             OracleError::Web3(ref err) => err.fmt(f),
+            OracleError::Step(ref err) => err.fmt(f),
         }
     }
 }
@@ -39,6 +48,7 @@ impl error::Error for OracleError {
         match *self {
             OracleError::Json(ref err) => err.description(),
             OracleError::Web3(ref err) => err.description(),
+            OracleError::Step(ref err) => err.description(),
         }
     }
 
@@ -46,6 +56,7 @@ impl error::Error for OracleError {
         match *self {
             OracleError::Json(ref err) => Some(err),
             OracleError::Web3(ref err) => Some(err),
+            OracleError::Step(ref err) => Some(err),
         }
     }
 }
@@ -33,8 +33,12 @@ pub struct Options {
     pub config: PathBuf,
 
     /// path to key file
-    #[structopt(long = "key-store", default_value = "oracle-keys.toml", parse(from_os_str))]
+    #[structopt(long = "key-store", default_value = "oracle-keys.json", parse(from_os_str))]
     pub keys: PathBuf,
+
+    /// path to key file passphrase (else read from `MIMIR_KEYSTORE_PASSPHRASE` or prompt)
+    #[structopt(long = "passphrase-file", parse(from_os_str))]
+    pub passphrase_file: Option<PathBuf>,
 }
 
 
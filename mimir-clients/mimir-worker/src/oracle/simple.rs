@@ -1,12 +1,11 @@
-use mimir_transport::common::{Auth,Role};
+use mimir_transport::common::{Auth,Role,Domain};
 use mimir_node::transact::TransactFuture;
-use mimir_proto::visit::{BlockVisitor,BlockState};
-use mimir_proto::message::Request;
+use mimir_proto::visit::{BlockVisitor,BlockState,BlockRegistry};
+use mimir_proto::message::{Request,CircuitSpec};
 use mimir_node::node::SimpleNode;
 use mimir_node::rpc::SimpleQuery;
 use mimir_node::abi::workerset;
 use mimir_types::Address;
-use crossbeam::sync::ArcCell;
 use web3::types::CallRequest;
 use web3::{self,Transport};
 use futures::Future;
@@ -15,7 +14,7 @@ use std::sync::Arc;
 
 use oracle::util::MessageBuilder;
 use oracle::types::SimpleOracleFuture;
-use common::ArcSealer;
+use common::{ArcSealer,BlockCell};
 
 
 pub type SimpleRequest = Request<SimpleQuery>;
@@ -24,7 +23,9 @@ pub type SimpleRequest = Request<SimpleQuery>;
 #[derive(Debug)]
 pub struct SimpleOracle<T> {
     sealer: ArcSealer,
-    block: ArcCell<BlockState>,
+    spec: Arc<CircuitSpec>,
+    domain: Domain,
+    blocks: BlockCell,
     node: SimpleNode<T>,
 }
 
@@ -32,10 +33,23 @@ impl<T> SimpleOracle<T> {
 
     /// instantiate new oracle client
     pub fn new(sealer: ArcSealer, node: SimpleNode<T>) -> Self {
-        let block = ArcCell::new(Default::default());
-        SimpleOracle { sealer, block, node }
+        let blocks = BlockCell::default();
+        let (spec,domain) = Default::default();
+        SimpleOracle { sealer, spec, domain, blocks, node }
     }
 
+    /// configure with a non-default circuit spec
+    pub fn with_spec(mut self, spec: CircuitSpec) -> Self { self.spec = Arc::new(spec); self }
+
+    /// get reference to circuit spec
+    pub fn spec(&self) -> &Arc<CircuitSpec> { &self.spec }
+
+    /// configure with a non-legacy signing domain
+    pub fn with_domain(mut self, domain: Domain) -> Self { self.domain = domain; self }
+
+    /// get reference to signing domain
+    pub fn domain(&self) -> &Domain { &self.domain }
+
     /// get reference to inner node handle
     pub fn node(&self) -> &SimpleNode<T> { &self.node }
 
@@ -43,13 +57,16 @@ impl<T> SimpleOracle<T> {
     pub fn sealer(&self) -> &ArcSealer { &self.sealer }
 
     /// get handle to current block state
-    pub fn get_block(&self) -> Arc<BlockState> { self.block.get() }
+    pub fn get_block(&self) -> Arc<BlockState> {
+        self.blocks.latest()
+    }
+
+    /// get handle to registry of recent block states
+    pub fn get_registry(&self) -> Arc<BlockRegistry> { self.blocks.get() }
    
-    /// set current block state
+    /// set current block state (retaining recent states).
     pub fn set_block<B>(&self, block: B) where B: Into<Arc<BlockState>> {
-        let block_state = block.into();
-        debug!("setting new block state {:?}",block_state);
-        let _ = self.block.set(block_state);
+        self.blocks.insert(block);
     }
 
     /// generate auth cert.
@@ -58,7 +75,7 @@ impl<T> SimpleOracle<T> {
         // of separate `Oracle` and `Verifier` roles.  The call below
         // will need to be changed as soon as the admin server & solidity
         // assets are updated to reflect this.
-        Auth::new(&self.sealer,Role::Oracle)
+        Auth::new(&self.sealer,&self.domain,Role::Oracle)
     }
 }
 
@@ -82,9 +99,9 @@ impl<T: Transport> SimpleOracle<T> {
                 MessageBuilder::new(address,blind)
             }
         };
-        let sealer = self.sealer.clone();
+        let (sealer,spec) = (self.sealer.clone(),self.spec.clone());
         let work = self.node.execute_query(query); 
-        SimpleOracleFuture::new(builder,sealer,work)
+        SimpleOracleFuture::new(builder,sealer,spec,self.domain,work)
     }
 
 
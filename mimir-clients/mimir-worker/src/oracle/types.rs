@@ -1,11 +1,13 @@
 use mimir_node::node::SimpleRpcFuture;
-use mimir_proto::message::Message;
-use mimir_proto::seal::Sealer;
+use mimir_proto::message::{Message,CircuitSpec};
+use mimir_proto::message::cert::Cert;
+use mimir_proto::seal::{Sealer,Domain};
 use futures::{Future,Async,Poll};
 use oracle::util::MessageBuilder;
 use oracle::OracleError;
 use common::ArcSealer;
 use serde_json::Value;
+use std::sync::Arc;
 use web3::Error as Web3Error;
 
 
@@ -14,15 +16,17 @@ use web3::Error as Web3Error;
 pub struct SimpleOracleFuture<T> {
     builder: Option<MessageBuilder>,
     sealer: ArcSealer,
+    spec: Arc<CircuitSpec>,
+    domain: Domain,
     inner: SimpleRpcFuture<T>,
 }
 
 
 impl<T> SimpleOracleFuture<T> {
 
-    pub fn new(builder: MessageBuilder, sealer: ArcSealer, inner: SimpleRpcFuture<T>) -> Self {
+    pub fn new(builder: MessageBuilder, sealer: ArcSealer, spec: Arc<CircuitSpec>, domain: Domain, inner: SimpleRpcFuture<T>) -> Self {
         let builder = Some(builder);
-        SimpleOracleFuture { builder, sealer, inner }
+        SimpleOracleFuture { builder, sealer, spec, domain, inner }
     }
 }
 
@@ -37,9 +41,9 @@ impl<T> Future for SimpleOracleFuture<T> where T: Future<Item=Value,Error=Web3Er
         let record = try_ready!(self.inner.poll());
         let builder = self.builder.take()
             .expect("no polling past completion");
-        let mut message = builder.finish(record)?;
-        let cert = self.sealer.seal_oracle(&message);
-        message.verify.push(cert);
+        let mut message = builder.finish(&self.spec,&self.domain,record)?;
+        let cert = self.sealer.seal_oracle(&self.spec,&self.domain,&message);
+        message.append_cert(&self.spec,Cert::Oracle(cert))?;
         Ok(Async::Ready(message))
     }
 }
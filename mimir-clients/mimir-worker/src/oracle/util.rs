@@ -1,9 +1,12 @@
 use mimir_node::rpc::SimpleRecord;
-use mimir_proto::message::{Message,Payload};
+use mimir_proto::message::{Message,Payload,CircuitSpec};
+use mimir_proto::message::cert::Cert;
 use mimir_proto::visit::BlockState;
+use mimir_proto::seal::Domain;
 use mimir_types::{U256,H256,Address};
 use oracle::simple::SimpleRequest;
-use serde_json::{self,Error};
+use oracle::OracleError;
+use serde_json;
 use std::sync::Arc;
 
 
@@ -60,14 +63,14 @@ impl MessageBuilder {
 
     pub fn hash(mut self, hash: H256) -> Self { self.hash = Some(hash); self }
 
-    pub fn finish(self, record: SimpleRecord) -> Result<Message,Error> {
+    pub fn finish(self, spec: &CircuitSpec, domain: &Domain, record: SimpleRecord) -> Result<Message,OracleError> {
         let record = serde_json::to_string(&record)?;
         let MessageBuilder { address, blind, number, hash } = self;
         let number = number.unwrap_or_else(Default::default);
         let hash = hash.unwrap_or_else(Default::default);
-        let payload = Payload { record, address, number, hash };
+        let payload = Payload { record: record.into(), address, number, hash, version: domain.version };
         let mut message = Message::new(payload);
-        message.blind.push(blind);
+        message.append_cert(spec,Cert::Blind(blind))?;
         Ok(message)
     }
 }